@@ -0,0 +1,666 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::db;
+use crate::serial;
+use crate::telemetry::{DataPacket, FixStatus};
+
+const RULES_FILE: &str = "alert_rules.json";
+const LOG_FILE: &str = "alerts.jsonl";
+
+// Flight event detection thresholds, in feet
+const LAUNCH_RISE_FT: f64 = 150.0;
+const APOGEE_DROP_FT: f64 = 100.0;
+const LANDED_BAND_FT: f64 = 30.0;
+const LANDED_STILL_MS: i64 = 15_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlightEvent {
+    Launch,
+    Apogee,
+    Landing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AlertCondition {
+    #[serde(rename_all = "camelCase")]
+    NoPacket { seconds: u64 },
+    FixLost,
+    // The clear_* thresholds give each level rule its hysteresis band
+    #[serde(rename_all = "camelCase")]
+    LowSatellites { below: u8, clear_at: u8 },
+    #[serde(rename_all = "camelCase")]
+    LowRssi { below_dbm: i16, clear_at_dbm: i16 },
+    #[serde(rename_all = "camelCase")]
+    AltitudeAbove { above_ft: i32, clear_below_ft: i32 },
+    #[serde(rename_all = "camelCase")]
+    Flight { event: FlightEvent },
+    // Enter/exit transitions reported by the geofence module
+    Geofence,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    pub condition: AlertCondition,
+    pub enabled: bool,
+    pub severity: Severity,
+    pub notify: bool,
+    // How long a condition has to hold before it is raised
+    #[serde(default)]
+    pub hold_s: u64,
+    #[serde(default)]
+    pub disabled_nodes: BTreeSet<String>,
+}
+
+impl AlertRule {
+    fn applies_to(&self, node: &str) -> bool {
+        self.enabled && !self.disabled_nodes.contains(node)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertState {
+    Raised,
+    Cleared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertEvent {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub node: String,
+    pub severity: Severity,
+    pub state: AlertState,
+    pub message: String,
+    pub timestamp_ms: i64,
+    #[serde(skip)]
+    notify: bool,
+}
+
+pub fn default_rules() -> Vec<AlertRule> {
+    let rule = |id: &str, name: &str, condition, severity, notify| AlertRule {
+        id: id.into(),
+        name: name.into(),
+        condition,
+        enabled: true,
+        severity,
+        notify,
+        hold_s: 0,
+        disabled_nodes: BTreeSet::new(),
+    };
+    vec![
+        rule("no-packet", "No packets", AlertCondition::NoPacket { seconds: 30 }, Severity::Critical, true),
+        rule("fix-lost", "GPS fix lost", AlertCondition::FixLost, Severity::Warning, true),
+        AlertRule {
+            hold_s: 5,
+            ..rule("low-sats", "Low satellite count", AlertCondition::LowSatellites { below: 4, clear_at: 5 }, Severity::Warning, false)
+        },
+        AlertRule {
+            hold_s: 5,
+            ..rule("low-rssi", "Weak signal", AlertCondition::LowRssi { below_dbm: -120, clear_at_dbm: -115 }, Severity::Warning, false)
+        },
+        AlertRule {
+            enabled: false,
+            ..rule("altitude", "Altitude ceiling", AlertCondition::AltitudeAbove { above_ft: 10_000, clear_below_ft: 9_500 }, Severity::Critical, true)
+        },
+        rule("launch", "Launch detected", AlertCondition::Flight { event: FlightEvent::Launch }, Severity::Info, true),
+        rule("apogee", "Apogee detected", AlertCondition::Flight { event: FlightEvent::Apogee }, Severity::Info, true),
+        rule("landing", "Landing detected", AlertCondition::Flight { event: FlightEvent::Landing }, Severity::Info, true),
+        rule("geofence", "Geofence", AlertCondition::Geofence, Severity::Critical, true),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum FlightPhase {
+    #[default]
+    Pad,
+    Ascent,
+    Descent,
+    Landed,
+}
+
+#[derive(Debug, Default)]
+struct FlightDetector {
+    phase: FlightPhase,
+    ground_ft: Option<f64>,
+    peak_ft: f64,
+    still_since: Option<(i64, f64)>,
+}
+
+impl FlightDetector {
+    // Returns the event and the altitude it refers to (the peak, for apogee)
+    fn update(&mut self, alt: f64, ts: i64) -> Option<(FlightEvent, f64)> {
+        match self.phase {
+            FlightPhase::Pad => {
+                let ground = *self.ground_ft.get_or_insert(alt);
+                if alt < ground {
+                    self.ground_ft = Some(alt);
+                } else if alt > ground + LAUNCH_RISE_FT {
+                    self.phase = FlightPhase::Ascent;
+                    self.peak_ft = alt;
+                    return Some((FlightEvent::Launch, alt));
+                }
+            }
+            FlightPhase::Ascent => {
+                if alt > self.peak_ft {
+                    self.peak_ft = alt;
+                } else if alt < self.peak_ft - APOGEE_DROP_FT {
+                    self.phase = FlightPhase::Descent;
+                    return Some((FlightEvent::Apogee, self.peak_ft));
+                }
+            }
+            FlightPhase::Descent => match self.still_since {
+                Some((since, ref_alt)) if (alt - ref_alt).abs() <= LANDED_BAND_FT => {
+                    if ts - since >= LANDED_STILL_MS {
+                        self.phase = FlightPhase::Landed;
+                        return Some((FlightEvent::Landing, alt));
+                    }
+                }
+                _ => self.still_since = Some((ts, alt)),
+            },
+            FlightPhase::Landed => {}
+        }
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    active: bool,
+    pending_since: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+struct NodeState {
+    last_packet_ms: i64,
+    had_fix: bool,
+    has_fix: Option<bool>,
+    sats: Option<u8>,
+    rssi: Option<i16>,
+    altitude_ft: Option<f64>,
+    flight: FlightDetector,
+    rules: BTreeMap<String, RuleState>,
+    // Fence id -> what the node is doing wrong there, as reported by the geofence module
+    fence_violations: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    nodes: BTreeMap<String, NodeState>,
+    // When a port was last opened; silence before then, or while nothing is open, isn't a lost node
+    listening_since: Option<i64>,
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        AlertEngine { rules: default_rules(), nodes: BTreeMap::new(), listening_since: None }
+    }
+}
+
+fn event(rule: &AlertRule, node: &str, state: AlertState, message: String, timestamp_ms: i64) -> AlertEvent {
+    AlertEvent {
+        rule_id: rule.id.clone(),
+        rule_name: rule.name.clone(),
+        node: node.to_string(),
+        severity: rule.severity,
+        state,
+        message,
+        timestamp_ms,
+        notify: rule.notify && state == AlertState::Raised,
+    }
+}
+
+// Some(true) when the condition is violated, None when there is nothing to judge it on yet.
+fn violated(condition: &AlertCondition, node: &NodeState, active: bool, listening_since: Option<i64>, now_ms: i64) -> Option<(bool, String)> {
+    match condition {
+        AlertCondition::NoPacket { seconds } => listening_since.map(|since| {
+            let silent_s = (now_ms - node.last_packet_ms.max(since)) / 1000;
+            (silent_s >= *seconds as i64, format!("no packet for {silent_s} s"))
+        }),
+        AlertCondition::FixLost => node.has_fix.map(|fix| (node.had_fix && !fix, "GPS fix lost".to_string())),
+        AlertCondition::LowSatellites { below, clear_at } => node.sats.map(|sats| {
+            let limit = if active { *clear_at } else { *below };
+            (sats < limit, format!("{sats} satellites (below {below})"))
+        }),
+        AlertCondition::LowRssi { below_dbm, clear_at_dbm } => node.rssi.map(|rssi| {
+            let limit = if active { *clear_at_dbm } else { *below_dbm };
+            (rssi < limit, format!("RSSI {rssi} dBm (below {below_dbm} dBm)"))
+        }),
+        AlertCondition::AltitudeAbove { above_ft, clear_below_ft } => node.altitude_ft.map(|alt| {
+            let limit = if active { *clear_below_ft } else { *above_ft };
+            (alt > limit as f64, format!("altitude {alt:.0} ft (above {above_ft} ft)"))
+        }),
+        AlertCondition::Geofence => {
+            let violations: Vec<&str> = node.fence_violations.values().map(String::as_str).collect();
+            Some((!violations.is_empty(), violations.join(", ")))
+        }
+        // Edge-triggered in ingest()
+        AlertCondition::Flight { .. } => None,
+    }
+}
+
+impl AlertEngine {
+    fn evaluate(&mut self, node_key: &str, now_ms: i64, events: &mut Vec<AlertEvent>) {
+        let Some(node) = self.nodes.get_mut(node_key) else {
+            return;
+        };
+        for rule in &self.rules {
+            if !rule.applies_to(node_key) {
+                // Disabling a rule for a node quietly drops whatever it had raised
+                node.rules.remove(&rule.id);
+                continue;
+            }
+            let active = node.rules.get(&rule.id).is_some_and(|state| state.active);
+            let Some((bad, message)) = violated(&rule.condition, node, active, self.listening_since, now_ms) else {
+                continue;
+            };
+            let state = node.rules.entry(rule.id.clone()).or_default();
+            if bad {
+                if state.active {
+                    continue;
+                }
+                let since = *state.pending_since.get_or_insert(now_ms);
+                if now_ms - since >= rule.hold_s as i64 * 1000 {
+                    state.active = true;
+                    state.pending_since = None;
+                    events.push(event(rule, node_key, AlertState::Raised, message, now_ms));
+                }
+            } else {
+                state.pending_since = None;
+                if state.active {
+                    state.active = false;
+                    events.push(event(rule, node_key, AlertState::Cleared, format!("{} cleared", rule.name), now_ms));
+                }
+            }
+        }
+    }
+
+    pub fn ingest(&mut self, pkt: &DataPacket) -> Vec<AlertEvent> {
+        let key = pkt.node_key();
+        let now_ms = pkt.timestamp_ms;
+        self.listening_since.get_or_insert(now_ms);
+        let node = self.nodes.entry(key.clone()).or_default();
+        node.last_packet_ms = now_ms;
+        match pkt.fix_status {
+            FixStatus::Fix | FixStatus::Diff | FixStatus::Est => {
+                node.has_fix = Some(true);
+                node.had_fix = true;
+            }
+            FixStatus::NoFix => node.has_fix = Some(false),
+            FixStatus::Unknown => {}
+        }
+        if pkt.satellites_count.is_some() {
+            node.sats = pkt.satellites_count;
+        }
+        if pkt.receiver_rssi.is_some() {
+            node.rssi = pkt.receiver_rssi;
+        }
+
+        // Filtered altitude keeps a single garbled sample from faking a launch or apogee
+        let altitude = match &pkt.filtered {
+            Some(filtered) if filtered.rejected.is_some() => None,
+            Some(filtered) => filtered.altitude_ft,
+            None => pkt.altitude_ft.map(f64::from),
+        };
+
+        let mut events = Vec::new();
+        if let Some(alt) = altitude {
+            node.altitude_ft = Some(alt);
+            if let Some((flight_event, at_ft)) = node.flight.update(alt, now_ms) {
+                for rule in &self.rules {
+                    if rule.condition == (AlertCondition::Flight { event: flight_event }) && rule.applies_to(&key) {
+                        let message = match flight_event {
+                            FlightEvent::Launch => format!("launch detected at {at_ft:.0} ft"),
+                            FlightEvent::Apogee => format!("apogee at {at_ft:.0} ft"),
+                            FlightEvent::Landing => format!("landed at {at_ft:.0} ft"),
+                        };
+                        events.push(event(rule, &key, AlertState::Raised, message, now_ms));
+                    }
+                }
+            }
+        }
+
+        self.evaluate(&key, now_ms, &mut events);
+        events
+    }
+
+    // The geofence module has already applied its own crossing hysteresis; the rule's hold applies on top.
+    pub fn geofence_transition(&mut self, node: &str, fence_id: &str, violation: bool, message: String, now_ms: i64) -> Vec<AlertEvent> {
+        let state = self.nodes.entry(node.to_string()).or_default();
+        if violation {
+            state.fence_violations.insert(fence_id.to_string(), message);
+        } else {
+            state.fence_violations.remove(fence_id);
+        }
+        let mut events = Vec::new();
+        self.evaluate(node, now_ms, &mut events);
+        events
+    }
+
+    pub fn forget_geofence(&mut self, fence_id: &str, now_ms: i64) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        let keys: Vec<String> = self.nodes.iter().filter(|(_, node)| node.fence_violations.contains_key(fence_id)).map(|(key, _)| key.clone()).collect();
+        for key in keys {
+            if let Some(node) = self.nodes.get_mut(&key) {
+                node.fence_violations.remove(fence_id);
+            }
+            self.evaluate(&key, now_ms, &mut events);
+        }
+        events
+    }
+
+    // Time-based conditions (stale nodes, hold periods) need evaluating even without new packets.
+    // With no port open the operator has disconnected on purpose, so stale-node alerts are cleared
+    // and suspended.
+    pub fn tick(&mut self, now_ms: i64, listening: bool) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        if !listening {
+            self.listening_since = None;
+            let stale_rules = self.rules.iter().filter(|rule| matches!(rule.condition, AlertCondition::NoPacket { .. }));
+            for rule in stale_rules {
+                for (key, node) in self.nodes.iter_mut() {
+                    if node.rules.remove(&rule.id).is_some_and(|state| state.active) {
+                        let message = format!("{} cleared: no receiver connected", rule.name);
+                        events.push(event(rule, key, AlertState::Cleared, message, now_ms));
+                    }
+                }
+            }
+        } else {
+            self.listening_since.get_or_insert(now_ms);
+        }
+        let keys: Vec<String> = self.nodes.keys().cloned().collect();
+        for key in keys {
+            self.evaluate(&key, now_ms, &mut events);
+        }
+        events
+    }
+}
+
+static ENGINE: OnceLock<Mutex<AlertEngine>> = OnceLock::new();
+
+fn get_engine() -> &'static Mutex<AlertEngine> {
+    ENGINE.get_or_init(|| Mutex::new(AlertEngine::default()))
+}
+
+fn rules_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| format!("No config directory: {e}"))?;
+    Ok(dir.join(RULES_FILE))
+}
+
+fn log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("No data directory: {e}"))?;
+    Ok(dir.join(LOG_FILE))
+}
+
+fn save_rules(app: &AppHandle, rules: &[AlertRule]) -> Result<(), String> {
+    let path = rules_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(rules).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save alert rules: {e}"))
+}
+
+// Restores rules saved by a previous session, if any.
+pub fn load_rules(app: &AppHandle) {
+    let Ok(path) = rules_path(app) else {
+        return;
+    };
+    let Ok(text) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<Vec<AlertRule>>(&text) {
+        Ok(rules) => {
+            if let Ok(mut engine) = get_engine().lock() {
+                engine.rules = rules;
+            }
+        }
+        Err(e) => eprintln!("Ignoring unreadable {}: {e}", path.display()),
+    }
+}
+
+fn append_log(app: &AppHandle, events: &[AlertEvent]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = log_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for evt in events {
+        writeln!(file, "{}", serde_json::to_string(evt)?)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+fn dispatch(app: &AppHandle, events: Vec<AlertEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    if let Err(e) = append_log(app, &events) {
+        eprintln!("Failed to write alert log: {e}");
+    }
+    for evt in events {
+        db::record_event("alert", Some(&evt.node), &evt.message, evt.timestamp_ms, &evt);
+        if evt.notify {
+            let _ = app
+                .notification()
+                .builder()
+                .title(format!("{}: {}", evt.node, evt.rule_name))
+                .body(&evt.message)
+                .show();
+        }
+        let _ = app.emit("alert", evt);
+    }
+}
+
+pub fn process_packet(app: &AppHandle, pkt: &DataPacket) {
+    let events = match get_engine().lock() {
+        Ok(mut engine) => engine.ingest(pkt),
+        Err(_) => return,
+    };
+    dispatch(app, events);
+}
+
+pub fn geofence_transition(app: &AppHandle, node: &str, fence_id: &str, violation: bool, message: String, now_ms: i64) {
+    let events = match get_engine().lock() {
+        Ok(mut engine) => engine.geofence_transition(node, fence_id, violation, message, now_ms),
+        Err(_) => return,
+    };
+    dispatch(app, events);
+}
+
+// A deleted fence can't be violated any more
+pub fn forget_geofence(app: &AppHandle, fence_id: &str, now_ms: i64) {
+    let events = match get_engine().lock() {
+        Ok(mut engine) => engine.forget_geofence(fence_id, now_ms),
+        Err(_) => return,
+    };
+    dispatch(app, events);
+}
+
+pub fn tick(app: &AppHandle, now_ms: i64) {
+    let events = match get_engine().lock() {
+        Ok(mut engine) => engine.tick(now_ms, serial::open_port_count() > 0),
+        Err(_) => return,
+    };
+    dispatch(app, events);
+}
+
+#[tauri::command]
+pub fn get_alert_rules() -> Result<Vec<AlertRule>, String> {
+    let engine = get_engine().lock().map_err(|e| format!("alert lock error: {}", e))?;
+    Ok(engine.rules.clone())
+}
+
+#[tauri::command]
+pub fn set_alert_rules(app: AppHandle, rules: Vec<AlertRule>) -> Result<(), String> {
+    save_rules(&app, &rules)?;
+    let mut engine = get_engine().lock().map_err(|e| format!("alert lock error: {}", e))?;
+    engine.rules = rules;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_alert_rule_node_enabled(app: AppHandle, rule_id: String, node: String, enabled: bool) -> Result<(), String> {
+    let mut engine = get_engine().lock().map_err(|e| format!("alert lock error: {}", e))?;
+    let rule = engine
+        .rules
+        .iter_mut()
+        .find(|rule| rule.id == rule_id)
+        .ok_or_else(|| format!("Unknown alert rule: {rule_id}"))?;
+    if enabled {
+        rule.disabled_nodes.remove(&node);
+    } else {
+        rule.disabled_nodes.insert(node);
+    }
+    save_rules(&app, &engine.rules)
+}
+
+#[tauri::command]
+pub fn get_active_alerts() -> Result<Vec<(String, String)>, String> {
+    let engine = get_engine().lock().map_err(|e| format!("alert lock error: {}", e))?;
+    Ok(engine
+        .nodes
+        .iter()
+        .flat_map(|(node, state)| {
+            state
+                .rules
+                .iter()
+                .filter(|(_, rule)| rule.active)
+                .map(move |(rule_id, _)| (node.clone(), rule_id.clone()))
+        })
+        .collect())
+}
+
+// Forget per-node state (flight phase, raised alerts) before a new flight.
+#[tauri::command]
+pub fn reset_alerts() -> Result<(), String> {
+    let mut engine = get_engine().lock().map_err(|e| format!("alert lock error: {}", e))?;
+    engine.nodes.clear();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_alert_log(app: AppHandle, limit: Option<usize>) -> Result<Vec<AlertEvent>, String> {
+    let path = log_path(&app)?;
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut events: Vec<AlertEvent> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    if let Some(limit) = limit {
+        let skip = events.len().saturating_sub(limit);
+        events.drain(..skip);
+    }
+    Ok(events)
+}
+
+#[tauri::command]
+pub fn clear_alert_log(app: AppHandle) -> Result<(), String> {
+    let path = log_path(&app)?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to clear alert log: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::fixtures::packet;
+
+    fn ids(events: &[AlertEvent]) -> Vec<(&str, AlertState)> {
+        events.iter().map(|e| (e.rule_id.as_str(), e.state)).collect()
+    }
+
+    #[test]
+    fn rssi_rule_holds_and_has_hysteresis() {
+        let mut engine = AlertEngine::default();
+        let heard = |ts, rssi| DataPacket { receiver_rssi: Some(rssi), ..packet(1, ts, 43.0, -77.5, 500) };
+        assert!(engine.ingest(&heard(0, -125)).is_empty());
+        // Needs 5 s below the threshold before raising
+        assert_eq!(ids(&engine.ingest(&heard(5_000, -125))), vec![("low-rssi", AlertState::Raised)]);
+        // Back above -120 but not yet above the -115 clear level
+        assert!(engine.ingest(&heard(6_000, -118)).is_empty());
+        assert_eq!(ids(&engine.ingest(&heard(7_000, -110))), vec![("low-rssi", AlertState::Cleared)]);
+    }
+
+    #[test]
+    fn stale_rule_fires_from_tick_and_respects_node_disable() {
+        let mut engine = AlertEngine::default();
+        engine.ingest(&packet(1, 0, 43.0, -77.5, 500));
+        assert_eq!(ids(&engine.tick(31_000, true)), vec![("no-packet", AlertState::Raised)]);
+        assert_eq!(ids(&engine.ingest(&packet(1, 32_000, 43.0, -77.5, 500))), vec![("no-packet", AlertState::Cleared)]);
+
+        engine.rules[0].disabled_nodes.insert("Node 1".into());
+        assert!(engine.tick(90_000, true).is_empty());
+    }
+
+    #[test]
+    fn geofence_violations_are_tracked_per_fence() {
+        let mut engine = AlertEngine::default();
+        engine.ingest(&packet(1, 0, 43.0, -77.5, 500));
+        assert_eq!(ids(&engine.geofence_transition("Node 1", "waiver", true, "outside Waiver".into(), 0)), vec![("geofence", AlertState::Raised)]);
+        assert!(engine.geofence_transition("Node 1", "road", true, "entered Road".into(), 1_000).is_empty());
+        assert!(engine.geofence_transition("Node 1", "waiver", false, "entered Waiver".into(), 2_000).is_empty());
+        let active = engine.nodes["Node 1"].rules.get("geofence").is_some_and(|rule| rule.active);
+        assert!(active);
+        assert_eq!(ids(&engine.forget_geofence("road", 3_000)), vec![("geofence", AlertState::Cleared)]);
+    }
+
+    #[test]
+    fn disconnecting_suspends_stale_rule() {
+        let mut engine = AlertEngine::default();
+        engine.ingest(&packet(1, 0, 43.0, -77.5, 500));
+        assert!(engine.tick(10_000, false).is_empty());
+        assert!(engine.tick(60_000, false).is_empty());
+        // Silence only counts from when a port is open again
+        assert!(engine.tick(70_000, true).is_empty());
+        assert!(engine.tick(99_000, true).is_empty());
+        assert_eq!(ids(&engine.tick(100_000, true)), vec![("no-packet", AlertState::Raised)]);
+        // Disconnecting closes the raised alert rather than leaving it open
+        assert_eq!(ids(&engine.tick(101_000, false)), vec![("no-packet", AlertState::Cleared)]);
+        assert!(engine.tick(102_000, false).is_empty());
+    }
+
+    #[test]
+    fn detects_launch_apogee_and_landing() {
+        let mut engine = AlertEngine::default();
+        let mut seen = Vec::new();
+        let profile = [600, 600, 2_000, 5_000, 5_400, 5_200, 3_000, 1_000, 610, 605, 600, 600];
+        for (i, alt) in profile.iter().enumerate() {
+            for evt in engine.ingest(&packet(1, i as i64 * 5_000, 43.0, -77.5, *alt)) {
+                seen.push(evt.rule_id);
+            }
+        }
+        assert_eq!(seen, vec!["launch", "apogee", "landing"]);
+    }
+}
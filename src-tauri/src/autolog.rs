@@ -0,0 +1,431 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::export::{CsvColumn, ExportRecord};
+use crate::telemetry::DataPacket;
+
+const CONFIG_FILE: &str = "autolog.json";
+const MANIFEST_FILE: &str = "manifest.json";
+
+// Everything except the raw lines, which have their own transcript file
+const LOG_COLUMNS: [CsvColumn; 16] = [
+    CsvColumn::Time,
+    CsvColumn::Node,
+    CsvColumn::NodeId,
+    CsvColumn::Callsign,
+    CsvColumn::Latitude,
+    CsvColumn::Longitude,
+    CsvColumn::AltitudeFt,
+    CsvColumn::Rssi,
+    CsvColumn::Snr,
+    CsvColumn::Fix,
+    CsvColumn::Satellites,
+    CsvColumn::FilteredLatitude,
+    CsvColumn::FilteredLongitude,
+    CsvColumn::Rejected,
+    CsvColumn::DeviceUptimeMs,
+    CsvColumn::Receivers,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutologConfig {
+    pub enabled: bool,
+    // Parent of the per-session directories; defaults to <app data>/sessions
+    pub directory: Option<String>,
+    pub csv: bool,
+    pub jsonl: bool,
+    // Every serial line exactly as received, for re-parsing later
+    pub raw: bool,
+    // A new set of files is started when either limit is reached; 0 disables that limit
+    pub rotate_mb: u64,
+    pub rotate_minutes: u64,
+}
+
+impl Default for AutologConfig {
+    fn default() -> Self {
+        AutologConfig {
+            enabled: true,
+            directory: None,
+            csv: true,
+            jsonl: true,
+            raw: true,
+            rotate_mb: 50,
+            rotate_minutes: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestPort {
+    pub port: String,
+    pub baud_rate: u32,
+    pub opened_ms: i64,
+    pub closed_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionManifest {
+    pub app_version: String,
+    pub started_at: String,
+    pub started_ms: i64,
+    pub ended_ms: Option<i64>,
+    pub ports: Vec<ManifestPort>,
+    pub files: Vec<String>,
+    pub packet_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutologStatus {
+    pub active: bool,
+    pub directory: Option<String>,
+    pub part: u32,
+    pub packet_count: u64,
+    pub last_error: Option<String>,
+}
+
+struct ActiveLog {
+    dir: PathBuf,
+    manifest: SessionManifest,
+    part: u32,
+    part_started_ms: i64,
+    part_bytes: u64,
+    csv: Option<File>,
+    jsonl: Option<File>,
+    raw: Option<File>,
+}
+
+#[derive(Default)]
+struct AutologState {
+    config: AutologConfig,
+    active: Option<ActiveLog>,
+    last_error: Option<String>,
+}
+
+static STATE: OnceLock<Mutex<AutologState>> = OnceLock::new();
+
+fn get_state() -> &'static Mutex<AutologState> {
+    STATE.get_or_init(|| Mutex::new(AutologState::default()))
+}
+
+fn csv_line(fields: impl IntoIterator<Item = String>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut writer = csv::WriterBuilder::new().from_writer(Vec::new());
+    writer.write_record(fields)?;
+    Ok(writer.into_inner().map_err(|e| e.to_string())?)
+}
+
+// Flushed and synced on every write so a dead laptop loses at most the packet in flight.
+fn append(file: &mut File, bytes: &[u8]) -> std::io::Result<()> {
+    file.write_all(bytes)?;
+    file.flush()?;
+    file.sync_data()
+}
+
+fn create(dir: &Path, name: &str, files: &mut Vec<String>) -> std::io::Result<File> {
+    files.push(name.to_string());
+    OpenOptions::new().create(true).append(true).open(dir.join(name))
+}
+
+impl ActiveLog {
+    fn open_part(&mut self, config: &AutologConfig, now_ms: i64) -> Result<(), Box<dyn std::error::Error>> {
+        self.part += 1;
+        self.part_started_ms = now_ms;
+        self.part_bytes = 0;
+        let files = &mut self.manifest.files;
+        self.csv = None;
+        self.jsonl = None;
+        self.raw = None;
+        if config.csv {
+            let mut file = create(&self.dir, &format!("packets-{:03}.csv", self.part), files)?;
+            append(&mut file, &csv_line(LOG_COLUMNS.iter().map(|c| c.header().to_string()))?)?;
+            self.csv = Some(file);
+        }
+        if config.jsonl {
+            self.jsonl = Some(create(&self.dir, &format!("packets-{:03}.jsonl", self.part), files)?);
+        }
+        if config.raw {
+            self.raw = Some(create(&self.dir, &format!("raw-{:03}.log", self.part), files)?);
+        }
+        self.write_manifest()
+    }
+
+    fn rotate_if_due(&mut self, config: &AutologConfig, now_ms: i64) -> Result<(), Box<dyn std::error::Error>> {
+        let too_big = config.rotate_mb > 0 && self.part_bytes >= config.rotate_mb * 1024 * 1024;
+        let too_old = config.rotate_minutes > 0 && now_ms - self.part_started_ms >= config.rotate_minutes as i64 * 60_000;
+        if too_big || too_old {
+            self.open_part(config, now_ms)?;
+        }
+        Ok(())
+    }
+
+    // Written to a temporary file first so a crash never leaves a half-written manifest.
+    fn write_manifest(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = self.dir.join(format!("{MANIFEST_FILE}.tmp"));
+        fs::write(&tmp, serde_json::to_string_pretty(&self.manifest)?)?;
+        fs::rename(tmp, self.dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    fn write_packet(&mut self, config: &AutologConfig, pkt: &DataPacket) -> Result<(), Box<dyn std::error::Error>> {
+        self.rotate_if_due(config, pkt.timestamp_ms)?;
+        if let Some(file) = self.csv.as_mut() {
+            let record = ExportRecord::from(pkt);
+            let line = csv_line(LOG_COLUMNS.iter().map(|c| c.value(&record)))?;
+            append(file, &line)?;
+            self.part_bytes += line.len() as u64;
+        }
+        if let Some(file) = self.jsonl.as_mut() {
+            let mut line = serde_json::to_vec(pkt)?;
+            line.push(b'\n');
+            append(file, &line)?;
+            self.part_bytes += line.len() as u64;
+        }
+        self.manifest.packet_count += 1;
+        Ok(())
+    }
+
+    fn write_line(&mut self, config: &AutologConfig, port: &str, line: &str, now_ms: i64) -> Result<(), Box<dyn std::error::Error>> {
+        self.rotate_if_due(config, now_ms)?;
+        if let Some(file) = self.raw.as_mut() {
+            let stamp = DateTime::from_timestamp_millis(now_ms).unwrap_or_default();
+            let entry = format!("{}\t{}\t{}\n", stamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true), port, line);
+            append(file, entry.as_bytes())?;
+            self.part_bytes += entry.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+fn start_session(app: &AppHandle, config: &AutologConfig, now_ms: i64) -> Result<ActiveLog, Box<dyn std::error::Error>> {
+    let parent = match &config.directory {
+        Some(dir) => PathBuf::from(dir),
+        None => app.path().app_data_dir()?.join("sessions"),
+    };
+    let started = DateTime::from_timestamp_millis(now_ms).unwrap_or_default();
+    fs::create_dir_all(&parent)?;
+    // Never reuse a directory: its files are appended to, so a reconnect would write into the old session
+    let stem = format!("session-{}", started.format("%Y%m%dT%H%M%S%3f"));
+    let mut dir = parent.join(&stem);
+    let mut suffix = 1;
+    loop {
+        match fs::create_dir(&dir) {
+            Ok(()) => break,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                suffix += 1;
+                dir = parent.join(format!("{stem}-{suffix}"));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let mut log = ActiveLog {
+        dir,
+        manifest: SessionManifest {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: started.to_rfc3339(),
+            started_ms: now_ms,
+            ended_ms: None,
+            ports: Vec::new(),
+            files: Vec::new(),
+            packet_count: 0,
+        },
+        part: 0,
+        part_started_ms: now_ms,
+        part_bytes: 0,
+        csv: None,
+        jsonl: None,
+        raw: None,
+    };
+    log.open_part(config, now_ms)?;
+    Ok(log)
+}
+
+fn report(state: &mut AutologState, result: Result<(), Box<dyn std::error::Error>>) {
+    if let Err(e) = result {
+        eprintln!("Auto-log error: {e}");
+        state.last_error = Some(e.to_string());
+    }
+}
+
+// A session starts with the first open port and ends when the last one closes.
+pub fn port_opened(app: &AppHandle, port: &str, baud_rate: u32) {
+    let Ok(mut state) = get_state().lock() else {
+        return;
+    };
+    if !state.config.enabled {
+        return;
+    }
+    let now_ms = Utc::now().timestamp_millis();
+    if state.active.is_none() {
+        match start_session(app, &state.config, now_ms) {
+            Ok(log) => state.active = Some(log),
+            Err(e) => return report(&mut state, Err(e)),
+        }
+    }
+    let result = state.active.as_mut().map_or(Ok(()), |log| {
+        log.manifest.ports.push(ManifestPort { port: port.to_string(), baud_rate, opened_ms: now_ms, closed_ms: None });
+        log.write_manifest()
+    });
+    report(&mut state, result);
+}
+
+pub fn port_closed(port: &str) {
+    let Ok(mut state) = get_state().lock() else {
+        return;
+    };
+    let Some(log) = state.active.as_mut() else {
+        return;
+    };
+    let now_ms = Utc::now().timestamp_millis();
+    for entry in log.manifest.ports.iter_mut().filter(|p| p.port == port && p.closed_ms.is_none()) {
+        entry.closed_ms = Some(now_ms);
+    }
+    let finished = log.manifest.ports.iter().all(|p| p.closed_ms.is_some());
+    if finished {
+        log.manifest.ended_ms = Some(now_ms);
+    }
+    let result = log.write_manifest();
+    if finished {
+        state.active = None;
+    }
+    report(&mut state, result);
+}
+
+pub fn record_packet(pkt: &DataPacket) {
+    let Ok(mut state) = get_state().lock() else {
+        return;
+    };
+    let state = &mut *state;
+    if let Some(log) = state.active.as_mut() {
+        let result = log.write_packet(&state.config, pkt);
+        report(state, result);
+    }
+}
+
+pub fn record_line(port: &str, line: &str) {
+    let Ok(mut state) = get_state().lock() else {
+        return;
+    };
+    let state = &mut *state;
+    if let Some(log) = state.active.as_mut() {
+        let result = log.write_line(&state.config, port, line, Utc::now().timestamp_millis());
+        report(state, result);
+    }
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| format!("No config directory: {e}"))?;
+    Ok(dir.join(CONFIG_FILE))
+}
+
+pub fn load_config(app: &AppHandle) {
+    let Ok(path) = config_path(app) else {
+        return;
+    };
+    let Ok(text) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<AutologConfig>(&text) {
+        Ok(config) => {
+            if let Ok(mut state) = get_state().lock() {
+                state.config = config;
+            }
+        }
+        Err(e) => eprintln!("Ignoring unreadable {}: {e}", path.display()),
+    }
+}
+
+#[tauri::command]
+pub fn get_autolog_config() -> Result<AutologConfig, String> {
+    let state = get_state().lock().map_err(|e| format!("autolog lock error: {}", e))?;
+    Ok(state.config.clone())
+}
+
+// Takes effect from the next session; a session already being logged keeps its files.
+#[tauri::command]
+pub fn set_autolog_config(app: AppHandle, config: AutologConfig) -> Result<(), String> {
+    let path = config_path(&app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save auto-log settings: {e}"))?;
+
+    let mut state = get_state().lock().map_err(|e| format!("autolog lock error: {}", e))?;
+    state.config = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_autolog_status() -> Result<AutologStatus, String> {
+    let state = get_state().lock().map_err(|e| format!("autolog lock error: {}", e))?;
+    Ok(AutologStatus {
+        active: state.active.is_some(),
+        directory: state.active.as_ref().map(|log| log.dir.to_string_lossy().into_owned()),
+        part: state.active.as_ref().map_or(0, |log| log.part),
+        packet_count: state.active.as_ref().map_or(0, |log| log.manifest.packet_count),
+        last_error: state.last_error.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_log(dir: PathBuf, config: &AutologConfig) -> ActiveLog {
+        fs::create_dir_all(&dir).unwrap();
+        let mut log = ActiveLog {
+            dir,
+            manifest: SessionManifest {
+                app_version: "test".into(),
+                started_at: String::new(),
+                started_ms: 0,
+                ended_ms: None,
+                ports: Vec::new(),
+                files: Vec::new(),
+                packet_count: 0,
+            },
+            part: 0,
+            part_started_ms: 0,
+            part_bytes: 0,
+            csv: None,
+            jsonl: None,
+            raw: None,
+        };
+        log.open_part(config, 0).unwrap();
+        log
+    }
+
+    #[test]
+    fn writes_and_rotates_by_time() {
+        let dir = std::env::temp_dir().join(format!("dispatch-autolog-{}", std::process::id()));
+        let config = AutologConfig { rotate_minutes: 1, ..Default::default() };
+        let mut log = test_log(dir.clone(), &config);
+
+        let pkt = |ts: i64| DataPacket { node_id: Some(1), latitude: Some(43.0), timestamp_ms: ts, ..Default::default() };
+        log.write_packet(&config, &pkt(1_000)).unwrap();
+        log.write_line(&config, "COM3", "Node 1", 1_000).unwrap();
+        log.write_packet(&config, &pkt(61_000)).unwrap();
+
+        assert_eq!(log.part, 2);
+        let first = fs::read_to_string(dir.join("packets-001.csv")).unwrap();
+        assert_eq!(first.lines().count(), 2);
+        assert!(first.starts_with("Time,Node,Node ID"));
+        let second = fs::read_to_string(dir.join("packets-002.jsonl")).unwrap();
+        assert_eq!(serde_json::from_str::<DataPacket>(second.trim()).unwrap().timestamp_ms, 61_000);
+        assert!(fs::read_to_string(dir.join("raw-001.log")).unwrap().ends_with("\tCOM3\tNode 1\n"));
+
+        let manifest: SessionManifest = serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest.files.len(), 6);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::profiles::{self, ApplyReport, Profile};
+use super::{get, identity, probe, schema, target_port, ConfigError, DeviceType};
+use crate::serial::blocking;
+
+const BACKUPS_DIR: &str = "config-backups";
+pub const BACKUP_FORMAT: &str = "dispatch-config-backup";
+pub const BACKUP_VERSION: u32 = 1;
+
+// Everything a device reported about its settings at one moment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backup {
+    pub format: String,
+    pub version: u32,
+    pub created_at: String,
+    pub port: String,
+    pub identity: Option<String>,
+    pub device_type: DeviceType,
+    pub firmware: Option<String>,
+    pub values: BTreeMap<String, String>,
+    // Keys the device listed but wouldn't report a value for
+    #[serde(default)]
+    pub unreadable: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedBackup {
+    pub path: String,
+    pub backup: Backup,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffStatus {
+    Same,
+    Changed,
+    // In the backup but not offered by this device
+    MissingOnDevice,
+    // Offered by the device but not in the backup; left alone on restore
+    DeviceOnly,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffEntry {
+    pub key: String,
+    pub backup: Option<String>,
+    pub device: Option<String>,
+    pub status: DiffStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDiff {
+    pub backup: Backup,
+    pub device: Backup,
+    pub entries: Vec<DiffEntry>,
+}
+
+// Reads every setting the device offers.
+pub fn snapshot(port: &str, timeout_ms: Option<u64>) -> Result<Backup, ConfigError> {
+    let device = probe(port, timeout_ms)?;
+    let mut values = BTreeMap::new();
+    let mut unreadable = Vec::new();
+    for key in &device.keys {
+        match get(port, key, timeout_ms) {
+            Ok(reply) => {
+                values.insert(key.clone(), reply.value.unwrap_or_default());
+            }
+            Err(e) if e.is_fatal() => return Err(e),
+            Err(_) => unreadable.push(key.clone()),
+        }
+    }
+    Ok(Backup {
+        format: BACKUP_FORMAT.into(),
+        version: BACKUP_VERSION,
+        created_at: Utc::now().to_rfc3339(),
+        port: port.to_string(),
+        identity: device.identity,
+        device_type: device.device_type,
+        firmware: identity::describe(port, timeout_ms)?.firmware,
+        values,
+        unreadable,
+    })
+}
+
+pub fn diff(backup: &Backup, device: &Backup) -> Vec<DiffEntry> {
+    let device_keys = device.values.keys().chain(&device.unreadable);
+    let mut keys: Vec<&String> = backup.values.keys().chain(device_keys).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .map(|key| {
+            let saved = backup.values.get(key);
+            let current = device.values.get(key);
+            let offered = current.is_some() || device.unreadable.contains(key);
+            let status = match (saved, current) {
+                (Some(_), _) if !offered => DiffStatus::MissingOnDevice,
+                (None, _) => DiffStatus::DeviceOnly,
+                (Some(saved), Some(current)) if schema::same_value(key, saved, current) => DiffStatus::Same,
+                _ => DiffStatus::Changed,
+            };
+            DiffEntry { key: key.clone(), backup: saved.cloned(), device: current.cloned(), status }
+        })
+        .collect()
+}
+
+fn backups_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("No data directory: {e}"))?;
+    Ok(dir.join(BACKUPS_DIR))
+}
+
+fn read_backup(path: &str) -> Result<Backup, ConfigError> {
+    let invalid = |message: String| ConfigError::Invalid { key: "backup".into(), message };
+    let text = fs::read_to_string(path).map_err(|e| invalid(format!("Failed to read {path}: {e}")))?;
+    let backup: Backup = serde_json::from_str(&text).map_err(|e| invalid(format!("{path} is not a config backup: {e}")))?;
+    if backup.format != BACKUP_FORMAT || backup.version > BACKUP_VERSION {
+        return Err(invalid(format!("{path} is not a config backup this version of Dispatch can read")));
+    }
+    Ok(backup)
+}
+
+// Saves every setting of the device to a timestamped file in the app's data directory, or to
+// `path` when given.
+#[tauri::command]
+pub async fn backup_device(
+    app: AppHandle,
+    port_name: Option<String>,
+    path: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<SavedBackup, ConfigError> {
+    blocking(move || {
+        let port = target_port(port_name)?;
+        let backup = snapshot(&port, timeout_ms)?;
+        let write_error = |message: String| ConfigError::Write { message };
+        let target = match path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let dir = backups_dir(&app).map_err(write_error)?;
+                fs::create_dir_all(&dir).map_err(|e| write_error(format!("Failed to create backups directory: {e}")))?;
+                let device = backup.identity.clone().unwrap_or_else(|| backup.device_type.label().to_string());
+                let node = backup.values.get("node_id").map(|id| format!("-{id}")).unwrap_or_default();
+                dir.join(format!("{device}{node}-{}.json", Utc::now().format("%Y%m%dT%H%M%SZ")))
+            }
+        };
+        let json = serde_json::to_string_pretty(&backup).map_err(|e| write_error(e.to_string()))?;
+        fs::write(&target, json).map_err(|e| write_error(format!("Failed to save backup: {e}")))?;
+        Ok(SavedBackup { path: target.to_string_lossy().into_owned(), backup })
+    })
+    .await
+}
+
+// Backups in the app's data directory, newest first.
+#[tauri::command]
+pub fn list_backups(app: AppHandle) -> Result<Vec<SavedBackup>, String> {
+    let Ok(entries) = fs::read_dir(backups_dir(&app)?) else {
+        return Ok(Vec::new());
+    };
+    let mut backups: Vec<SavedBackup> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path().to_string_lossy().into_owned())
+        .filter(|path| path.ends_with(".json"))
+        .filter_map(|path| read_backup(&path).ok().map(|backup| SavedBackup { path, backup }))
+        .collect();
+    backups.sort_by(|a, b| b.backup.created_at.cmp(&a.backup.created_at));
+    Ok(backups)
+}
+
+// Compares a backup with what the connected device holds now, for review before restoring.
+#[tauri::command]
+pub async fn diff_backup(path: String, port_name: Option<String>, timeout_ms: Option<u64>) -> Result<BackupDiff, ConfigError> {
+    blocking(move || {
+        let backup = read_backup(&path)?;
+        let device = snapshot(&target_port(port_name)?, timeout_ms)?;
+        let entries = diff(&backup, &device);
+        Ok(BackupDiff { backup, device, entries })
+    })
+    .await
+}
+
+// Writes a backup to the same or a replacement device. Only settings that differ are written,
+// optionally limited to `keys`, and each is verified like a profile.
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    path: String,
+    port_name: Option<String>,
+    keys: Option<Vec<String>>,
+    reboot: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<ApplyReport, ConfigError> {
+    blocking(move || {
+        let backup = read_backup(&path)?;
+        let port = target_port(port_name)?;
+        let device = snapshot(&port, timeout_ms)?;
+        let values: BTreeMap<String, String> = diff(&backup, &device)
+            .into_iter()
+            .filter(|e| e.status == DiffStatus::Changed)
+            .filter(|e| keys.as_ref().is_none_or(|keys| keys.contains(&e.key)))
+            .filter_map(|e| Some((e.key, e.backup?)))
+            .collect();
+        let profile = Profile {
+            name: format!("Backup of {}", backup.created_at),
+            description: None,
+            device_type: Some(backup.device_type),
+            values,
+        };
+        if profile.values.is_empty() {
+            return Ok(ApplyReport { profile: profile.name, port, reopened_port: None, rebooted: false, ok: true, results: Vec::new() });
+        }
+        profiles::apply(&app, &port, &profile, reboot.unwrap_or(false), timeout_ms)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backup_diff_classifies_keys() {
+        let backup = |values: &[(&str, &str)], unreadable: &[&str]| Backup {
+            format: BACKUP_FORMAT.into(),
+            version: BACKUP_VERSION,
+            created_at: String::new(),
+            port: "COM3".into(),
+            identity: None,
+            device_type: DeviceType::Tracker,
+            firmware: None,
+            values: values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            unreadable: unreadable.iter().map(|k| k.to_string()).collect(),
+        };
+        let saved = backup(&[("freq", "433.92"), ("node_id", "4"), ("callsign", "KD2YIE"), ("hunter_gain", "12")], &[]);
+        let device = backup(&[("freq", "433.920000"), ("node_id", "7"), ("tx_power", "20")], &["callsign"]);
+        let statuses: Vec<(String, DiffStatus)> = diff(&saved, &device).into_iter().map(|e| (e.key, e.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("callsign".to_string(), DiffStatus::Changed),
+                ("freq".to_string(), DiffStatus::Same),
+                ("hunter_gain".to_string(), DiffStatus::MissingOnDevice),
+                ("node_id".to_string(), DiffStatus::Changed),
+                ("tx_power".to_string(), DiffStatus::DeviceOnly),
+            ]
+        );
+    }
+}
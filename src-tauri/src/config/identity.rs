@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+use super::schema::{self, ConfigSchema};
+use super::{check_reply, exchange, parse_probe, target_port, ConfigError, DeviceType};
+use crate::deputy_interpreter::ParserDialect;
+use crate::serial::blocking;
+
+lazy_static! {
+    // "firmware: v1.2.0", "Build hash = 1a2b3c4", "hw_rev: B"
+    static ref RE_FIELD: Regex = Regex::new(r"(?i)^([a-z][a-z _-]*?)\s*[:=]\s*(\S.*)$").unwrap();
+    // Git describe suffix on a version string: "v1.2.0-14-g1a2b3c4d"
+    static ref RE_GIT_HASH: Regex = Regex::new(r"-g([0-9a-f]{7,40})\b").unwrap();
+    static ref RE_NOT_FOUND: Regex = Regex::new(r"(?i)command not found").unwrap();
+}
+
+// What a device says about itself. Decides how its output is parsed and which settings it gets.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDescriptor {
+    pub port: String,
+    pub identity: Option<String>,
+    pub device_type: DeviceType,
+    pub firmware: Option<String>,
+    pub build_hash: Option<String>,
+    pub hardware_revision: Option<String>,
+    // From the firmware, or failing that the USB descriptor
+    pub serial_number: Option<String>,
+    pub dialect: ParserDialect,
+    pub schema: ConfigSchema,
+    pub identified_at: String,
+}
+
+static DESCRIPTORS: OnceLock<Mutex<HashMap<String, DeviceDescriptor>>> = OnceLock::new();
+
+fn get_descriptors() -> &'static Mutex<HashMap<String, DeviceDescriptor>> {
+    DESCRIPTORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn cached(port: &str) -> Option<DeviceDescriptor> {
+    get_descriptors().lock().ok()?.get(port).cloned()
+}
+
+// Called when the port closes; whatever is plugged in next may be a different device.
+pub fn forget(port: &str) {
+    if let Ok(mut descriptors) = get_descriptors().lock() {
+        descriptors.remove(port);
+    }
+}
+
+// Ports nobody has identified yet are read as telemetry, as they always were.
+pub fn dialect(port: &str) -> ParserDialect {
+    get_descriptors().lock().ok().and_then(|d| d.get(port).map(|d| d.dialect)).unwrap_or_default()
+}
+
+fn dialect_for(device_type: DeviceType) -> ParserDialect {
+    match device_type {
+        DeviceType::Tracker => ParserDialect::Deputy,
+        DeviceType::Marshal => ParserDialect::Console,
+    }
+}
+
+// The reply to a query the firmware may not have. Older builds answer "command not found" or
+// nothing at all, which just means one less thing known about the device.
+fn query(port: &str, command: &str, timeout_ms: Option<u64>) -> Result<Option<Vec<String>>, ConfigError> {
+    match exchange(port, command, timeout_ms) {
+        Ok(reply) if reply.iter().any(|line| RE_NOT_FOUND.is_match(line)) => Ok(None),
+        Ok(reply) => Ok(check_reply(command, None, &reply).ok().map(|_| reply)),
+        Err(e) if e.is_fatal() => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Reported {
+    identity: Option<String>,
+    firmware: Option<String>,
+    build_hash: Option<String>,
+    hardware_revision: Option<String>,
+    serial_number: Option<String>,
+}
+
+// Reads the `identity` reply: "identity marshal" on its own, or "label: value" lines.
+fn parse_identity(reply: &[String]) -> Reported {
+    let mut reported = Reported::default();
+    for line in reply {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("identity ").filter(|name| !name.contains(':')) {
+            reported.identity = Some(name.trim().to_string());
+            continue;
+        }
+        let Some(cap) = RE_FIELD.captures(line) else { continue };
+        let label = cap[1].to_lowercase().replace(['-', '_'], " ");
+        let value = Some(cap[2].trim().to_string());
+        match label.as_str() {
+            "identity" | "device" | "name" => reported.identity = value,
+            "firmware" | "fw" | "version" | "fw version" | "firmware version" => reported.firmware = value,
+            "build" | "build hash" | "commit" | "git" => reported.build_hash = value,
+            "hardware" | "hw" | "hw rev" | "hardware revision" | "revision" | "board revision" => reported.hardware_revision = value,
+            "serial" | "serial number" | "sn" | "uid" | "id" | "device id" => reported.serial_number = value,
+            _ => {}
+        }
+    }
+    if reported.build_hash.is_none() {
+        reported.build_hash = reported.firmware.as_deref().and_then(git_hash);
+    }
+    reported
+}
+
+fn git_hash(version: &str) -> Option<String> {
+    RE_GIT_HASH.captures(version).map(|c| c[1].to_string())
+}
+
+// Serial number from the USB descriptor of the port, for firmware that can't report its own
+fn usb_serial_number(port: &str) -> Option<String> {
+    serialport::available_ports().ok()?.into_iter().find(|p| p.port_name == port).and_then(|p| match p.port_type {
+        serialport::SerialPortType::UsbPort(usb) => usb.serial_number,
+        _ => None,
+    })
+}
+
+// Asks the device who it is, falling back to the Zephyr shell's `version` and `hwinfo devid` and
+// to the settings it lists for whatever `identity` doesn't cover.
+pub fn identify(port: &str, timeout_ms: Option<u64>) -> Result<DeviceDescriptor, ConfigError> {
+    let mut reported = query(port, "identity", timeout_ms)?.map(|reply| parse_identity(&reply)).unwrap_or_default();
+    if reported.firmware.is_none() {
+        let version = query(port, "version", timeout_ms)?.unwrap_or_default();
+        reported.firmware = version.into_iter().find(|line| schema::parse_version(line).is_some());
+        reported.build_hash = reported.build_hash.or_else(|| reported.firmware.as_deref().and_then(git_hash));
+    }
+    if reported.serial_number.is_none() {
+        let devid = query(port, "hwinfo devid", timeout_ms)?.map(|reply| parse_identity(&reply)).unwrap_or_default();
+        reported.serial_number = devid.serial_number.or_else(|| usb_serial_number(port));
+    }
+
+    let named = reported.identity.as_deref().map(str::to_lowercase);
+    let device_type = match named.as_deref() {
+        Some(name) if name.contains("marshal") => DeviceType::Marshal,
+        Some(name) if name.contains("tracker") || name.contains("deputy") => DeviceType::Tracker,
+        // Firmware without an identity still gives itself away by the settings it offers
+        _ => parse_probe(port, exchange(port, "config", timeout_ms)?).device_type,
+    };
+
+    let descriptor = DeviceDescriptor {
+        port: port.to_string(),
+        identity: reported.identity,
+        device_type,
+        dialect: dialect_for(device_type),
+        schema: schema::schema_for(device_type, reported.firmware.as_deref()),
+        firmware: reported.firmware,
+        build_hash: reported.build_hash,
+        hardware_revision: reported.hardware_revision,
+        serial_number: reported.serial_number,
+        identified_at: Utc::now().to_rfc3339(),
+    };
+    if let Ok(mut descriptors) = get_descriptors().lock() {
+        descriptors.insert(port.to_string(), descriptor.clone());
+    }
+    Ok(descriptor)
+}
+
+// The cached descriptor, identifying the device first if it hasn't been.
+pub fn describe(port: &str, timeout_ms: Option<u64>) -> Result<DeviceDescriptor, ConfigError> {
+    match cached(port) {
+        Some(descriptor) => Ok(descriptor),
+        None => identify(port, timeout_ms),
+    }
+}
+
+#[tauri::command]
+pub async fn identify_device(
+    port_name: Option<String>,
+    refresh: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<DeviceDescriptor, ConfigError> {
+    blocking(move || {
+        let port = target_port(port_name)?;
+        if refresh.unwrap_or(false) {
+            identify(&port, timeout_ms)
+        } else {
+            describe(&port, timeout_ms)
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_replies_are_parsed() {
+        let reply = ["identity marshal", "Firmware: v1.3.0-14-g1a2b3c4d", "HW_Rev: B", "Serial number: E6614C311B4A"].map(String::from);
+        assert_eq!(
+            parse_identity(&reply),
+            Reported {
+                identity: Some("marshal".into()),
+                firmware: Some("v1.3.0-14-g1a2b3c4d".into()),
+                build_hash: Some("1a2b3c4d".into()),
+                hardware_revision: Some("B".into()),
+                serial_number: Some("E6614C311B4A".into()),
+            }
+        );
+        // Zephyr's `hwinfo devid`
+        let devid = parse_identity(&["ID: 0x3a1b2c3d4e5f6071".to_string()]);
+        assert_eq!(devid.serial_number.as_deref(), Some("0x3a1b2c3d4e5f6071"));
+        assert_eq!(dialect_for(DeviceType::Marshal), ParserDialect::Console);
+    }
+}
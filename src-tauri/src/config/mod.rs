@@ -0,0 +1,439 @@
+pub mod backup;
+pub mod identity;
+pub mod profiles;
+pub mod provision;
+pub mod reboot;
+pub mod schema;
+pub mod verify;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::deputy_interpreter::{RE_HEADER_LICENSED_NOFIX, RE_HEADER_NODE, RE_LOG_UPTIME};
+use crate::serial::{self, blocking};
+
+const DEFAULT_TIMEOUT_MS: u64 = 2000;
+// A reply is complete once the device has gone this long without another line
+const QUIET_MS: u64 = 250;
+
+// Words in the `config` help output that aren't settings
+const IGNORED_KEYS: [&str; 7] = ["config", "subcommands", "uart", "usage", "help", "identity", "save"];
+
+lazy_static! {
+    static ref RE_ANSI: Regex = Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap();
+    // Zephyr shell prompt, e.g. "uart:~$ "
+    static ref RE_PROMPT: Regex = Regex::new(r"^[\w-]*:~\$\s*").unwrap();
+    static ref RE_ERROR: Regex =
+        Regex::new(r"(?i)\b(error|invalid|unknown|not supported|unsupported|failed|out of range|usage)\b").unwrap();
+    static ref RE_UNKNOWN_KEY: Regex =
+        Regex::new(r"(?i)\b(unknown|unsupported|not supported)\b.*\b(key|config|setting|parameter|subcommand)\b|\b(key|config|setting|parameter|subcommand)\b.*\b(unknown|not found|not supported)\b").unwrap();
+    static ref RE_KEY_PATTERNS: [Regex; 5] = [
+        Regex::new(r"^supported\s+([a-z][a-z0-9_]*)\b").unwrap(),
+        Regex::new(r"^([a-z][a-z0-9_]*)$").unwrap(),
+        Regex::new(r"^([a-z][a-z0-9_]*)\s*[:=-]").unwrap(),
+        Regex::new(r"^config\s+([a-z][a-z0-9_]*)\b").unwrap(),
+        Regex::new(r"^[*-]\s*([a-z][a-z0-9_]*)\b").unwrap(),
+    ];
+    static ref RE_KEY_VALUE: Regex = Regex::new(r"^(?:config\s+)?([a-z][a-z0-9_]*)\s*[:=]\s*(\S.*)$").unwrap();
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ConfigError {
+    // No such port open, or several open and none named
+    Port { message: String },
+    // Another config request is still waiting on this port
+    Busy { port: String },
+    // The device said nothing in reply
+    Timeout { command: String, timeout_ms: u64 },
+    UnknownKey { key: String },
+    // Refused by the schema before it was sent
+    Invalid { key: String, message: String },
+    Rejected { command: String, message: String },
+    // The device replied, but not in a form we understand
+    Malformed { command: String, reply: Vec<String> },
+    // A profile for one kind of device applied to another
+    WrongDevice { expected: DeviceType, found: DeviceType },
+    // Read back after a write and found to differ
+    Mismatch { checks: Vec<verify::KeyCheck> },
+    Write { message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Port { message } | ConfigError::Write { message } => write!(f, "{message}"),
+            ConfigError::Busy { port } => write!(f, "{port} is busy with another config request"),
+            ConfigError::Timeout { command, timeout_ms } => write!(f, "No reply to \"{command}\" within {timeout_ms} ms"),
+            ConfigError::UnknownKey { key } => write!(f, "The device has no setting named {key}"),
+            ConfigError::Invalid { message, .. } => write!(f, "{message}"),
+            ConfigError::Rejected { command, message } => write!(f, "The device rejected \"{command}\": {message}"),
+            ConfigError::Malformed { command, reply } => write!(f, "Unexpected reply to \"{command}\": {}", reply.join(" / ")),
+            ConfigError::WrongDevice { expected, found } => {
+                write!(f, "This is for a {} but the device is a {}", expected.label(), found.label())
+            }
+            ConfigError::Mismatch { checks } => {
+                let diffs: Vec<String> = checks
+                    .iter()
+                    .filter(|c| !c.matches)
+                    .map(|c| format!("{} expected {}, device has {}", c.key, c.expected, c.actual.as_deref().unwrap_or("nothing")))
+                    .collect();
+                write!(f, "Settings differ after writing: {}", diffs.join("; "))
+            }
+        }
+    }
+}
+
+// A failure outside any one request, such as the worker running it dying
+impl From<String> for ConfigError {
+    fn from(message: String) -> Self {
+        ConfigError::Write { message }
+    }
+}
+
+impl ConfigError {
+    // Losing the port or the config lock, which ends a batch of requests rather than one setting
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, ConfigError::Port { .. } | ConfigError::Write { .. } | ConfigError::Busy { .. })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceType {
+    Tracker,
+    Marshal,
+}
+
+impl DeviceType {
+    pub fn label(self) -> &'static str {
+        match self {
+            DeviceType::Tracker => "tracker",
+            DeviceType::Marshal => "marshal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub port: String,
+    // From the "identity <name>" line, when the firmware prints one
+    pub identity: Option<String>,
+    pub device_type: DeviceType,
+    pub keys: Vec<String>,
+    // Current values, for firmware that lists them alongside the keys
+    pub values: BTreeMap<String, String>,
+    pub reply: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValue {
+    pub key: String,
+    // The value the device reported; a set that is acknowledged without echoing it leaves this empty
+    pub value: Option<String>,
+    pub reply: Vec<String>,
+}
+
+// One pending request per port. The serial reader hands every line to it while telemetry carries
+// on through the packet pipeline as usual.
+static PENDING: OnceLock<Mutex<HashMap<String, Sender<String>>>> = OnceLock::new();
+
+fn get_pending() -> &'static Mutex<HashMap<String, Sender<String>>> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn observe_line(port: &str, line: &str) {
+    if let Ok(pending) = get_pending().lock() {
+        if let Some(tx) = pending.get(port) {
+            let _ = tx.send(line.to_string());
+        }
+    }
+}
+
+// Holds a port's reply channel until the request is done
+struct Subscription {
+    port: String,
+    lines: Receiver<String>,
+}
+
+impl Subscription {
+    fn open(port: &str) -> Result<Self, ConfigError> {
+        let mut pending = get_pending().lock().map_err(|e| ConfigError::Port { message: format!("config lock error: {}", e) })?;
+        if pending.contains_key(port) {
+            return Err(ConfigError::Busy { port: port.to_string() });
+        }
+        let (tx, rx) = mpsc::channel();
+        pending.insert(port.to_string(), tx);
+        Ok(Subscription { port: port.to_string(), lines: rx })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = get_pending().lock() {
+            pending.remove(&self.port);
+        }
+    }
+}
+
+// The text of a line as part of a shell reply, or None for telemetry and log output that merely
+// arrived at the same time.
+fn reply_text(line: &str) -> Option<String> {
+    let clean = RE_ANSI.replace_all(line, "");
+    let text = RE_PROMPT.replace(clean.trim(), "").trim().to_string();
+    if RE_LOG_UPTIME.is_match(&text) || RE_HEADER_NODE.is_match(&text) || RE_HEADER_LICENSED_NOFIX.is_match(&text) {
+        return None;
+    }
+    Some(text)
+}
+
+// Gathers the reply to `command`: the lines after the shell's echo of it until the device goes
+// quiet. Times out only when the device says nothing at all.
+fn collect_reply(lines: &Receiver<String>, command: &str, timeout: Duration) -> Result<Vec<String>, ConfigError> {
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+    let mut heard = false;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        let wait = if heard { Duration::from_millis(QUIET_MS).min(deadline - now) } else { deadline - now };
+        match lines.recv_timeout(wait) {
+            Ok(line) => {
+                let Some(text) = reply_text(&line) else { continue };
+                if text == command {
+                    heard = true;
+                } else if !text.is_empty() {
+                    heard = true;
+                    reply.push(text);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    if !heard {
+        return Err(ConfigError::Timeout { command: command.to_string(), timeout_ms: timeout.as_millis() as u64 });
+    }
+    Ok(reply)
+}
+
+fn exchange(port: &str, command: &str, timeout_ms: Option<u64>) -> Result<Vec<String>, ConfigError> {
+    let subscription = Subscription::open(port)?;
+    serial::write_line(port, command).map_err(|message| ConfigError::Write { message })?;
+    collect_reply(&subscription.lines, command, Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS)))
+}
+
+fn check_reply(command: &str, key: Option<&str>, reply: &[String]) -> Result<(), ConfigError> {
+    let Some(line) = reply.iter().find(|line| RE_ERROR.is_match(line)) else {
+        return Ok(());
+    };
+    match key {
+        Some(key) if RE_UNKNOWN_KEY.is_match(line) => Err(ConfigError::UnknownKey { key: key.to_string() }),
+        _ => Err(ConfigError::Rejected { command: command.to_string(), message: line.clone() }),
+    }
+}
+
+// "freq: 433.92", "freq = 433.92", "config freq 433.92" or just "433.92"
+fn parse_value(key: &str, reply: &[String]) -> Option<String> {
+    let pattern = Regex::new(&format!(r"(?i)^(?:config\s+)?{}\s*(?:[:=]\s*|\s+)(\S.*)$", regex::escape(key))).ok()?;
+    if let Some(value) = reply.iter().find_map(|line| pattern.captures(line).map(|c| c[1].trim().to_string())) {
+        return Some(value);
+    }
+    match reply {
+        [only] if !only.contains(char::is_whitespace) => Some(only.clone()),
+        _ => None,
+    }
+}
+
+fn parse_probe(port: &str, reply: Vec<String>) -> ProbeResult {
+    let mut keys: Vec<String> = Vec::new();
+    let mut values = BTreeMap::new();
+    let mut identity = None;
+    for line in &reply {
+        let line = line.trim().to_lowercase();
+        if let Some(name) = line.strip_prefix("identity ") {
+            identity = Some(name.trim().to_string());
+            continue;
+        }
+        for pattern in RE_KEY_PATTERNS.iter() {
+            if let Some(key) = pattern.captures(&line).and_then(|c| c.get(1)) {
+                let key = key.as_str().to_string();
+                if !IGNORED_KEYS.contains(&key.as_str()) && !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        if let Some(cap) = RE_KEY_VALUE.captures(&line) {
+            values.insert(cap[1].to_string(), cap[2].trim().to_string());
+        }
+    }
+    for key in schema::keys() {
+        if !keys.iter().any(|k| k == key) && reply.iter().any(|line| line.to_lowercase().contains(key)) {
+            keys.push(key.to_string());
+        }
+    }
+    values.retain(|key, _| keys.contains(key));
+    keys.sort_by(|a, b| {
+        // Settings in the schema first, then device-specific ones
+        let unknown = |k: &String| schema::field(k).is_none();
+        unknown(a).cmp(&unknown(b)).then_with(|| a.cmp(b))
+    });
+
+    let marshal_key = |k: &String| schema::field(k).is_some_and(|f| f.device_type == DeviceType::Marshal);
+    let device_type = if identity.as_deref() == Some("marshal") || keys.iter().any(marshal_key) {
+        DeviceType::Marshal
+    } else {
+        DeviceType::Tracker
+    };
+    ProbeResult { port: port.to_string(), identity, device_type, keys, values, reply }
+}
+
+fn target_port(port_name: Option<String>) -> Result<String, ConfigError> {
+    serial::resolve_port(port_name).map_err(|message| ConfigError::Port { message })
+}
+
+// Asks the device which settings it supports.
+pub fn probe(port: &str, timeout_ms: Option<u64>) -> Result<ProbeResult, ConfigError> {
+    let reply = exchange(port, "config", timeout_ms)?;
+    let mut result = parse_probe(port, reply);
+    // The device's own word on what it is beats guessing from its settings
+    if let Some(descriptor) = identity::cached(port) {
+        result.device_type = descriptor.device_type;
+    }
+    Ok(result)
+}
+
+pub fn get(port: &str, key: &str, timeout_ms: Option<u64>) -> Result<ConfigValue, ConfigError> {
+    let command = format!("config {key}");
+    let reply = exchange(port, &command, timeout_ms)?;
+    check_reply(&command, Some(key), &reply)?;
+    match parse_value(key, &reply) {
+        Some(value) => Ok(ConfigValue { key: key.to_string(), value: Some(value), reply }),
+        None => Err(ConfigError::Malformed { command, reply }),
+    }
+}
+
+pub fn set(port: &str, key: &str, value: &str, timeout_ms: Option<u64>) -> Result<ConfigValue, ConfigError> {
+    let value = schema::validate(key, value).map_err(|message| ConfigError::Invalid { key: key.to_string(), message })?;
+    // Once the device is identified, schema settings meant for other devices or firmware are refused
+    if let Some(descriptor) = identity::cached(port) {
+        if schema::field(key).is_some() && !descriptor.schema.fields.iter().any(|f| f.key == key) {
+            return Err(ConfigError::UnknownKey { key: key.to_string() });
+        }
+    }
+    let command = format!("config {key} {value}");
+    let reply = exchange(port, &command, timeout_ms)?;
+    check_reply(&command, Some(key), &reply)?;
+    Ok(ConfigValue { key: key.to_string(), value: parse_value(key, &reply), reply })
+}
+
+// Writes the settings to flash, for firmware that doesn't save on every set.
+pub fn save(port: &str, timeout_ms: Option<u64>) -> Result<Vec<String>, ConfigError> {
+    let command = "config save";
+    let reply = exchange(port, command, timeout_ms)?;
+    check_reply(command, None, &reply)?;
+    Ok(reply)
+}
+
+#[tauri::command]
+pub async fn config_probe(port_name: Option<String>, timeout_ms: Option<u64>) -> Result<ProbeResult, ConfigError> {
+    blocking(move || probe(&target_port(port_name)?, timeout_ms)).await
+}
+
+#[tauri::command]
+pub async fn config_get(port_name: Option<String>, key: String, timeout_ms: Option<u64>) -> Result<ConfigValue, ConfigError> {
+    blocking(move || get(&target_port(port_name)?, &key, timeout_ms)).await
+}
+
+#[tauri::command]
+pub async fn config_set(
+    port_name: Option<String>,
+    key: String,
+    value: String,
+    verify: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<ConfigValue, ConfigError> {
+    blocking(move || {
+        let port = target_port(port_name)?;
+        let result = set(&port, &key, &value, timeout_ms)?;
+        // Read the setting straight back; a write the device silently dropped shows up here
+        if verify.unwrap_or(false) {
+            let check = verify::check_key(&port, &key, &value, timeout_ms)?;
+            if !check.matches {
+                return Err(ConfigError::Mismatch { checks: vec![check] });
+            }
+        }
+        Ok(result)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn config_save(port_name: Option<String>, timeout_ms: Option<u64>) -> Result<Vec<String>, ConfigError> {
+    blocking(move || save(&target_port(port_name)?, timeout_ms)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(lines: &[&str]) -> Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        for line in lines {
+            tx.send(line.to_string()).unwrap();
+        }
+        // Keep the sender alive past the quiet period like the serial reader does
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(QUIET_MS * 2));
+            drop(tx);
+        });
+        rx
+    }
+
+    #[test]
+    fn reply_skips_echo_and_interleaved_telemetry() {
+        let rx = feed(&[
+            "\x1b[1;32muart:~$ \x1b[mconfig freq",
+            "[00:01:11.262,000] <inf> lora: Node 1: (13 bytes | -80 dBm | 7 dB):",
+            "freq: 433.920000",
+            "Node 2: (13 bytes | -91 dBm | 5 dB):",
+        ]);
+        let reply = collect_reply(&rx, "config freq", Duration::from_secs(1)).unwrap();
+        assert_eq!(reply, vec!["freq: 433.920000"]);
+        assert_eq!(parse_value("freq", &reply).as_deref(), Some("433.920000"));
+
+        let silent = feed(&["[00:01:12.000,000] <inf> lora: Latitude: 43.0847"]);
+        let err = collect_reply(&silent, "config freq", Duration::from_millis(100)).unwrap_err();
+        assert!(matches!(err, ConfigError::Timeout { timeout_ms: 100, .. }));
+    }
+
+    #[test]
+    fn replies_are_classified() {
+        let probe = parse_probe(
+            "COM3",
+            ["identity marshal", "Subcommands:", "  mode  :deploy mode", "  main_alt  :main altitude", "  save  :write to flash", "main_alt: 500"]
+                .map(String::from)
+                .to_vec(),
+        );
+        assert_eq!(probe.identity.as_deref(), Some("marshal"));
+        assert_eq!(probe.device_type, DeviceType::Marshal);
+        assert_eq!(probe.keys, vec!["main_alt", "mode"]);
+        assert_eq!(probe.values.get("main_alt").map(String::as_str), Some("500"));
+
+        let unknown = vec!["Unknown config key: foo".to_string()];
+        assert_eq!(check_reply("config foo 1", Some("foo"), &unknown), Err(ConfigError::UnknownKey { key: "foo".into() }));
+        let rejected = vec!["Error: frequency out of range".to_string()];
+        assert!(matches!(check_reply("config freq 500", Some("freq"), &rejected), Err(ConfigError::Rejected { .. })));
+        assert_eq!(check_reply("config freq 433.92", Some("freq"), &["OK".to_string()]), Ok(()));
+    }
+}
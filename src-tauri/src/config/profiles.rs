@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::reboot::{self, RebootMethod};
+use super::verify::{self, KeyCheck};
+use super::{probe, schema, set, target_port, ConfigError, DeviceType};
+use crate::serial::blocking;
+
+const PROFILES_DIR: &str = "profiles";
+
+// A named set of settings to write to devices, e.g. the club frequency and callsign
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Devices the profile is meant for; applying it to another type is refused
+    #[serde(default)]
+    pub device_type: Option<DeviceType>,
+    #[serde(deserialize_with = "setting_values")]
+    pub values: BTreeMap<String, String>,
+}
+
+// Hand-written TOML tends to say `node_id = 4` rather than `node_id = "4"`
+fn setting_values<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error> {
+    let raw = BTreeMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    raw.into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(text) => Ok((key, text)),
+            serde_json::Value::Number(number) => Ok((key, number.to_string())),
+            serde_json::Value::Bool(flag) => Ok((key, flag.to_string())),
+            other => Err(serde::de::Error::custom(format!("{key}: {other} is not a setting value"))),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProfileFormat {
+    Toml,
+    Json,
+}
+
+impl ProfileFormat {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("toml") => Ok(ProfileFormat::Toml),
+            Some("json") => Ok(ProfileFormat::Json),
+            _ => Err(format!("{} is not a .toml or .json profile", path.display())),
+        }
+    }
+}
+
+fn parse_profile(text: &str, format: ProfileFormat) -> Result<Profile, String> {
+    match format {
+        ProfileFormat::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+        ProfileFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+    }
+}
+
+fn render_profile(profile: &Profile, format: ProfileFormat) -> Result<String, String> {
+    match format {
+        ProfileFormat::Toml => toml::to_string_pretty(profile).map_err(|e| e.to_string()),
+        ProfileFormat::Json => serde_json::to_string_pretty(profile).map_err(|e| e.to_string()),
+    }
+}
+
+// Checks every value against the schema, normalising them the way they'll be written.
+fn validate_profile(profile: &mut Profile) -> Result<(), String> {
+    profile.name = profile.name.trim().to_string();
+    if profile.name.is_empty() {
+        return Err("A profile needs a name".into());
+    }
+    if profile.values.is_empty() {
+        return Err(format!("Profile {} has no settings", profile.name));
+    }
+    let mut errors = Vec::new();
+    for (key, value) in profile.values.iter_mut() {
+        if let (Some(spec), Some(device_type)) = (schema::field(key), profile.device_type) {
+            if spec.device_type != device_type {
+                errors.push(format!("{key}: not a {} setting", device_type.label()));
+                continue;
+            }
+        }
+        match schema::validate(key, value) {
+            Ok(normalized) => *value = normalized,
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    Ok(())
+}
+
+// File name for a profile: "Club Tracker 433" is stored as club-tracker-433.toml
+fn file_stem(name: &str) -> String {
+    let mut stem = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            stem.push(c.to_ascii_lowercase());
+        } else if !stem.ends_with('-') {
+            stem.push('-');
+        }
+    }
+    let stem = stem.trim_matches('-').to_string();
+    if stem.is_empty() {
+        "profile".into()
+    } else {
+        stem
+    }
+}
+
+fn profiles_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| format!("No config directory: {e}"))?;
+    Ok(dir.join(PROFILES_DIR))
+}
+
+// Every readable profile with the file it came from. Unreadable files are skipped so one bad
+// hand edit doesn't hide the rest.
+fn load_all(app: &AppHandle) -> Result<Vec<(PathBuf, Profile)>, String> {
+    let dir = profiles_dir(app)?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut profiles = Vec::new();
+    for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+        let Ok(format) = ProfileFormat::from_path(&path) else {
+            continue;
+        };
+        let parsed = fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| parse_profile(&text, format));
+        match parsed {
+            Ok(profile) => profiles.push((path, profile)),
+            Err(e) => eprintln!("Ignoring unreadable profile {}: {e}", path.display()),
+        }
+    }
+    profiles.sort_by_key(|(_, p)| p.name.to_lowercase());
+    Ok(profiles)
+}
+
+pub(super) fn find(app: &AppHandle, name: &str) -> Result<(PathBuf, Profile), String> {
+    load_all(app)?
+        .into_iter()
+        .find(|(_, p)| p.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("No profile named {name}"))
+}
+
+fn write_profile(app: &AppHandle, profile: &Profile) -> Result<PathBuf, String> {
+    let dir = profiles_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create profiles directory: {e}"))?;
+    let path = dir.join(format!("{}.toml", file_stem(&profile.name)));
+    fs::write(&path, render_profile(profile, ProfileFormat::Toml)?).map_err(|e| format!("Failed to save profile: {e}"))?;
+    Ok(path)
+}
+
+#[tauri::command]
+pub fn list_profiles(app: AppHandle) -> Result<Vec<Profile>, String> {
+    Ok(load_all(&app)?.into_iter().map(|(_, p)| p).collect())
+}
+
+// Creates a profile, or replaces `original_name` when editing one (which may rename it).
+#[tauri::command]
+pub fn save_profile(app: AppHandle, mut profile: Profile, original_name: Option<String>) -> Result<Profile, String> {
+    validate_profile(&mut profile)?;
+    let existing = load_all(&app)?;
+    let original = original_name.as_deref().map(str::trim);
+    let clash = existing.iter().find(|(path, p)| {
+        let is_original = original.is_some_and(|o| p.name.eq_ignore_ascii_case(o));
+        !is_original
+            && (p.name.eq_ignore_ascii_case(&profile.name)
+                || path.file_stem().and_then(|s| s.to_str()) == Some(file_stem(&profile.name).as_str()))
+    });
+    if let Some((_, other)) = clash {
+        return Err(format!("A profile named {} already exists", other.name));
+    }
+
+    let saved = write_profile(&app, &profile)?;
+    if let Some((old_path, _)) = existing.iter().find(|(_, p)| original.is_some_and(|o| p.name.eq_ignore_ascii_case(o))) {
+        if *old_path != saved {
+            fs::remove_file(old_path).map_err(|e| format!("Failed to remove the old profile: {e}"))?;
+        }
+    }
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, name: String) -> Result<(), String> {
+    let (path, _) = find(&app, &name)?;
+    fs::remove_file(path).map_err(|e| format!("Failed to delete profile: {e}"))
+}
+
+// Reads a .toml or .json profile from elsewhere, e.g. one a teammate shared. A name already in
+// use gets a number appended.
+#[tauri::command]
+pub fn import_profile(app: AppHandle, path: String) -> Result<Profile, String> {
+    let path = PathBuf::from(path);
+    let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let mut profile = parse_profile(&text, ProfileFormat::from_path(&path)?)?;
+    validate_profile(&mut profile)?;
+
+    let taken: Vec<String> = load_all(&app)?.into_iter().map(|(_, p)| file_stem(&p.name)).collect();
+    let base = profile.name.clone();
+    let mut n = 2;
+    while taken.contains(&file_stem(&profile.name)) {
+        profile.name = format!("{base} ({n})");
+        n += 1;
+    }
+    write_profile(&app, &profile)?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn export_profile(app: AppHandle, name: String, path: String) -> Result<String, String> {
+    let (_, profile) = find(&app, &name)?;
+    let target = PathBuf::from(path);
+    let text = render_profile(&profile, ProfileFormat::from_path(&target)?)?;
+    fs::write(&target, text).map_err(|e| format!("Failed to export profile: {e}"))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyResult {
+    pub key: String,
+    pub value: String,
+    pub ok: bool,
+    // Why the write failed, if it did
+    pub error: Option<String>,
+    // Read back straight after the write
+    pub check: Option<KeyCheck>,
+    // Read back after the reboot, when one was asked for
+    pub check_after_reboot: Option<KeyCheck>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyReport {
+    pub profile: String,
+    pub port: String,
+    // Where the device came back after the reboot, which can be a new name
+    pub reopened_port: Option<String>,
+    pub rebooted: bool,
+    // Whether every setting was written and read back correctly
+    pub ok: bool,
+    pub results: Vec<KeyResult>,
+}
+
+// Writes a profile's settings to a device one at a time, reading each back, and optionally
+// reboots the device and checks them all again.
+pub fn apply(app: &AppHandle, port: &str, profile: &Profile, reboot: bool, timeout_ms: Option<u64>) -> Result<ApplyReport, ConfigError> {
+    let device = probe(port, timeout_ms)?;
+    if let Some(expected) = profile.device_type.filter(|t| *t != device.device_type) {
+        return Err(ConfigError::WrongDevice { expected, found: device.device_type });
+    }
+
+    let mut results = Vec::new();
+    for (key, value) in &profile.values {
+        let mut result = KeyResult {
+            key: key.clone(),
+            value: value.clone(),
+            ok: false,
+            error: None,
+            check: None,
+            check_after_reboot: None,
+        };
+        if !device.keys.contains(key) {
+            result.error = Some("Not supported by this device".into());
+        } else {
+            match set(port, key, value, timeout_ms) {
+                Ok(_) => {
+                    let check = verify::check_key(port, key, value, timeout_ms)?;
+                    result.ok = check.matches;
+                    result.check = Some(check);
+                }
+                Err(e) if e.is_fatal() => return Err(e),
+                Err(e) => result.error = Some(e.to_string()),
+            }
+        }
+        results.push(result);
+    }
+
+    let written: BTreeMap<String, String> = results.iter().filter(|r| r.ok).map(|r| (r.key.clone(), r.value.clone())).collect();
+    let rebooted = reboot && !written.is_empty();
+    let mut reopened_port = None;
+    if rebooted {
+        let reopened = reboot::reboot(app, port, RebootMethod::Shell, None, reboot::DEFAULT_TIMEOUT_MS)?.reopened_port;
+        for check in verify::read_back(&reopened, &written, timeout_ms)? {
+            if let Some(result) = results.iter_mut().find(|r| r.key == check.key) {
+                result.ok = check.matches;
+                result.check_after_reboot = Some(check);
+            }
+        }
+        reopened_port = Some(reopened);
+    }
+
+    let ok = results.iter().all(|r| r.ok);
+    Ok(ApplyReport { profile: profile.name.clone(), port: port.to_string(), reopened_port, rebooted, ok, results })
+}
+
+#[tauri::command]
+pub async fn apply_profile(
+    app: AppHandle,
+    name: String,
+    port_name: Option<String>,
+    reboot: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<ApplyReport, ConfigError> {
+    blocking(move || {
+        let (_, profile) = find(&app, &name).map_err(|message| ConfigError::Invalid { key: "profile".into(), message })?;
+        apply(&app, &target_port(port_name)?, &profile, reboot.unwrap_or(false), timeout_ms)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_parse_and_validate() {
+        let text = r#"
+name = " Club tracker "
+deviceType = "tracker"
+
+[values]
+freq = 433.92
+node_id = 4
+callsign = "kd2yie"
+"#;
+        let mut profile = parse_profile(text, ProfileFormat::Toml).unwrap();
+        validate_profile(&mut profile).unwrap();
+        assert_eq!(profile.name, "Club tracker");
+        assert_eq!(profile.values.get("node_id").map(String::as_str), Some("4"));
+        assert_eq!(profile.values.get("callsign").map(String::as_str), Some("KD2YIE"));
+
+        let json = render_profile(&profile, ProfileFormat::Json).unwrap();
+        assert_eq!(parse_profile(&json, ProfileFormat::Json).unwrap(), profile);
+        let toml = render_profile(&profile, ProfileFormat::Toml).unwrap();
+        assert_eq!(parse_profile(&toml, ProfileFormat::Toml).unwrap(), profile);
+        assert_eq!(file_stem(&profile.name), "club-tracker");
+
+        profile.values.insert("main_alt".into(), "600".into());
+        profile.values.insert("node_id".into(), "12".into());
+        let err = validate_profile(&mut profile).unwrap_err();
+        assert!(err.contains("main_alt: not a tracker setting") && err.contains("node_id"), "{err}");
+    }
+}
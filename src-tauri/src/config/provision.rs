@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::profiles::{self, ApplyReport, Profile};
+use crate::serial::{self, PortMode};
+
+const POLL_MS: u64 = 500;
+// Time for a freshly plugged device to boot its shell before we talk to it
+const SETTLE_MS: u64 = 1500;
+const DEFAULT_BAUD: u32 = 115_200;
+const MANIFESTS_DIR: &str = "provisioning";
+pub const MANIFEST_FORMAT: &str = "dispatch-provisioning-manifest";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    WaitingForDevice,
+    Configuring,
+    // The device is done and should be unplugged before the next one goes in
+    Unplug,
+    Finished,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvisionedDevice {
+    // USB serial number of the device, which stays with the hardware whatever port it appears on
+    pub serial_number: Option<String>,
+    pub port: String,
+    pub node_id: u8,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub timestamp: String,
+    pub report: Option<ApplyReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvisioningStatus {
+    pub profile: String,
+    pub first_node_id: u8,
+    pub last_node_id: u8,
+    // None once every node ID has been handed out
+    pub next_node_id: Option<u8>,
+    pub state: JobState,
+    pub port: Option<String>,
+    // What the operator should do now
+    pub message: String,
+    pub devices: Vec<ProvisionedDevice>,
+    pub manifest_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub format: String,
+    pub profile: String,
+    pub created_at: String,
+    // Hardware serial number to node ID, for every device provisioned successfully
+    pub nodes: BTreeMap<String, u8>,
+    pub devices: Vec<ProvisionedDevice>,
+}
+
+struct Job {
+    status: ProvisioningStatus,
+    cancel: Arc<AtomicBool>,
+}
+
+static JOB: OnceLock<Mutex<Option<Job>>> = OnceLock::new();
+
+fn get_job() -> &'static Mutex<Option<Job>> {
+    JOB.get_or_init(|| Mutex::new(None))
+}
+
+// Updates the shared status and tells the UI.
+fn update(app: &AppHandle, change: impl FnOnce(&mut ProvisioningStatus)) {
+    let status = {
+        let Ok(mut job) = get_job().lock() else {
+            return;
+        };
+        let Some(job) = job.as_mut() else {
+            return;
+        };
+        change(&mut job.status);
+        job.status.clone()
+    };
+    let _ = app.emit("provisioning", status);
+}
+
+// USB serial ports present now, with their hardware serial numbers
+fn usb_ports() -> Vec<(String, Option<String>)> {
+    serialport::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|port| match port.port_type {
+            serialport::SerialPortType::UsbPort(usb) => Some((port.port_name, usb.serial_number)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Polls until `check` yields something, or the job is cancelled.
+fn wait_for<T>(cancel: &AtomicBool, mut check: impl FnMut() -> Option<T>) -> Option<T> {
+    while !cancel.load(Ordering::Relaxed) {
+        if let Some(found) = check() {
+            return Some(found);
+        }
+        thread::sleep(Duration::from_millis(POLL_MS));
+    }
+    None
+}
+
+fn prompt(node_id: Option<u8>) -> String {
+    match node_id {
+        Some(id) => format!("Plug in the tracker to become node {id}"),
+        None => "All node IDs have been assigned".into(),
+    }
+}
+
+fn provision_device(app: &AppHandle, port: &str, profile: &Profile, baud: u32, reboot: bool) -> Result<ApplyReport, String> {
+    // Opened for its config shell only, so provisioning leaves no sessions, logs or alerts behind
+    serial::open(app.clone(), port.to_string(), baud, PortMode::ConfigOnly)?;
+    thread::sleep(Duration::from_millis(SETTLE_MS));
+    let result = profiles::apply(app, port, profile, reboot, None).map_err(|e| e.to_string());
+    // A reboot can bring the device back under another name
+    let reopened = result.as_ref().ok().and_then(|report| report.reopened_port.clone());
+    let _ = serial::close_port(Some(reopened.unwrap_or_else(|| port.to_string())));
+    result
+}
+
+fn write_manifest(app: &AppHandle, status: &ProvisioningStatus) -> Result<String, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("No data directory: {e}"))?.join(MANIFESTS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create provisioning directory: {e}"))?;
+    let manifest = build_manifest(status);
+    let path = dir.join(format!("manifest-{}.json", Utc::now().format("%Y%m%dT%H%M%SZ")));
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write manifest: {e}"))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+fn build_manifest(status: &ProvisioningStatus) -> Manifest {
+    let nodes = status
+        .devices
+        .iter()
+        .filter(|d| d.ok)
+        .map(|d| (d.serial_number.clone().unwrap_or_else(|| format!("unknown ({})", d.port)), d.node_id))
+        .collect();
+    Manifest {
+        format: MANIFEST_FORMAT.into(),
+        profile: status.profile.clone(),
+        created_at: Utc::now().to_rfc3339(),
+        nodes,
+        devices: status.devices.clone(),
+    }
+}
+
+// The node ID after a device has been handled: the next one on success, the same one again after a
+// failure so the next device plugged in retries it.
+fn next_node(current: u8, last: u8, ok: bool) -> Option<u8> {
+    match ok {
+        true if current < last => Some(current + 1),
+        true => None,
+        false => Some(current),
+    }
+}
+
+// Whether the device just handled is still connected: followed by its USB serial number when it has
+// one, since a reboot may have moved it to another port, or else by the ports it was seen on.
+fn still_plugged_in(present: &[(String, Option<String>)], ports: &[String], serial_number: Option<&str>) -> bool {
+    match serial_number {
+        Some(serial) => present.iter().any(|(_, s)| s.as_deref() == Some(serial)),
+        None => present.iter().any(|(name, _)| ports.contains(name)),
+    }
+}
+
+fn run(app: AppHandle, profile: Profile, nodes: (u8, u8), baud: u32, reboot: bool, cancel: Arc<AtomicBool>) {
+    // Ports already present, like the base station receiver, are never provisioned
+    let mut baseline: Vec<String> = usb_ports().into_iter().map(|(name, _)| name).collect();
+    let (mut node_id, last) = (Some(nodes.0), nodes.1);
+
+    while let Some(id) = node_id {
+        update(&app, |s| {
+            s.state = JobState::WaitingForDevice;
+            s.port = None;
+            s.message = prompt(Some(id));
+        });
+        let Some((port, serial_number)) = wait_for(&cancel, || {
+            let present = usb_ports();
+            baseline.retain(|name| present.iter().any(|(p, _)| p == name));
+            present.into_iter().find(|(name, _)| !baseline.contains(name))
+        }) else {
+            break;
+        };
+
+        update(&app, |s| {
+            s.state = JobState::Configuring;
+            s.port = Some(port.clone());
+            s.message = format!("Configuring {port} as node {id}");
+        });
+        let mut device_profile = profile.clone();
+        device_profile.values.insert("node_id".into(), id.to_string());
+        let result = provision_device(&app, &port, &device_profile, baud, reboot);
+        let ok = result.as_ref().is_ok_and(|report| report.ok);
+        // A reboot can bring the device back under another name
+        let ports: Vec<String> =
+            std::iter::once(port.clone()).chain(result.as_ref().ok().and_then(|report| report.reopened_port.clone())).collect();
+        let error = match &result {
+            Ok(report) if !report.ok => Some(
+                report
+                    .results
+                    .iter()
+                    .filter(|r| !r.ok)
+                    .map(|r| format!("{}: {}", r.key, r.error.as_deref().unwrap_or("read back a different value")))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ),
+            Ok(_) => None,
+            Err(e) => Some(e.clone()),
+        };
+        let device = ProvisionedDevice {
+            serial_number: serial_number.clone(),
+            port: port.clone(),
+            node_id: id,
+            ok,
+            error: error.clone(),
+            timestamp: Utc::now().to_rfc3339(),
+            report: result.ok(),
+        };
+        node_id = next_node(id, last, ok);
+
+        update(&app, |s| {
+            s.devices.push(device);
+            s.next_node_id = node_id;
+            s.state = JobState::Unplug;
+            s.message = match &error {
+                None => format!("Node {id} done. Unplug {port}. {}", prompt(node_id)),
+                Some(e) => format!("Node {id} failed on {port}: {e}. Unplug it and try another device."),
+            };
+        });
+        // Wait for the device to go before looking for the next one
+        if wait_for(&cancel, || (!still_plugged_in(&usb_ports(), &ports, serial_number.as_deref())).then_some(())).is_none() {
+            break;
+        }
+    }
+
+    let cancelled = cancel.load(Ordering::Relaxed);
+    let status = get_job().lock().ok().and_then(|job| job.as_ref().map(|j| j.status.clone()));
+    let manifest = status.as_ref().map(|s| write_manifest(&app, s));
+    update(&app, |s| {
+        s.state = if cancelled { JobState::Cancelled } else { JobState::Finished };
+        s.port = None;
+        match manifest {
+            Some(Ok(path)) => {
+                s.message = format!("Provisioned {} devices. Manifest saved to {path}", s.devices.iter().filter(|d| d.ok).count());
+                s.manifest_path = Some(path);
+            }
+            Some(Err(e)) => s.message = e,
+            None => {}
+        }
+    });
+}
+
+// Starts provisioning devices with `profile`, giving them node IDs first..=last as they are
+// plugged in one at a time. Progress is reported through "provisioning" events.
+#[tauri::command]
+pub fn start_provisioning(
+    app: AppHandle,
+    profile: String,
+    first_node_id: u8,
+    last_node_id: u8,
+    baud_rate: Option<u32>,
+    reboot: Option<bool>,
+) -> Result<ProvisioningStatus, String> {
+    let (_, profile) = profiles::find(&app, &profile)?;
+    let node_range = super::schema::field("node_id").ok_or("The config schema has no node_id")?;
+    node_range.validate(&first_node_id.to_string())?;
+    node_range.validate(&last_node_id.to_string())?;
+    if first_node_id > last_node_id {
+        return Err("The first node ID is after the last".into());
+    }
+
+    let mut job = get_job().lock().map_err(|e| format!("provisioning lock error: {}", e))?;
+    if job.as_ref().is_some_and(|j| !matches!(j.status.state, JobState::Finished | JobState::Cancelled)) {
+        return Err("A provisioning job is already running".into());
+    }
+    let status = ProvisioningStatus {
+        profile: profile.name.clone(),
+        first_node_id,
+        last_node_id,
+        next_node_id: Some(first_node_id),
+        state: JobState::WaitingForDevice,
+        port: None,
+        message: prompt(Some(first_node_id)),
+        devices: Vec::new(),
+        manifest_path: None,
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    *job = Some(Job { status: status.clone(), cancel: cancel.clone() });
+    drop(job);
+
+    let baud = baud_rate.unwrap_or(DEFAULT_BAUD);
+    let reboot = reboot.unwrap_or(false);
+    thread::spawn(move || run(app, profile, (first_node_id, last_node_id), baud, reboot, cancel));
+    Ok(status)
+}
+
+// Stops after the current device; the manifest still covers what was done.
+#[tauri::command]
+pub fn cancel_provisioning() -> Result<(), String> {
+    let job = get_job().lock().map_err(|e| format!("provisioning lock error: {}", e))?;
+    if let Some(job) = job.as_ref() {
+        job.cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_provisioning_status() -> Result<Option<ProvisioningStatus>, String> {
+    let job = get_job().lock().map_err(|e| format!("provisioning lock error: {}", e))?;
+    Ok(job.as_ref().map(|j| j.status.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unplug_is_noticed_across_a_port_rename() {
+        let present = |entries: &[(&str, Option<&str>)]| -> Vec<(String, Option<String>)> {
+            entries.iter().map(|(name, serial)| (name.to_string(), serial.map(String::from))).collect()
+        };
+        let ports = vec!["/dev/ttyACM1".to_string(), "/dev/ttyACM2".to_string()];
+        // Rebooted from ACM1 onto ACM2 and still plugged in
+        let renamed = present(&[("/dev/ttyACM0", Some("RECEIVER")), ("/dev/ttyACM2", Some("E6614C311B4A"))]);
+        assert!(still_plugged_in(&renamed, &ports[..1], Some("E6614C311B4A")));
+        assert!(still_plugged_in(&renamed, &ports, None));
+        // Unplugged
+        let gone = present(&[("/dev/ttyACM0", Some("RECEIVER"))]);
+        assert!(!still_plugged_in(&gone, &ports[..1], Some("E6614C311B4A")));
+        assert!(!still_plugged_in(&gone, &ports, None));
+    }
+
+    #[test]
+    fn node_ids_advance_only_on_success() {
+        assert_eq!(next_node(3, 9, true), Some(4));
+        assert_eq!(next_node(3, 9, false), Some(3));
+        assert_eq!(next_node(9, 9, true), None);
+
+        let device = |serial: Option<&str>, node_id, ok| ProvisionedDevice {
+            serial_number: serial.map(String::from),
+            port: "/dev/ttyACM1".into(),
+            node_id,
+            ok,
+            error: None,
+            timestamp: String::new(),
+            report: None,
+        };
+        let status = ProvisioningStatus {
+            profile: "Club tracker".into(),
+            first_node_id: 0,
+            last_node_id: 9,
+            next_node_id: Some(2),
+            state: JobState::Finished,
+            port: None,
+            message: String::new(),
+            devices: vec![device(Some("E6614C311B4A"), 0, true), device(Some("E6614C311B4B"), 1, false), device(None, 1, true)],
+            manifest_path: None,
+        };
+        let manifest = build_manifest(&status);
+        assert_eq!(manifest.nodes.get("E6614C311B4A"), Some(&0));
+        assert_eq!(manifest.nodes.get("unknown (/dev/ttyACM1)"), Some(&1));
+        assert_eq!(manifest.nodes.len(), 2);
+        assert_eq!(manifest.devices.len(), 3);
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::verify::{self, KeyCheck, REBOOT_COMMAND};
+use super::{target_port, ConfigError};
+use crate::serial::{self, blocking, PortMode};
+
+pub(super) const DEFAULT_TIMEOUT_MS: u64 = 20_000;
+const POLL_MS: u64 = 250;
+// Boards behind a USB-UART bridge never disappear from the port list; after this long the port
+// is assumed to be the rebooted device even if it wasn't seen going away
+const SETTLE_MS: u64 = 1500;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RebootMethod {
+    // `kernel reboot cold` from the Zephyr shell
+    #[default]
+    Shell,
+    // Pulse RTS, for boards wired for auto-reset
+    DtrRts,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RebootStage {
+    Rebooting,
+    WaitingForDevice,
+    Reopened,
+    Ready,
+    Verified,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebootProgress {
+    pub port: String,
+    pub stage: RebootStage,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebootReport {
+    pub port: String,
+    // Where the device came back; a USB device can re-enumerate under another name
+    pub reopened_port: String,
+    pub method: RebootMethod,
+    pub elapsed_ms: u64,
+    pub checks: Option<Vec<KeyCheck>>,
+}
+
+// What identifies the physical device across a re-enumeration
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UsbIdentity {
+    vid: u16,
+    pid: u16,
+    serial_number: Option<String>,
+}
+
+fn usb_ports() -> Vec<(String, Option<UsbIdentity>)> {
+    serialport::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|port| {
+            let usb = match port.port_type {
+                serialport::SerialPortType::UsbPort(usb) => {
+                    Some(UsbIdentity { vid: usb.vid, pid: usb.pid, serial_number: usb.serial_number })
+                }
+                _ => None,
+            };
+            (port.port_name, usb)
+        })
+        .collect()
+}
+
+// The port the device is on now. Devices with a USB serial number are followed to whatever port
+// they come back on; without one only the original port name is trusted.
+fn find_device(ports: &[(String, Option<UsbIdentity>)], port: &str, device: Option<&UsbIdentity>) -> Option<String> {
+    match device {
+        Some(device) if device.serial_number.is_some() => {
+            ports.iter().find(|(_, usb)| usb.as_ref() == Some(device)).map(|(name, _)| name.clone())
+        }
+        Some(device) => ports
+            .iter()
+            .find(|(name, usb)| name == port && usb.as_ref().is_some_and(|u| u.vid == device.vid && u.pid == device.pid))
+            .map(|(name, _)| name.clone()),
+        None => ports.iter().find(|(name, _)| name == port).map(|(name, _)| name.clone()),
+    }
+}
+
+fn progress(app: &AppHandle, port: &str, stage: RebootStage, message: String) {
+    let _ = app.emit("device-reboot", RebootProgress { port: port.to_string(), stage, message });
+}
+
+// Reboots the device on `port` and reopens it wherever it comes back. Everything that reboots a
+// device goes through here, so the UI sees the port close and reopen through "device-reboot".
+pub(super) fn reboot(
+    app: &AppHandle,
+    port: &str,
+    method: RebootMethod,
+    expected: Option<&BTreeMap<String, String>>,
+    timeout_ms: u64,
+) -> Result<RebootReport, ConfigError> {
+    let result = reboot_and_reopen(app, port, method, expected, timeout_ms);
+    if let Err(e) = &result {
+        progress(app, port, RebootStage::Failed, e.to_string());
+    }
+    result
+}
+
+fn reboot_and_reopen(
+    app: &AppHandle,
+    port: &str,
+    method: RebootMethod,
+    expected: Option<&BTreeMap<String, String>>,
+    timeout_ms: u64,
+) -> Result<RebootReport, ConfigError> {
+    let started = Instant::now();
+    let deadline = started + Duration::from_millis(timeout_ms);
+    let write_error = |message: String| ConfigError::Write { message };
+    let baud = serial::port_baud_rate(port).ok_or_else(|| ConfigError::Port { message: "Port not open".into() })?;
+    let mode = serial::port_mode(port).unwrap_or(PortMode::Receiver);
+    let device = usb_ports().into_iter().find(|(name, _)| name == port).and_then(|(_, usb)| usb);
+
+    progress(app, port, RebootStage::Rebooting, format!("Rebooting the device on {port}"));
+    match method {
+        RebootMethod::Shell => serial::write_line(port, REBOOT_COMMAND).map_err(write_error)?,
+        RebootMethod::DtrRts => serial::pulse_reset(port).map_err(write_error)?,
+    }
+    // Let the command drain before the port goes away under it
+    thread::sleep(Duration::from_millis(200));
+    serial::close_port(Some(port.to_string())).map_err(write_error)?;
+
+    progress(app, port, RebootStage::WaitingForDevice, "Waiting for the device to come back".into());
+    let mut seen_gone = false;
+    let reopened = loop {
+        if Instant::now() >= deadline {
+            return Err(ConfigError::Timeout { command: format!("reopen {port}"), timeout_ms });
+        }
+        match find_device(&usb_ports(), port, device.as_ref()) {
+            None => seen_gone = true,
+            Some(found) if seen_gone || started.elapsed() >= Duration::from_millis(SETTLE_MS) => {
+                // The port can be listed a moment before it will open
+                if serial::open(app.clone(), found.clone(), baud, mode).is_ok() {
+                    break found;
+                }
+            }
+            Some(_) => {}
+        }
+        thread::sleep(Duration::from_millis(POLL_MS));
+    };
+    progress(app, &reopened, RebootStage::Reopened, format!("Reopened {reopened}"));
+
+    let remaining = deadline.saturating_duration_since(Instant::now()).as_millis() as u64;
+    verify::wait_for_shell(&reopened, remaining.max(POLL_MS))?;
+    progress(app, &reopened, RebootStage::Ready, "The device is back".into());
+
+    let checks = match expected {
+        Some(expected) => {
+            let checks = verify::verify(&reopened, expected, None)?;
+            progress(app, &reopened, RebootStage::Verified, format!("{} settings verified after reboot", checks.len()));
+            Some(checks)
+        }
+        None => None,
+    };
+    Ok(RebootReport {
+        port: port.to_string(),
+        reopened_port: reopened,
+        method,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        checks,
+    })
+}
+
+// Reboots the device, waits for the same USB device to re-enumerate and reopens it at the same
+// baud rate, optionally checking `expected` settings afterwards. Progress is reported through
+// "device-reboot" events.
+#[tauri::command]
+pub async fn reboot_device(
+    app: AppHandle,
+    port_name: Option<String>,
+    method: Option<RebootMethod>,
+    expected: Option<BTreeMap<String, String>>,
+    timeout_ms: Option<u64>,
+) -> Result<RebootReport, ConfigError> {
+    blocking(move || {
+        let port = target_port(port_name)?;
+        reboot(&app, &port, method.unwrap_or_default(), expected.as_ref(), timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebooted_device_is_found_by_usb_identity() {
+        let usb = |serial: Option<&str>| Some(UsbIdentity { vid: 0x2fe3, pid: 0x0100, serial_number: serial.map(String::from) });
+        let ports = vec![
+            ("/dev/ttyACM0".to_string(), usb(Some("RECEIVER"))),
+            ("/dev/ttyACM2".to_string(), usb(Some("E6614C311B4A"))),
+            ("/dev/ttyS0".to_string(), None),
+        ];
+        // Came back under a new name
+        assert_eq!(find_device(&ports, "/dev/ttyACM1", usb(Some("E6614C311B4A")).as_ref()).as_deref(), Some("/dev/ttyACM2"));
+        assert_eq!(find_device(&ports, "/dev/ttyACM1", usb(Some("OTHER")).as_ref()), None);
+        // No serial number: only the same port name will do
+        assert_eq!(find_device(&ports, "/dev/ttyACM0", usb(None).as_ref()).as_deref(), Some("/dev/ttyACM0"));
+        assert_eq!(find_device(&ports, "/dev/ttyACM1", usb(None).as_ref()), None);
+        assert_eq!(find_device(&ports, "/dev/ttyS0", None).as_deref(), Some("/dev/ttyS0"));
+    }
+}
@@ -0,0 +1,278 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+use super::DeviceType;
+
+lazy_static! {
+    // ITU amateur callsign: a one or two character prefix, a digit, then a letter suffix
+    static ref RE_CALLSIGN: Regex = Regex::new(r"^(?:[A-Z]{1,2}|[A-Z][0-9]|[0-9][A-Z])[0-9][A-Z]{1,4}$").unwrap();
+    static ref RE_VERSION: Regex = Regex::new(r"(\d+)\.(\d+)(?:\.(\d+))?").unwrap();
+
+    static ref FIELDS: Vec<FieldSpec> = vec![
+        FieldSpec {
+            key: "freq",
+            label: "Frequency (MHz)",
+            hint: "410 - 450 or 902 - 928 MHz",
+            device_type: DeviceType::Tracker,
+            placeholder: "e.g. 433.920000 or 903.123456",
+            kind: FieldKind::Number { ranges: &[[410.0, 450.0], [902.0, 928.0]], decimals: 6, unit: "MHz" },
+            since: None,
+        },
+        FieldSpec {
+            key: "node_id",
+            label: "Node ID",
+            hint: "0 - 9",
+            device_type: DeviceType::Tracker,
+            placeholder: "0 - 9",
+            kind: FieldKind::Integer { min: 0, max: 9, unit: "" },
+            since: None,
+        },
+        FieldSpec {
+            key: "callsign",
+            label: "Callsign",
+            hint: "Licensed operators only",
+            device_type: DeviceType::Tracker,
+            placeholder: "e.g. KD2YIE",
+            kind: FieldKind::Callsign { max_length: 6 },
+            since: Some((1, 1, 0)),
+        },
+        FieldSpec {
+            key: "mode",
+            label: "Deploy Mode",
+            hint: "dual_deploy | drogue_only | main_only",
+            device_type: DeviceType::Marshal,
+            placeholder: "",
+            kind: FieldKind::Choice {
+                options: &[
+                    ChoiceOption { label: "Dual Deploy", value: "dual_deploy" },
+                    ChoiceOption { label: "Drogue Only", value: "drogue_only" },
+                    ChoiceOption { label: "Main Only", value: "main_only" },
+                ],
+            },
+            since: None,
+        },
+        FieldSpec {
+            key: "main_alt",
+            label: "Main Deploy Altitude (ft)",
+            hint: "0 - 30000 ft AGL",
+            device_type: DeviceType::Marshal,
+            placeholder: "e.g. 500",
+            kind: FieldKind::Integer { min: 0, max: 30_000, unit: "ft" },
+            since: None,
+        },
+        FieldSpec {
+            key: "arm_alt",
+            label: "Arming Altitude (ft)",
+            hint: "0 - 3000 ft AGL",
+            device_type: DeviceType::Marshal,
+            placeholder: "e.g. 100",
+            kind: FieldKind::Integer { min: 0, max: 3_000, unit: "ft" },
+            since: None,
+        },
+        FieldSpec {
+            key: "apogee_delay",
+            label: "Apogee Delay (ms)",
+            hint: "0 - 30000 ms",
+            device_type: DeviceType::Marshal,
+            placeholder: "e.g. 0",
+            kind: FieldKind::Integer { min: 0, max: 30_000, unit: "ms" },
+            since: Some((1, 1, 0)),
+        },
+        FieldSpec {
+            key: "bat_min",
+            label: "Min Battery (mV)",
+            hint: "0 - 10000 mV",
+            device_type: DeviceType::Marshal,
+            placeholder: "e.g. 3300",
+            kind: FieldKind::Integer { min: 0, max: 10_000, unit: "mV" },
+            since: Some((1, 2, 0)),
+        },
+    ];
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChoiceOption {
+    pub label: &'static str,
+    pub value: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum FieldKind {
+    // Decimal within any one of the ranges, with at most `decimals` places
+    Number { ranges: &'static [[f64; 2]], decimals: usize, unit: &'static str },
+    Integer { min: i64, max: i64, unit: &'static str },
+    // Written in upper case whatever the user typed
+    Callsign { max_length: usize },
+    Choice { options: &'static [ChoiceOption] },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldSpec {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub hint: &'static str,
+    pub device_type: DeviceType,
+    pub placeholder: &'static str,
+    pub kind: FieldKind,
+    // First firmware version with the setting; None for ones every firmware has
+    #[serde(skip)]
+    pub since: Option<(u32, u32, u32)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSchema {
+    pub device_type: DeviceType,
+    pub firmware: Option<String>,
+    pub fields: Vec<FieldSpec>,
+}
+
+// "v1.2.3", "1.2" or "zephyr-v1.2.3-g1a2b3c"
+pub fn parse_version(text: &str) -> Option<(u32, u32, u32)> {
+    let cap = RE_VERSION.captures(text)?;
+    let part = |i: usize| cap.get(i).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
+    Some((part(1), part(2), part(3)))
+}
+
+// The settings a device type supports. Unknown or unparseable firmware versions get every field.
+pub fn schema_for(device_type: DeviceType, firmware: Option<&str>) -> ConfigSchema {
+    let version = firmware.and_then(parse_version);
+    let fields = FIELDS
+        .iter()
+        .filter(|f| f.device_type == device_type)
+        .filter(|f| match (f.since, version) {
+            (Some(since), Some(version)) => version >= since,
+            _ => true,
+        })
+        .cloned()
+        .collect();
+    ConfigSchema { device_type, firmware: firmware.map(String::from), fields }
+}
+
+pub fn keys() -> impl Iterator<Item = &'static str> {
+    FIELDS.iter().map(|f| f.key)
+}
+
+pub fn field(key: &str) -> Option<&'static FieldSpec> {
+    FIELDS.iter().find(|f| f.key == key)
+}
+
+impl FieldSpec {
+    // Checks a value against the field's rules, returning it as it should be sent to the device.
+    pub fn validate(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(format!("{}: a value is required", self.key));
+        }
+        match &self.kind {
+            FieldKind::Number { ranges, decimals, unit } => {
+                let number: f64 = value.parse().map_err(|_| format!("{}: {value:?} is not a number", self.key))?;
+                if value.split_once('.').is_some_and(|(_, fraction)| fraction.len() > *decimals) {
+                    return Err(format!("{}: at most {decimals} decimal places", self.key));
+                }
+                if !ranges.iter().any(|[min, max]| (*min..=*max).contains(&number)) {
+                    let allowed: Vec<String> = ranges.iter().map(|[min, max]| format!("{min} - {max}")).collect();
+                    return Err(format!("{}: {number} {unit} is outside {} {unit}", self.key, allowed.join(" or ")));
+                }
+                // Written out in full, so "+4.3392e2" reaches the device as 433.920000
+                Ok(format!("{number:.decimals$}"))
+            }
+            FieldKind::Integer { min, max, unit } => {
+                let number: i64 = value.parse().map_err(|_| format!("{}: {value:?} is not a whole number", self.key))?;
+                if !(*min..=*max).contains(&number) {
+                    return Err(format!("{}: {number} is outside {min} - {max} {unit}", self.key).trim_end().to_string());
+                }
+                Ok(number.to_string())
+            }
+            FieldKind::Callsign { max_length } => {
+                let callsign = value.to_ascii_uppercase();
+                if callsign.len() > *max_length {
+                    return Err(format!("{}: at most {max_length} characters", self.key));
+                }
+                if !RE_CALLSIGN.is_match(&callsign) {
+                    return Err(format!("{}: {value:?} is not a valid amateur callsign", self.key));
+                }
+                Ok(callsign)
+            }
+            FieldKind::Choice { options } => match options.iter().find(|o| o.value.eq_ignore_ascii_case(value)) {
+                Some(option) => Ok(option.value.to_string()),
+                None => {
+                    let allowed: Vec<&str> = options.iter().map(|o| o.value).collect();
+                    Err(format!("{}: {value:?} is not one of {}", self.key, allowed.join(", ")))
+                }
+            },
+        }
+    }
+}
+
+// Whether a value read back from the device is the one that was written, allowing for the
+// device formatting numbers its own way ("433.92" vs "433.920000").
+pub fn same_value(key: &str, expected: &str, actual: &str) -> bool {
+    let (expected, actual) = (expected.trim(), actual.trim());
+    match field(key).map(|f| &f.kind) {
+        Some(FieldKind::Number { decimals, .. }) => match (expected.parse::<f64>(), actual.parse::<f64>()) {
+            (Ok(e), Ok(a)) => (e - a).abs() < 0.5 * 10f64.powi(-(*decimals as i32)),
+            _ => false,
+        },
+        Some(FieldKind::Integer { .. }) => matches!((expected.parse::<i64>(), actual.parse::<i64>()), (Ok(e), Ok(a)) if e == a),
+        Some(FieldKind::Callsign { .. } | FieldKind::Choice { .. }) => expected.eq_ignore_ascii_case(actual),
+        None => expected == actual,
+    }
+}
+
+// Validates a setting before it is written. Device-specific settings outside the schema pass
+// through unchecked.
+pub fn validate(key: &str, value: &str) -> Result<String, String> {
+    match field(key) {
+        Some(spec) => spec.validate(value),
+        None => Ok(value.trim().to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn config_schema(device_type: DeviceType, firmware: Option<String>) -> ConfigSchema {
+    schema_for(device_type, firmware.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_checked_against_the_schema() {
+        assert_eq!(validate("freq", "433.920000"), Ok("433.920000".into()));
+        assert_eq!(validate("freq", "903.5"), Ok("903.500000".into()));
+        assert_eq!(validate("freq", "+4.3392e2"), Ok("433.920000".into()));
+        assert!(validate("freq", "500").is_err());
+        assert!(validate("freq", "433.1234567").is_err());
+        assert_eq!(validate("node_id", " 7 "), Ok("7".into()));
+        assert!(validate("node_id", "10").is_err());
+        assert!(validate("node_id", "1.5").is_err());
+        assert_eq!(validate("callsign", "kd2yie"), Ok("KD2YIE".into()));
+        assert!(validate("callsign", "HELLO").is_err());
+        assert!(validate("callsign", "KD2YIEX").is_err());
+        assert_eq!(validate("mode", "Drogue_Only"), Ok("drogue_only".into()));
+        assert!(validate("mode", "apogee").is_err());
+        assert!(validate("main_alt", "30001").is_err());
+        assert!(validate("arm_alt", "-1").is_err());
+        assert_eq!(validate("hunter_gain", "12 dB"), Ok("12 dB".into()));
+
+        let keys = |firmware| schema_for(DeviceType::Marshal, firmware).fields.iter().map(|f| f.key).collect::<Vec<_>>();
+        assert_eq!(keys(Some("v1.2.0")), vec!["mode", "main_alt", "arm_alt", "apogee_delay", "bat_min"]);
+        assert_eq!(keys(Some("zephyr-v1.1.4-g1a2b3c")), vec!["mode", "main_alt", "arm_alt", "apogee_delay"]);
+        assert_eq!(keys(Some("v1.0.2")), vec!["mode", "main_alt", "arm_alt"]);
+        assert_eq!(keys(None).len(), 5);
+        let tracker = schema_for(DeviceType::Tracker, Some("1.0"));
+        assert!(!tracker.fields.iter().any(|f| f.key == "callsign"));
+        assert_eq!(parse_version("zephyr-v2.10.1-g1a2b3c"), Some((2, 10, 1)));
+
+        assert!(same_value("freq", "433.92", "433.920000"));
+        assert!(!same_value("freq", "433.92", "433.920001"));
+        assert!(same_value("node_id", "4", "04"));
+        assert!(!same_value("callsign", "KD2YIE", "KD2YIF"));
+    }
+}
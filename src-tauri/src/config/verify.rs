@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::reboot::{self, RebootMethod};
+use super::{exchange, get, schema, target_port, ConfigError};
+use crate::serial::blocking;
+
+pub const REBOOT_COMMAND: &str = "kernel reboot cold";
+// How long each "are you back yet" probe waits while the device restarts
+const REBOOT_POLL_MS: u64 = 1000;
+
+// Expected against actual for one setting
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyCheck {
+    pub key: String,
+    pub expected: String,
+    // What the device reported; None when it couldn't be read back
+    pub actual: Option<String>,
+    pub matches: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyReport {
+    pub port: String,
+    // Where the device came back after the reboot, which can be a new name
+    pub reopened_port: Option<String>,
+    pub rebooted: bool,
+    pub checks: Vec<KeyCheck>,
+}
+
+// Reads one setting back. Failing to read it is a failed check rather than an error, so the rest
+// of a batch still gets checked; only losing the port stops the run.
+pub fn check_key(port: &str, key: &str, expected: &str, timeout_ms: Option<u64>) -> Result<KeyCheck, ConfigError> {
+    let expected = schema::validate(key, expected).unwrap_or_else(|_| expected.trim().to_string());
+    let (actual, error) = match get(port, key, timeout_ms) {
+        Ok(value) => (value.value, None),
+        Err(e) if e.is_fatal() => return Err(e),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let matches = actual.as_deref().is_some_and(|actual| schema::same_value(key, &expected, actual));
+    Ok(KeyCheck { key: key.to_string(), expected, actual, matches, error })
+}
+
+pub fn read_back(port: &str, expected: &BTreeMap<String, String>, timeout_ms: Option<u64>) -> Result<Vec<KeyCheck>, ConfigError> {
+    expected.iter().map(|(key, value)| check_key(port, key, value, timeout_ms)).collect()
+}
+
+pub fn verify(port: &str, expected: &BTreeMap<String, String>, timeout_ms: Option<u64>) -> Result<Vec<KeyCheck>, ConfigError> {
+    let checks = read_back(port, expected, timeout_ms)?;
+    if checks.iter().any(|c| !c.matches) {
+        return Err(ConfigError::Mismatch { checks });
+    }
+    Ok(checks)
+}
+
+// Polls the shell until the device answers, e.g. while it boots.
+pub fn wait_for_shell(port: &str, timeout_ms: u64) -> Result<(), ConfigError> {
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    while Instant::now() < deadline {
+        match exchange(port, "config", Some(REBOOT_POLL_MS)) {
+            Ok(_) => return Ok(()),
+            Err(ConfigError::Timeout { .. }) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(ConfigError::Timeout { command: REBOOT_COMMAND.into(), timeout_ms })
+}
+
+// Reads `expected` back from the device, optionally after rebooting it so settings are read from
+// flash rather than what the running firmware holds in memory. Any difference is a Mismatch error
+// carrying the whole diff.
+#[tauri::command]
+pub async fn config_verify(
+    app: AppHandle,
+    port_name: Option<String>,
+    expected: BTreeMap<String, String>,
+    reboot: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<VerifyReport, ConfigError> {
+    blocking(move || {
+        let port = target_port(port_name)?;
+        let rebooted = reboot.unwrap_or(false);
+        let reopened_port = match rebooted {
+            true => Some(reboot::reboot(&app, &port, RebootMethod::Shell, None, reboot::DEFAULT_TIMEOUT_MS)?.reopened_port),
+            false => None,
+        };
+        let checks = verify(reopened_port.as_deref().unwrap_or(&port), &expected, timeout_ms)?;
+        Ok(VerifyReport { port, reopened_port, rebooted, checks })
+    })
+    .await
+}
@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::config;
+use crate::deputy_interpreter::RE_LOG_UPTIME;
+use crate::serial::{self, blocking};
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+// Without a prompt to mark the end, a response is over once the shell has been quiet this long.
+// Longer than the serial reader's read timeout, which is what hands over a waiting prompt.
+const QUIET_MS: u64 = 1000;
+const HISTORY_LIMIT: usize = 500;
+const HISTORY_DIR: &str = "console-history";
+
+lazy_static! {
+    // CSI ("\x1b[2K", "\x1b[1;32m"), OSC titles, and the two-byte escapes such as charset selects
+    static ref RE_VT100: Regex = Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[()][0-9A-Za-z]|\x1b[@-Z\\-_]").unwrap();
+    // Zephyr's "uart:~$ ", with whatever backend name and path it was built with
+    static ref RE_PROMPT: Regex = Regex::new(r"^([\w-]+:[~/\w.-]*\$)\s?").unwrap();
+    static ref RE_HELP_SECTION: Regex = Regex::new(r"(?i)^(available commands|subcommands):$").unwrap();
+    static ref RE_HELP_ENTRY: Regex = Regex::new(r"^\s+([\w-]+)\s*:").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShellEvent {
+    Prompt(String),
+    Line(String),
+}
+
+struct Session {
+    app: AppHandle,
+    device: String,
+    prompt: Option<String>,
+    history: Vec<String>,
+    // Command names from `help`, and subcommands from `<command> -h`, keyed by the command path
+    commands: HashMap<String, Vec<String>>,
+    pending: Option<Sender<ShellEvent>>,
+    next_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleInfo {
+    pub port: String,
+    // What the history is kept under: the device's serial number where it has one
+    pub device: String,
+    pub prompt: Option<String>,
+    pub history: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleResponse {
+    pub id: u64,
+    pub port: String,
+    pub command: String,
+    pub lines: Vec<String>,
+    pub prompt: Option<String>,
+    // False when the response was cut off by silence rather than the prompt coming back
+    pub complete: bool,
+    pub elapsed_ms: u64,
+}
+
+// Output that isn't part of any command's response, such as log messages while idle
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleOutput {
+    pub port: String,
+    pub text: String,
+}
+
+static SESSIONS: OnceLock<Mutex<HashMap<String, Session>>> = OnceLock::new();
+
+fn get_sessions() -> &'static Mutex<HashMap<String, Session>> {
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// What a terminal would show for the line: escape sequences removed, carriage returns and
+// backspaces applied, other control characters dropped.
+pub fn clean_line(raw: &str) -> String {
+    let text = RE_VT100.replace_all(raw, "");
+    let text = text.trim_end_matches(['\r', '\n']);
+    let text = text.rsplit('\r').next().unwrap_or_default();
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{8}' => {
+                out.pop();
+            }
+            '\t' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn deliver(port: &str, event: ShellEvent) {
+    let Ok(mut sessions) = get_sessions().lock() else { return };
+    let Some(session) = sessions.get_mut(port) else { return };
+    if let ShellEvent::Prompt(prompt) = &event {
+        session.prompt = Some(prompt.clone());
+    }
+    match (&session.pending, event) {
+        (Some(tx), event) => {
+            let _ = tx.send(event);
+        }
+        (None, ShellEvent::Line(text)) => {
+            let _ = session.app.emit("console-output", ConsoleOutput { port: port.to_string(), text });
+        }
+        (None, ShellEvent::Prompt(_)) => {}
+    }
+}
+
+// Every full line from a port. The shell prompt can arrive glued to the echo of a command.
+pub fn observe_line(port: &str, line: &str) {
+    let text = clean_line(line);
+    match RE_PROMPT.captures(&text) {
+        Some(cap) => {
+            let rest = text[cap[0].len()..].trim().to_string();
+            deliver(port, ShellEvent::Prompt(cap[1].to_string()));
+            if !rest.is_empty() {
+                deliver(port, ShellEvent::Line(rest));
+            }
+        }
+        None => deliver(port, ShellEvent::Line(text)),
+    }
+}
+
+// A partial line left when the serial read timed out. The shell prints its prompt without a
+// newline, so this is how we learn it's waiting; returns whether the text was a prompt.
+pub fn observe_partial(port: &str, text: &str) -> bool {
+    let text = clean_line(text);
+    match RE_PROMPT.captures(&text) {
+        Some(cap) if cap[0].len() == text.len() || text[cap[0].len()..].trim().is_empty() => {
+            deliver(port, ShellEvent::Prompt(cap[1].to_string()));
+            true
+        }
+        _ => false,
+    }
+}
+
+// Gathers one command's output: what follows the shell's echo of it until the prompt returns.
+// Zephyr log messages that land in the middle are passed on as ordinary output.
+fn collect_response(events: &Receiver<ShellEvent>, command: &str, timeout: Duration, mut log: impl FnMut(String)) -> Result<(Vec<String>, bool), String> {
+    let deadline = Instant::now() + timeout;
+    let mut lines = Vec::new();
+    let mut heard = false;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        let wait = if heard { Duration::from_millis(QUIET_MS).min(deadline - now) } else { deadline - now };
+        match events.recv_timeout(wait) {
+            // A prompt before anything else is the one the command was typed at
+            Ok(ShellEvent::Prompt(_)) if heard => return Ok((lines, true)),
+            Ok(ShellEvent::Prompt(_)) => {}
+            Ok(ShellEvent::Line(text)) if !heard && text == command => heard = true,
+            Ok(ShellEvent::Line(text)) if RE_LOG_UPTIME.is_match(&text) => log(text),
+            Ok(ShellEvent::Line(text)) => {
+                heard = true;
+                lines.push(text);
+            }
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    if !heard {
+        return Err(format!("No response to \"{command}\" within {} ms", timeout.as_millis()));
+    }
+    Ok((lines, false))
+}
+
+// Command names listed under "Available commands:" (help) or "Subcommands:" (-h)
+pub fn parse_help(lines: &[String]) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut listing = false;
+    for line in lines {
+        if RE_HELP_SECTION.is_match(line.trim()) {
+            listing = true;
+        } else if let Some(cap) = RE_HELP_ENTRY.captures(line).filter(|_| listing) {
+            names.push(cap[1].to_string());
+        } else {
+            listing = false;
+        }
+    }
+    names
+}
+
+// Candidates for the word being typed at the end of `input`, as whole command lines
+fn candidates(input: &str, names: &[String]) -> Vec<String> {
+    let (head, word) = match input.rfind(' ') {
+        Some(i) => (&input[..=i], &input[i + 1..]),
+        None => ("", input),
+    };
+    names.iter().filter(|name| name.starts_with(word)).map(|name| format!("{head}{name}")).collect()
+}
+
+fn history_path(app: &AppHandle, device: &str) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("No data directory: {e}"))?;
+    Ok(dir.join(HISTORY_DIR).join(format!("{device}.json")))
+}
+
+fn load_history(app: &AppHandle, device: &str) -> Vec<String> {
+    history_path(app, device)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(app: &AppHandle, device: &str, history: &[String]) -> Result<(), String> {
+    let path = history_path(app, device)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create history directory: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(history).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to save console history: {e}"))
+}
+
+// Keeps history with the hardware rather than whichever port it happens to be on
+fn device_key(port: &str) -> String {
+    let usb_serial = || {
+        serialport::available_ports().ok()?.into_iter().find(|p| p.port_name == port).and_then(|p| match p.port_type {
+            serialport::SerialPortType::UsbPort(usb) => usb.serial_number,
+            _ => None,
+        })
+    };
+    let key = config::identity::cached(port)
+        .and_then(|d| d.serial_number)
+        .or_else(usb_serial)
+        .unwrap_or_else(|| port.to_string());
+    key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+// Runs one command on the port's session, holding it until the response is in.
+fn run(port: &str, command: &str, timeout: Duration) -> Result<ConsoleResponse, String> {
+    let (rx, id, app) = {
+        let mut sessions = get_sessions().lock().map_err(|e| format!("console lock error: {}", e))?;
+        let session = sessions.get_mut(port).ok_or("No console is open on this port")?;
+        if session.pending.is_some() {
+            return Err("The console is still waiting on the previous command".into());
+        }
+        let (tx, rx) = mpsc::channel();
+        session.pending = Some(tx);
+        session.next_id += 1;
+        (rx, session.next_id, session.app.clone())
+    };
+    let started = Instant::now();
+    let result = serial::write_line(port, command).and_then(|_| {
+        collect_response(&rx, command, timeout, |text| {
+            let _ = app.emit("console-output", ConsoleOutput { port: port.to_string(), text });
+        })
+    });
+
+    let mut sessions = get_sessions().lock().map_err(|e| format!("console lock error: {}", e))?;
+    let session = sessions.get_mut(port).ok_or("The console was closed")?;
+    session.pending = None;
+    let (lines, complete) = result?;
+    Ok(ConsoleResponse {
+        id,
+        port: port.to_string(),
+        command: command.to_string(),
+        lines,
+        prompt: session.prompt.clone(),
+        complete,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+// Subcommand names below `path` ("" for the top level), asked of the shell once per session
+fn command_names(port: &str, path: &str) -> Result<Vec<String>, String> {
+    let cached = get_sessions().lock().ok().and_then(|s| s.get(port).and_then(|s| s.commands.get(path).cloned()));
+    if let Some(names) = cached {
+        return Ok(names);
+    }
+    let command = if path.is_empty() { "help".to_string() } else { format!("{path} -h") };
+    let names = parse_help(&run(port, &command, Duration::from_millis(DEFAULT_TIMEOUT_MS))?.lines);
+    if let Ok(mut sessions) = get_sessions().lock() {
+        if let Some(session) = sessions.get_mut(port) {
+            session.commands.insert(path.to_string(), names.clone());
+        }
+    }
+    Ok(names)
+}
+
+pub fn close(port: &str) {
+    if let Ok(mut sessions) = get_sessions().lock() {
+        sessions.remove(port);
+    }
+}
+
+// Starts a console session on an open port. Output not belonging to a command arrives as
+// "console-output" events.
+#[tauri::command]
+pub fn console_open(app: AppHandle, port_name: Option<String>) -> Result<ConsoleInfo, String> {
+    let port = serial::resolve_port(port_name)?;
+    let device = device_key(&port);
+    let history = load_history(&app, &device);
+    let mut sessions = get_sessions().lock().map_err(|e| format!("console lock error: {}", e))?;
+    let session = sessions.entry(port.clone()).or_insert_with(|| Session {
+        app: app.clone(),
+        device: device.clone(),
+        prompt: None,
+        history,
+        commands: HashMap::new(),
+        pending: None,
+        next_id: 0,
+    });
+    Ok(ConsoleInfo { port, device: session.device.clone(), prompt: session.prompt.clone(), history: session.history.clone() })
+}
+
+#[tauri::command]
+pub fn console_close(port_name: Option<String>) -> Result<(), String> {
+    close(&serial::resolve_port(port_name)?);
+    Ok(())
+}
+
+// Runs a command and returns its output, remembering it in the device's history.
+#[tauri::command]
+pub async fn console_send(port_name: Option<String>, command: String, timeout_ms: Option<u64>) -> Result<ConsoleResponse, String> {
+    blocking(move || {
+        let port = serial::resolve_port(port_name)?;
+        let command = command.trim().to_string();
+        let (app, device, history) = {
+            let mut sessions = get_sessions().lock().map_err(|e| format!("console lock error: {}", e))?;
+            let session = sessions.get_mut(&port).ok_or("No console is open on this port")?;
+            if !command.is_empty() && session.history.last() != Some(&command) {
+                session.history.push(command.clone());
+                let excess = session.history.len().saturating_sub(HISTORY_LIMIT);
+                session.history.drain(..excess);
+            }
+            (session.app.clone(), session.device.clone(), session.history.clone())
+        };
+        save_history(&app, &device, &history)?;
+        run(&port, &command, Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS)))
+    })
+    .await
+}
+
+// Completions for a partly typed command line, from the shell's own help.
+#[tauri::command]
+pub async fn console_complete(port_name: Option<String>, input: String) -> Result<Vec<String>, String> {
+    blocking(move || {
+        let port = serial::resolve_port(port_name)?;
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let typed_words = if input.ends_with(' ') { words.len() } else { words.len().saturating_sub(1) };
+        let path = words[..typed_words].join(" ");
+        let names = command_names(&port, &path)?;
+        let input = if typed_words == words.len() { format!("{path} ") } else { input.trim_start().to_string() };
+        Ok(candidates(input.trim_start(), &names))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_output_is_cleaned_and_split() {
+        assert_eq!(clean_line("\x1b[1;32muart:~$ \x1b[mconfig freq\r\n"), "uart:~$ config freq");
+        assert_eq!(clean_line("\x1b[2K\rprogress 10%\rprogress 100%"), "progress 100%");
+        assert_eq!(clean_line("confx\x08ig\x07"), "config");
+
+        let (tx, rx) = mpsc::channel();
+        for event in [
+            ShellEvent::Prompt("uart:~$".into()),
+            ShellEvent::Line("kernel version".into()),
+            ShellEvent::Line("[00:00:05.100,000] <inf> gnss: fix acquired".into()),
+            ShellEvent::Line("Zephyr version 3.7.0".into()),
+            ShellEvent::Prompt("uart:~$".into()),
+            ShellEvent::Line("left for the next command".into()),
+        ] {
+            tx.send(event).unwrap();
+        }
+        let mut logged = Vec::new();
+        let (lines, complete) = collect_response(&rx, "kernel version", Duration::from_secs(1), |text| logged.push(text)).unwrap();
+        assert_eq!(lines, vec!["Zephyr version 3.7.0"]);
+        assert!(complete);
+        assert_eq!(logged.len(), 1);
+
+        let help: Vec<String> = [
+            "Please press the <Tab> button to see all available commands.",
+            "",
+            "Available commands:",
+            "  clear    :Clear screen.",
+            "  config   :Device configuration",
+            "  kernel   :Kernel commands",
+            "",
+            "Subcommands:",
+            "  freq  :Frequency",
+        ]
+        .map(String::from)
+        .to_vec();
+        let names = parse_help(&help);
+        assert_eq!(names, vec!["clear", "config", "kernel", "freq"]);
+        assert_eq!(candidates("c", &names[..3]), vec!["clear", "config"]);
+        assert_eq!(candidates("config f", &names[3..]), vec!["config freq"]);
+    }
+}
@@ -0,0 +1,621 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use rusqlite::types::ToSql;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::serial;
+use crate::session;
+use crate::telemetry::DataPacket;
+use crate::tracker;
+
+const DB_FILE: &str = "dispatch.db";
+
+// Applied in order; PRAGMA user_version records how many have run. Never edit a shipped entry,
+// append a new one instead.
+const MIGRATIONS: [&str; 1] = [r#"
+    CREATE TABLE sessions (
+        id INTEGER PRIMARY KEY,
+        name TEXT,
+        notes TEXT,
+        app_version TEXT NOT NULL,
+        started_ms INTEGER NOT NULL,
+        ended_ms INTEGER,
+        archived INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE session_ports (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        port TEXT NOT NULL,
+        baud_rate INTEGER NOT NULL,
+        opened_ms INTEGER NOT NULL,
+        closed_ms INTEGER
+    );
+    CREATE TABLE packets (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        node TEXT NOT NULL,
+        timestamp_ms INTEGER NOT NULL,
+        latitude REAL,
+        longitude REAL,
+        altitude_ft INTEGER,
+        satellites INTEGER,
+        fix_status TEXT NOT NULL,
+        rssi INTEGER,
+        snr INTEGER,
+        rejected TEXT,
+        packet TEXT NOT NULL
+    );
+    CREATE INDEX packets_by_session_node_time ON packets (session_id, node, timestamp_ms);
+    CREATE TABLE raw_lines (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        timestamp_ms INTEGER NOT NULL,
+        port TEXT NOT NULL,
+        line TEXT NOT NULL
+    );
+    CREATE INDEX raw_lines_by_session ON raw_lines (session_id, timestamp_ms);
+    CREATE TABLE events (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        timestamp_ms INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        node TEXT,
+        message TEXT NOT NULL,
+        detail TEXT
+    );
+    CREATE TABLE config_changes (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        timestamp_ms INTEGER NOT NULL,
+        port TEXT,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+"#];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub id: i64,
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    pub started_ms: i64,
+    pub ended_ms: Option<i64>,
+    pub archived: bool,
+    pub packet_count: i64,
+    pub nodes: Vec<String>,
+    pub ports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PacketFilter {
+    // None searches every session
+    pub session_id: Option<i64>,
+    pub nodes: Option<Vec<String>>,
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    // Fix labels as shown in the UI: FIX, DIFF, EST, NOFIX, UNKNOWN
+    pub fix_statuses: Option<Vec<String>>,
+    pub min_satellites: Option<u8>,
+    pub exclude_rejected: Option<bool>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionEvent {
+    pub timestamp_ms: i64,
+    pub kind: String,
+    pub node: Option<String>,
+    pub message: String,
+    pub detail: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChange {
+    pub timestamp_ms: i64,
+    pub port: Option<String>,
+    pub key: String,
+    pub value: String,
+}
+
+// Everything recorded with a session besides its packets
+#[derive(Debug, Clone, Default)]
+pub struct SessionHistory {
+    // (timestamp, port, line)
+    pub lines: Vec<(i64, String, String)>,
+    pub events: Vec<SessionEvent>,
+    pub config_changes: Vec<ConfigChange>,
+}
+
+struct Store {
+    conn: Connection,
+    // Session being recorded, if any port is open
+    active: Option<i64>,
+    // Session the session store currently holds: the one being recorded, or one loaded or imported
+    shown: Option<i64>,
+    open_ports: BTreeSet<String>,
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+impl Store {
+    fn new(mut conn: Connection) -> rusqlite::Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn)?;
+        // Sessions left open by a crash end at their last packet
+        conn.execute(
+            "UPDATE sessions SET ended_ms = COALESCE(
+                (SELECT MAX(timestamp_ms) FROM packets WHERE session_id = sessions.id), started_ms)
+             WHERE ended_ms IS NULL",
+            [],
+        )?;
+        Ok(Store { conn, active: None, shown: None, open_ports: BTreeSet::new() })
+    }
+
+    fn port_opened(&mut self, port: &str, baud_rate: u32, now_ms: i64) -> rusqlite::Result<()> {
+        let session_id = match self.active {
+            Some(id) => id,
+            None => {
+                self.conn.execute(
+                    "INSERT INTO sessions (app_version, started_ms) VALUES (?1, ?2)",
+                    params![env!("CARGO_PKG_VERSION"), now_ms],
+                )?;
+                let id = self.conn.last_insert_rowid();
+                self.active = Some(id);
+                self.shown = Some(id);
+                id
+            }
+        };
+        self.open_ports.insert(port.to_string());
+        self.conn.execute(
+            "INSERT INTO session_ports (session_id, port, baud_rate, opened_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, port, baud_rate, now_ms],
+        )?;
+        Ok(())
+    }
+
+    fn port_closed(&mut self, port: &str, now_ms: i64) -> rusqlite::Result<()> {
+        let Some(session_id) = self.active else {
+            return Ok(());
+        };
+        self.open_ports.remove(port);
+        self.conn.execute(
+            "UPDATE session_ports SET closed_ms = ?1 WHERE session_id = ?2 AND port = ?3 AND closed_ms IS NULL",
+            params![now_ms, session_id, port],
+        )?;
+        if self.open_ports.is_empty() {
+            self.conn.execute("UPDATE sessions SET ended_ms = ?1 WHERE id = ?2", params![now_ms, session_id])?;
+            self.active = None;
+        }
+        Ok(())
+    }
+
+    fn insert_packet(&self, session_id: i64, pkt: &DataPacket) -> Result<(), Box<dyn std::error::Error>> {
+        let rejected = pkt.filtered.as_ref().and_then(|f| f.rejected).map(|r| format!("{r:?}"));
+        self.conn.execute(
+            "INSERT INTO packets (session_id, node, timestamp_ms, latitude, longitude, altitude_ft, satellites,
+                                  fix_status, rssi, snr, rejected, packet)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                session_id,
+                pkt.node_key(),
+                pkt.timestamp_ms,
+                pkt.latitude.map(f64::from),
+                pkt.longitude.map(f64::from),
+                pkt.altitude_ft,
+                pkt.satellites_count,
+                pkt.fix_status.label(),
+                pkt.receiver_rssi,
+                pkt.receiver_snr,
+                rejected,
+                serde_json::to_string(pkt)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn summary(&self, id: i64) -> rusqlite::Result<Option<SessionSummary>> {
+        let Some(mut summary) = self
+            .conn
+            .query_row(
+                "SELECT id, name, notes, started_ms, ended_ms, archived,
+                        (SELECT COUNT(*) FROM packets WHERE session_id = sessions.id)
+                 FROM sessions WHERE id = ?1",
+                [id],
+                |row| {
+                    Ok(SessionSummary {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        notes: row.get(2)?,
+                        started_ms: row.get(3)?,
+                        ended_ms: row.get(4)?,
+                        archived: row.get(5)?,
+                        packet_count: row.get(6)?,
+                        nodes: Vec::new(),
+                        ports: Vec::new(),
+                    })
+                },
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        let mut nodes = self.conn.prepare("SELECT DISTINCT node FROM packets WHERE session_id = ?1 ORDER BY node")?;
+        summary.nodes = nodes.query_map([id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        let mut ports = self.conn.prepare("SELECT DISTINCT port FROM session_ports WHERE session_id = ?1 ORDER BY port")?;
+        summary.ports = ports.query_map([id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(Some(summary))
+    }
+
+    fn delete(&mut self, id: i64) -> rusqlite::Result<()> {
+        self.conn.execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+        // Its packets stay in the session store, now without a history entry behind them
+        if self.shown == Some(id) {
+            self.shown = None;
+        }
+        Ok(())
+    }
+
+    fn history(&self, id: i64) -> rusqlite::Result<SessionHistory> {
+        let mut lines = self.conn.prepare("SELECT timestamp_ms, port, line FROM raw_lines WHERE session_id = ?1 ORDER BY id")?;
+        let mut events = self
+            .conn
+            .prepare("SELECT timestamp_ms, kind, node, message, detail FROM events WHERE session_id = ?1 ORDER BY id")?;
+        let mut changes = self
+            .conn
+            .prepare("SELECT timestamp_ms, port, key, value FROM config_changes WHERE session_id = ?1 ORDER BY id")?;
+        let lines = lines.query_map([id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?.collect::<rusqlite::Result<_>>()?;
+        let events = events
+            .query_map([id], |row| {
+                let detail: Option<String> = row.get(4)?;
+                Ok(SessionEvent {
+                    timestamp_ms: row.get(0)?,
+                    kind: row.get(1)?,
+                    node: row.get(2)?,
+                    message: row.get(3)?,
+                    detail: detail.and_then(|d| serde_json::from_str(&d).ok()),
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        let config_changes = changes
+            .query_map([id], |row| {
+                Ok(ConfigChange { timestamp_ms: row.get(0)?, port: row.get(1)?, key: row.get(2)?, value: row.get(3)? })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(SessionHistory { lines, events, config_changes })
+    }
+
+    fn list(&self, include_archived: bool) -> rusqlite::Result<Vec<SessionSummary>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM sessions WHERE archived = 0 OR ?1 ORDER BY started_ms DESC")?;
+        let ids: Vec<i64> = stmt.query_map([include_archived], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(ids.into_iter().filter_map(|id| self.summary(id).ok().flatten()).collect())
+    }
+
+    fn query(&self, filter: &PacketFilter) -> Result<Vec<DataPacket>, Box<dyn std::error::Error>> {
+        let mut sql = String::from("SELECT packet FROM packets WHERE 1 = 1");
+        let mut args: Vec<Box<dyn ToSql>> = Vec::new();
+        let mut bind = |sql: &mut String, clause: &str, value: Box<dyn ToSql>| {
+            args.push(value);
+            sql.push_str(&clause.replace('?', &format!("?{}", args.len())));
+        };
+        if let Some(id) = filter.session_id {
+            bind(&mut sql, " AND session_id = ?", Box::new(id));
+        }
+        if let Some(start) = filter.start_ms {
+            bind(&mut sql, " AND timestamp_ms >= ?", Box::new(start));
+        }
+        if let Some(end) = filter.end_ms {
+            bind(&mut sql, " AND timestamp_ms <= ?", Box::new(end));
+        }
+        if let Some(min) = filter.min_satellites {
+            bind(&mut sql, " AND satellites >= ?", Box::new(min));
+        }
+        if filter.exclude_rejected.unwrap_or(false) {
+            sql.push_str(" AND rejected IS NULL");
+        }
+        for (column, values) in [("node", &filter.nodes), ("fix_status", &filter.fix_statuses)] {
+            if let Some(values) = values {
+                sql.push_str(&format!(" AND {column} IN (SELECT value FROM json_each(?{}))", args.len() + 1));
+                args.push(Box::new(serde_json::to_string(values)?));
+            }
+        }
+        sql.push_str(" ORDER BY timestamp_ms, id");
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let params: Vec<&dyn ToSql> = args.iter().map(|a| a.as_ref()).collect();
+        let rows: Vec<String> = stmt.query_map(params.as_slice(), |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(rows.iter().map(|json| serde_json::from_str(json)).collect::<Result<_, _>>()?)
+    }
+}
+
+static STORE: OnceLock<Mutex<Option<Store>>> = OnceLock::new();
+
+fn get_store() -> &'static Mutex<Option<Store>> {
+    STORE.get_or_init(|| Mutex::new(None))
+}
+
+// Opens (creating and migrating as needed) the database in the app data directory.
+pub fn open(app: &AppHandle) {
+    let result = (|| -> Result<Store, Box<dyn std::error::Error>> {
+        let dir = app.path().app_data_dir()?;
+        fs::create_dir_all(&dir)?;
+        let conn = Connection::open(dir.join(DB_FILE))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Ok(Store::new(conn)?)
+    })();
+    match result {
+        Ok(store) => {
+            if let Ok(mut slot) = get_store().lock() {
+                *slot = Some(store);
+            }
+        }
+        Err(e) => eprintln!("Session database unavailable: {e}"),
+    }
+}
+
+// Recording is best effort: a database problem must never interrupt live tracking.
+fn with_active(f: impl FnOnce(&Store, i64) -> Result<(), Box<dyn std::error::Error>>) {
+    let Ok(slot) = get_store().lock() else {
+        return;
+    };
+    if let Some(store) = slot.as_ref() {
+        if let Some(session_id) = store.active {
+            if let Err(e) = f(store, session_id) {
+                eprintln!("Session database write failed: {e}");
+            }
+        }
+    }
+}
+
+pub fn port_opened(port: &str, baud_rate: u32) {
+    if let Ok(mut slot) = get_store().lock() {
+        if let Some(store) = slot.as_mut() {
+            if let Err(e) = store.port_opened(port, baud_rate, Utc::now().timestamp_millis()) {
+                eprintln!("Session database write failed: {e}");
+            }
+        }
+    }
+}
+
+pub fn port_closed(port: &str) {
+    if let Ok(mut slot) = get_store().lock() {
+        if let Some(store) = slot.as_mut() {
+            if let Err(e) = store.port_closed(port, Utc::now().timestamp_millis()) {
+                eprintln!("Session database write failed: {e}");
+            }
+        }
+    }
+}
+
+pub fn record_packet(pkt: &DataPacket) {
+    with_active(|store, session_id| store.insert_packet(session_id, pkt));
+}
+
+pub fn record_line(port: &str, line: &str) {
+    with_active(|store, session_id| {
+        store.conn.execute(
+            "INSERT INTO raw_lines (session_id, timestamp_ms, port, line) VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, Utc::now().timestamp_millis(), port, line],
+        )?;
+        Ok(())
+    });
+}
+
+pub fn record_event(kind: &str, node: Option<&str>, message: &str, timestamp_ms: i64, detail: &impl Serialize) {
+    with_active(|store, session_id| {
+        store.conn.execute(
+            "INSERT INTO events (session_id, timestamp_ms, kind, node, message, detail) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![session_id, timestamp_ms, kind, node, message, serde_json::to_string(detail)?],
+        )?;
+        Ok(())
+    });
+}
+
+pub fn record_config_change(port: Option<&str>, key: &str, value: &str) {
+    with_active(|store, session_id| {
+        store.conn.execute(
+            "INSERT INTO config_changes (session_id, timestamp_ms, port, key, value) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![session_id, Utc::now().timestamp_millis(), port, key, value],
+        )?;
+        Ok(())
+    });
+}
+
+fn with_store<T>(f: impl FnOnce(&mut Store) -> Result<T, String>) -> Result<T, String> {
+    let mut slot = get_store().lock().map_err(|e| format!("database lock error: {}", e))?;
+    let store = slot.as_mut().ok_or("Session database is not available")?;
+    f(store)
+}
+
+fn db_err(e: impl std::fmt::Display) -> String {
+    format!("Database error: {e}")
+}
+
+// Stores packets read back from a file as a new, already finished session, and shows it.
+pub fn import_session(name: &str, notes: Option<&str>, packets: &[DataPacket], history: &SessionHistory) -> Result<i64, String> {
+    with_store(|store| {
+        let write = || -> Result<i64, Box<dyn std::error::Error>> {
+            let tx = store.conn.unchecked_transaction()?;
+            let started = packets.iter().map(|p| p.timestamp_ms).min().unwrap_or_default();
+            let ended = packets.iter().map(|p| p.timestamp_ms).max().unwrap_or_default();
+            tx.execute(
+                "INSERT INTO sessions (name, notes, app_version, started_ms, ended_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![name, notes, env!("CARGO_PKG_VERSION"), started, ended],
+            )?;
+            let session_id = tx.last_insert_rowid();
+            for pkt in packets {
+                store.insert_packet(session_id, pkt)?;
+            }
+            for (ts, port, line) in &history.lines {
+                tx.execute(
+                    "INSERT INTO raw_lines (session_id, timestamp_ms, port, line) VALUES (?1, ?2, ?3, ?4)",
+                    params![session_id, ts, port, line],
+                )?;
+            }
+            for evt in &history.events {
+                tx.execute(
+                    "INSERT INTO events (session_id, timestamp_ms, kind, node, message, detail) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![session_id, evt.timestamp_ms, evt.kind, evt.node, evt.message, evt.detail.as_ref().map(|d| d.to_string())],
+                )?;
+            }
+            for change in &history.config_changes {
+                tx.execute(
+                    "INSERT INTO config_changes (session_id, timestamp_ms, port, key, value) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![session_id, change.timestamp_ms, change.port, change.key, change.value],
+                )?;
+            }
+            tx.commit()?;
+            Ok(session_id)
+        };
+        let id = write().map_err(db_err)?;
+        store.shown = Some(id);
+        Ok(id)
+    })
+}
+
+// The history database entry behind what the session store holds, if any.
+pub fn shown_session() -> Option<i64> {
+    get_store().lock().ok()?.as_ref()?.shown
+}
+
+pub fn session_history(id: i64) -> Result<SessionHistory, String> {
+    with_store(|store| store.history(id).map_err(db_err))
+}
+
+#[tauri::command]
+pub fn list_sessions(include_archived: Option<bool>) -> Result<Vec<SessionSummary>, String> {
+    with_store(|store| store.list(include_archived.unwrap_or(false)).map_err(db_err))
+}
+
+#[tauri::command]
+pub fn query_packets(filter: PacketFilter) -> Result<Vec<DataPacket>, String> {
+    with_store(|store| store.query(&filter).map_err(db_err))
+}
+
+pub fn session_packets(id: i64) -> Result<(SessionSummary, Vec<DataPacket>), String> {
+    with_store(|store| {
+        let summary = store.summary(id).map_err(db_err)?.ok_or("No such session")?;
+        let filter = PacketFilter { session_id: Some(id), ..Default::default() };
+        Ok((summary, store.query(&filter).map_err(db_err)?))
+    })
+}
+
+// Replaces the live view with a past session: the tracker filters are rebuilt from its packets and
+// it becomes the session that exports read from.
+#[tauri::command]
+pub fn load_session(app: AppHandle, id: i64) -> Result<SessionSummary, String> {
+    if serial::open_port_count() > 0 {
+        return Err("Disconnect before loading a past session".into());
+    }
+    let (summary, mut packets) = session_packets(id)?;
+    tracker::load_history(&mut packets);
+    session::replace(summary.started_ms, packets);
+    with_store(|store| {
+        store.shown = Some(id);
+        Ok(())
+    })?;
+    let _ = app.emit("session-loaded", summary.clone());
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn rename_session(id: i64, name: Option<String>, notes: Option<String>) -> Result<(), String> {
+    with_store(|store| {
+        store
+            .conn
+            .execute("UPDATE sessions SET name = ?1, notes = ?2 WHERE id = ?3", params![name, notes, id])
+            .map_err(db_err)?;
+        Ok(())
+    })
+}
+
+// Archived sessions stay in the database but are hidden from list_sessions by default.
+#[tauri::command]
+pub fn archive_session(id: i64, archived: bool) -> Result<(), String> {
+    with_store(|store| {
+        store.conn.execute("UPDATE sessions SET archived = ?1 WHERE id = ?2", params![archived, id]).map_err(db_err)?;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn delete_session(id: i64) -> Result<(), String> {
+    with_store(|store| {
+        if store.active == Some(id) {
+            return Err("Cannot delete the session being recorded".into());
+        }
+        store.delete(id).map_err(db_err)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::fixtures::packet;
+    use crate::telemetry::FixStatus;
+
+    #[test]
+    fn records_and_queries_sessions() {
+        let mut store = Store::new(Connection::open_in_memory().unwrap()).unwrap();
+        store.port_opened("COM3", 115_200, 1_000).unwrap();
+        let id = store.active.unwrap();
+        store.insert_packet(id, &packet(1, 2_000, 43.0, -77.0, 1_000)).unwrap();
+        store.insert_packet(id, &DataPacket { fix_status: FixStatus::NoFix, satellites_count: Some(0), ..packet(2, 3_000, 43.0, -77.0, 1_000) }).unwrap();
+        store.insert_packet(id, &DataPacket { fix_status: FixStatus::Diff, satellites_count: Some(5), ..packet(1, 4_000, 43.0, -77.0, 1_000) }).unwrap();
+        store.port_closed("COM3", 5_000).unwrap();
+        assert!(store.active.is_none());
+
+        let sessions = store.list(false).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].packet_count, 3);
+        assert_eq!(sessions[0].ended_ms, Some(5_000));
+        assert_eq!(sessions[0].nodes, vec!["Node 1", "Node 2"]);
+        assert_eq!(sessions[0].ports, vec!["COM3"]);
+
+        let filter = PacketFilter {
+            nodes: Some(vec!["Node 1".into()]),
+            fix_statuses: Some(vec!["FIX".into(), "DIFF".into()]),
+            min_satellites: Some(6),
+            ..Default::default()
+        };
+        let found = store.query(&filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].timestamp_ms, 2_000);
+
+        assert_eq!(store.shown, Some(id));
+        store.delete(id).unwrap();
+        assert!(store.query(&PacketFilter::default()).unwrap().is_empty());
+        assert_eq!(store.shown, None);
+    }
+
+    #[test]
+    fn migrations_are_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+}
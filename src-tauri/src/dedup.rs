@@ -0,0 +1,190 @@
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::DataPacket;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupConfig {
+    pub enabled: bool,
+    // How long to wait for other receivers to report the same transmission
+    pub window_ms: i64,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        DedupConfig { enabled: true, window_ms: 500 }
+    }
+}
+
+#[derive(Debug)]
+struct PendingGroup {
+    fingerprint: String,
+    first_arrival_ms: i64,
+    copies: Vec<DataPacket>,
+}
+
+#[derive(Debug, Default)]
+pub struct Deduplicator {
+    config: DedupConfig,
+    pending: Vec<PendingGroup>,
+}
+
+// Everything the tracker transmitted; link measurements and timing are deliberately left out.
+fn fingerprint(pkt: &DataPacket) -> String {
+    format!(
+        "{}|{:?}|{:?}|{:?}|{:?}|{:?}",
+        pkt.node_key(),
+        pkt.latitude,
+        pkt.longitude,
+        pkt.altitude_ft,
+        pkt.satellites_count,
+        pkt.fix_status,
+    )
+}
+
+fn link_quality(pkt: &DataPacket) -> (i8, i16) {
+    (pkt.receiver_snr.unwrap_or(i8::MIN), pkt.receiver_rssi.unwrap_or(i16::MIN))
+}
+
+// Collapses copies of one transmission into a single packet built from the best-received copy.
+fn merge(mut copies: Vec<DataPacket>) -> DataPacket {
+    let best = copies
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, pkt)| link_quality(pkt))
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let mut receptions: Vec<_> = copies.iter().flat_map(|pkt| pkt.receptions.iter().cloned()).collect();
+    receptions.sort_by_key(|r| r.arrival_ms);
+    let first_arrival = copies.iter().map(|pkt| pkt.timestamp_ms).min();
+
+    let mut merged = copies.swap_remove(best);
+    merged.receptions = receptions;
+    if let Some(ts) = first_arrival {
+        merged.timestamp_ms = ts;
+    }
+    merged
+}
+
+impl Deduplicator {
+    // Returns the packet straight away when there is nothing to merge it with; otherwise it is held
+    // until drain_expired() releases its group.
+    pub fn offer(&mut self, pkt: DataPacket, multiple_receivers: bool) -> Option<DataPacket> {
+        if !self.config.enabled || (!multiple_receivers && self.pending.is_empty()) {
+            return Some(pkt);
+        }
+        let key = fingerprint(&pkt);
+        let port = pkt.receptions.first().map(|r| r.port.clone());
+        let window = self.config.window_ms;
+        // A receiver never duplicates itself, so a second copy from the same port is a new transmission
+        let group = self.pending.iter_mut().find(|g| {
+            g.fingerprint == key
+                && pkt.timestamp_ms - g.first_arrival_ms <= window
+                && !g.copies.iter().any(|c| c.receptions.iter().any(|r| Some(&r.port) == port.as_ref()))
+        });
+        match group {
+            Some(group) => group.copies.push(pkt),
+            None => self.pending.push(PendingGroup {
+                fingerprint: key,
+                first_arrival_ms: pkt.timestamp_ms,
+                copies: vec![pkt],
+            }),
+        }
+        None
+    }
+
+    pub fn drain_expired(&mut self, now_ms: i64) -> Vec<DataPacket> {
+        let window = self.config.window_ms;
+        let (expired, pending): (Vec<_>, Vec<_>) =
+            self.pending.drain(..).partition(|g| now_ms - g.first_arrival_ms >= window);
+        self.pending = pending;
+        expired.into_iter().map(|g| merge(g.copies)).collect()
+    }
+}
+
+static DEDUP: OnceLock<Mutex<Deduplicator>> = OnceLock::new();
+
+fn get_dedup() -> &'static Mutex<Deduplicator> {
+    DEDUP.get_or_init(|| Mutex::new(Deduplicator::default()))
+}
+
+pub fn offer(pkt: DataPacket, multiple_receivers: bool) -> Option<DataPacket> {
+    match get_dedup().lock() {
+        Ok(mut dedup) => dedup.offer(pkt, multiple_receivers),
+        Err(_) => Some(pkt),
+    }
+}
+
+pub fn drain_expired(now_ms: i64) -> Vec<DataPacket> {
+    get_dedup().lock().map(|mut dedup| dedup.drain_expired(now_ms)).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_dedup_config() -> Result<DedupConfig, String> {
+    let dedup = get_dedup().lock().map_err(|e| format!("dedup lock error: {}", e))?;
+    Ok(dedup.config.clone())
+}
+
+#[tauri::command]
+pub fn set_dedup_config(config: DedupConfig) -> Result<(), String> {
+    let mut dedup = get_dedup().lock().map_err(|e| format!("dedup lock error: {}", e))?;
+    dedup.config = config;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::Reception;
+
+    fn copy(port: &str, ts: i64, rssi: i16, snr: i8) -> DataPacket {
+        DataPacket {
+            node_id: Some(3),
+            latitude: Some(42.7),
+            longitude: Some(-77.19),
+            receiver_rssi: Some(rssi),
+            receiver_snr: Some(snr),
+            timestamp_ms: ts,
+            receptions: vec![Reception {
+                port: port.into(),
+                rssi: Some(rssi),
+                snr: Some(snr),
+                arrival_ms: ts,
+                device_uptime_ms: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merges_copies_from_different_receivers() {
+        let mut dedup = Deduplicator::default();
+        assert!(dedup.offer(copy("COM3", 1_000, -110, -2), true).is_none());
+        assert!(dedup.offer(copy("COM4", 1_080, -90, 6), true).is_none());
+        assert!(dedup.drain_expired(1_200).is_empty());
+
+        let merged = dedup.drain_expired(1_500);
+        assert_eq!(merged.len(), 1);
+        let pkt = &merged[0];
+        assert_eq!(pkt.receiver_snr, Some(6));
+        assert_eq!(pkt.timestamp_ms, 1_000);
+        let ports: Vec<_> = pkt.receptions.iter().map(|r| r.port.as_str()).collect();
+        assert_eq!(ports, vec!["COM3", "COM4"]);
+    }
+
+    #[test]
+    fn same_receiver_twice_is_two_transmissions() {
+        let mut dedup = Deduplicator::default();
+        dedup.offer(copy("COM3", 1_000, -100, 1), true);
+        dedup.offer(copy("COM3", 1_300, -100, 1), true);
+        assert_eq!(dedup.drain_expired(2_000).len(), 2);
+    }
+
+    #[test]
+    fn single_receiver_passes_straight_through() {
+        let mut dedup = Deduplicator::default();
+        assert!(dedup.offer(copy("COM3", 1_000, -100, 1), false).is_some());
+    }
+}
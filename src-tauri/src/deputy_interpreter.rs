@@ -1,6 +1,7 @@
 use crate::telemetry::{DataPacket, FixStatus};
 use regex::Regex;
 use lazy_static::lazy_static;
+use serde::Serialize;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug)]
@@ -8,6 +9,16 @@ pub enum ParseError {
     NoMatch,
 }
 
+// How a port's output is read. Receivers and trackers print Deputy telemetry; a Marshal's console
+// is shell and flight log output whose "Altitude:" lines must not turn into tracker packets.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ParserDialect {
+    #[default]
+    Deputy,
+    Console,
+}
+
 lazy_static! {
     // Probably shouldnt all unwrap but fiwb
 
@@ -29,6 +40,8 @@ lazy_static! {
     pub static ref RE_SATS: Regex = Regex::new(r"(?i)satellites count:\s*(\d+)").unwrap();
     pub static ref RE_FIX: Regex = Regex::new(r"(?i)fix status:\s*(\S+(?:\s+\S+)?)").unwrap();
     pub static ref RE_NOFIX: Regex = Regex::new(r"(?i)no fix acquired").unwrap();
+    // Zephyr log prefix carries the receiver's uptime: "[00:01:11.262,000]" (hh:mm:ss.ms,us)
+    pub static ref RE_LOG_UPTIME: Regex = Regex::new(r"^\[(\d+):(\d{2}):(\d{2})\.(\d{3}),\d{3}\]").unwrap();
     pub static ref RE_CALLSIGN: Regex = Regex::new(r"(?i)callsign:\s*([A-Z0-9/\-]{3,12})").unwrap();
 }
 
@@ -57,6 +70,11 @@ pub fn parse_zephyr_line(line: &str) -> Result<DataPacket, ParseError> {
         pkt.receiver_snr = cap.get(4).and_then(|m| m.as_str().parse::<i8>().ok());
     }
 
+    if let Some(cap) = RE_LOG_UPTIME.captures(line) {
+        let part = |i: usize| cap.get(i).and_then(|m| m.as_str().parse::<u64>().ok()).unwrap_or(0);
+        pkt.device_uptime_ms = Some(((part(1) * 60 + part(2)) * 60 + part(3)) * 1000 + part(4));
+    }
+
     if let Some(cap) = RE_LAT.captures(line) {
         pkt.latitude = cap.get(1).and_then(|m| m.as_str().parse::<f32>().ok());
     }
@@ -107,6 +125,80 @@ pub fn parse_zephyr_line(line: &str) -> Result<DataPacket, ParseError> {
     }
 }
 
+// Fills in whatever a later line of the same packet adds.
+fn merge_packet(dst: &mut DataPacket, src: DataPacket) {
+    if src.node_id.is_some() {
+        dst.node_id = src.node_id;
+    }
+    if src.latitude.is_some() {
+        dst.latitude = src.latitude;
+    }
+    if src.longitude.is_some() {
+        dst.longitude = src.longitude;
+    }
+    if src.altitude_ft.is_some() {
+        dst.altitude_ft = src.altitude_ft;
+    }
+    if src.satellites_count.is_some() {
+        dst.satellites_count = src.satellites_count;
+    }
+    if src.receiver_rssi.is_some() {
+        dst.receiver_rssi = src.receiver_rssi;
+    }
+    if src.receiver_snr.is_some() {
+        dst.receiver_snr = src.receiver_snr;
+    }
+    if src.callsign.is_some() {
+        dst.callsign = src.callsign;
+    }
+    if !matches!(src.fix_status, FixStatus::Unknown) {
+        dst.fix_status = src.fix_status;
+    }
+    if dst.device_uptime_ms.is_none() {
+        dst.device_uptime_ms = src.device_uptime_ms;
+    }
+    dst.timestamp_ms = src.timestamp_ms;
+    dst.raw_lines.extend(src.raw_lines);
+}
+
+// Groups consecutive lines into packets: a node header starts one and the fix status line ends it.
+// Shared by the live serial reader and transcript import.
+#[derive(Debug, Default)]
+pub struct PacketAssembler {
+    current: Option<DataPacket>,
+}
+
+impl PacketAssembler {
+    pub fn push_line(&mut self, line: &str, mut emit: impl FnMut(DataPacket)) -> Result<(), ParseError> {
+        let is_packet_start = RE_HEADER_NODE.is_match(line) || RE_HEADER_LICENSED_NOFIX.is_match(line);
+        let line_lower = line.to_lowercase();
+        let is_packet_end = line_lower.contains("fix status:") || line_lower.contains("no fix acquired");
+
+        if is_packet_start {
+            if let Some(prev) = self.current.take() {
+                emit(prev);
+            }
+        }
+
+        let part = parse_zephyr_line(line)?;
+        match self.current.as_mut() {
+            Some(existing) => merge_packet(existing, part),
+            None => self.current = Some(part),
+        }
+        if is_packet_end {
+            if let Some(done) = self.current.take() {
+                emit(done);
+            }
+        }
+        Ok(())
+    }
+
+    // Whatever packet is still open, e.g. when the port closes mid-packet
+    pub fn finish(&mut self) -> Option<DataPacket> {
+        self.current.take()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,4 +231,12 @@ mod tests {
 
         assert_eq!(pkt.altitude_ft, Some(-138));
     }
+
+    #[test]
+    fn parses_receiver_uptime_from_log_prefix() {
+        let pkt = parse_zephyr_line("[01:02:03.456,789] <inf> LoraTransceiver: Node 2: (13 bytes | -80 dBm | 7 dB):")
+            .expect("expected header to parse");
+
+        assert_eq!(pkt.device_uptime_ms, Some(3_723_456));
+    }
 }
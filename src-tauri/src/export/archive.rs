@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::{gpx, iso_time, kml, write_csv_to, CsvDialect, ExportRecord, ALL_COLUMNS};
+use crate::db::SessionHistory;
+use crate::telemetry::DataPacket;
+
+pub const ARCHIVE_FORMAT: &str = "dispatch-session-archive";
+// Bumped when the layout changes in a way older versions can't read
+pub const ARCHIVE_VERSION: u32 = 1;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const PACKETS_FILE: &str = "packets.jsonl";
+pub const RAW_FILE: &str = "raw.log";
+pub const EVENTS_FILE: &str = "events.jsonl";
+pub const CONFIG_FILE: &str = "config-changes.jsonl";
+const CSV_FILE: &str = "packets.csv";
+const KML_FILE: &str = "track.kml";
+const GPX_FILE: &str = "track.gpx";
+const NOTES_FILE: &str = "notes.txt";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub created_at: String,
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    pub started_ms: i64,
+    pub ended_ms: Option<i64>,
+    pub ports: Vec<String>,
+    pub nodes: Vec<String>,
+    pub packet_count: usize,
+    pub files: Vec<String>,
+}
+
+pub struct ArchiveSession<'a> {
+    pub name: Option<&'a str>,
+    pub notes: Option<&'a str>,
+    pub started_ms: i64,
+    pub ended_ms: Option<i64>,
+    pub ports: Vec<String>,
+    pub packets: &'a [DataPacket],
+    pub history: &'a SessionHistory,
+}
+
+fn jsonl<T: Serialize>(items: &[T]) -> Result<Vec<u8>, serde_json::Error> {
+    let mut out = Vec::new();
+    for item in items {
+        serde_json::to_writer(&mut out, item)?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+// One zip holding everything a reviewer needs: the packets in every export format, the raw serial
+// transcript, alerts, config changes and notes. packets.jsonl is what import reads back.
+pub fn write_archive(path: &Path, session: &ArchiveSession) -> Result<(), Box<dyn std::error::Error>> {
+    let records: Vec<ExportRecord> = session.packets.iter().map(ExportRecord::from).collect();
+    let mut csv = Vec::new();
+    write_csv_to(&mut csv, &records, &ALL_COLUMNS, &CsvDialect::default())?;
+
+    let mut entries: Vec<(&str, Vec<u8>)> = vec![
+        (PACKETS_FILE, jsonl(session.packets)?),
+        (CSV_FILE, csv),
+        (KML_FILE, kml::build_kml(&records, true).into_bytes()),
+        (GPX_FILE, gpx::build_gpx(&records, false).into_bytes()),
+    ];
+    if !session.history.lines.is_empty() {
+        let raw: String = session
+            .history
+            .lines
+            .iter()
+            .map(|(ts, port, line)| format!("{}\t{}\t{}\n", iso_time(*ts), port, line))
+            .collect();
+        entries.push((RAW_FILE, raw.into_bytes()));
+    }
+    if !session.history.events.is_empty() {
+        entries.push((EVENTS_FILE, jsonl(&session.history.events)?));
+    }
+    if !session.history.config_changes.is_empty() {
+        entries.push((CONFIG_FILE, jsonl(&session.history.config_changes)?));
+    }
+    if let Some(notes) = session.notes.filter(|n| !n.trim().is_empty()) {
+        entries.push((NOTES_FILE, format!("{notes}\n").into_bytes()));
+    }
+
+    let mut nodes: Vec<String> = Vec::new();
+    for pkt in session.packets {
+        let key = pkt.node_key();
+        if !nodes.contains(&key) {
+            nodes.push(key);
+        }
+    }
+    let manifest = ArchiveManifest {
+        format: ARCHIVE_FORMAT.into(),
+        version: ARCHIVE_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").into(),
+        created_at: Utc::now().to_rfc3339(),
+        name: session.name.map(String::from),
+        notes: session.notes.map(String::from),
+        started_ms: session.started_ms,
+        ended_ms: session.ended_ms,
+        ports: session.ports.clone(),
+        nodes,
+        packet_count: session.packets.len(),
+        files: entries.iter().map(|(name, _)| name.to_string()).collect(),
+    };
+    entries.push((MANIFEST_FILE, serde_json::to_vec_pretty(&manifest)?));
+
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, data) in entries {
+        zip.start_file(name, options)?;
+        zip.write_all(&data)?;
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
@@ -0,0 +1,357 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, TimeZone};
+use serde::Deserialize;
+
+use crate::geo::FEET_TO_METERS;
+
+// WGS84
+const SEMI_MAJOR_M: f64 = 6_378_137.0;
+const FLATTENING: f64 = 1.0 / 298.257_223_563;
+const UTM_SCALE: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+const UTM_BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWX";
+const MGRS_ROW_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+const MGRS_COLUMN_SETS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CsvTimeZone {
+    #[default]
+    Utc,
+    // The computer's own time zone
+    Local,
+    // Fixed offset from UTC in minutes, e.g. 120 for CEST
+    Offset(i32),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeFormat {
+    // 2024-06-01T14:00:00.000+02:00
+    #[default]
+    Rfc3339,
+    // 2024-06-01 14:00:00.000, which spreadsheet apps recognise as a date
+    Spreadsheet,
+    // Milliseconds since the Unix epoch
+    UnixMs,
+    // strftime pattern, e.g. "%H:%M:%S"
+    Custom(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UnitSystem {
+    // Feet, as the trackers report
+    #[default]
+    Imperial,
+    Metric,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CoordinateFormat {
+    #[default]
+    DecimalDegrees,
+    Dms,
+    Utm,
+    Mgrs,
+}
+
+// How values are written: separators, time zone and units. Which columns appear is up to ExportOptions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub decimal_separator: char,
+    pub time_zone: CsvTimeZone,
+    pub time_format: TimeFormat,
+    pub units: UnitSystem,
+    pub coordinates: CoordinateFormat,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: ',',
+            decimal_separator: '.',
+            time_zone: CsvTimeZone::Utc,
+            time_format: TimeFormat::Rfc3339,
+            units: UnitSystem::Imperial,
+            coordinates: CoordinateFormat::DecimalDegrees,
+        }
+    }
+}
+
+impl CsvDialect {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.delimiter.is_ascii() || self.delimiter == '"' || self.delimiter == '\n' {
+            return Err(format!("Unsupported delimiter {:?}", self.delimiter));
+        }
+        if self.decimal_separator == self.delimiter {
+            return Err("The decimal separator and delimiter must differ".into());
+        }
+        if let CsvTimeZone::Offset(minutes) = self.time_zone {
+            FixedOffset::east_opt(minutes * 60).ok_or_else(|| format!("Invalid UTC offset of {minutes} minutes"))?;
+        }
+        if let TimeFormat::Custom(pattern) = &self.time_format {
+            if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+                return Err(format!("Invalid time format {pattern:?}"));
+            }
+        }
+        Ok(())
+    }
+
+    // Number with a fixed number of decimals and the dialect's decimal separator
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        self.localize(format!("{value:.decimals$}"))
+    }
+
+    // Swaps the decimal point of an already formatted number for the dialect's separator
+    pub fn localize(&self, text: String) -> String {
+        if self.decimal_separator == '.' {
+            text
+        } else {
+            text.replace('.', &self.decimal_separator.to_string())
+        }
+    }
+
+    pub fn time(&self, ts: i64) -> String {
+        let Some(utc) = DateTime::from_timestamp_millis(ts) else {
+            return String::new();
+        };
+        match self.time_zone {
+            CsvTimeZone::Utc => self.render_time(utc),
+            CsvTimeZone::Local => self.render_time(utc.with_timezone(&Local)),
+            CsvTimeZone::Offset(minutes) => match FixedOffset::east_opt(minutes * 60) {
+                Some(offset) => self.render_time(utc.with_timezone(&offset)),
+                None => self.render_time(utc),
+            },
+        }
+    }
+
+    // Offset from UTC in effect at `ts`, e.g. "+02:00". Written next to times that don't carry one.
+    pub fn utc_offset(&self, ts: i64) -> String {
+        let Some(utc) = DateTime::from_timestamp_millis(ts) else {
+            return String::new();
+        };
+        match self.time_zone {
+            CsvTimeZone::Utc => "+00:00".into(),
+            CsvTimeZone::Local => utc.with_timezone(&Local).format("%:z").to_string(),
+            CsvTimeZone::Offset(minutes) => match FixedOffset::east_opt(minutes * 60) {
+                Some(offset) => offset.to_string(),
+                None => "+00:00".into(),
+            },
+        }
+    }
+
+    // Whether rendered times leave out their UTC offset, so the offset needs a column of its own
+    pub fn needs_offset_column(&self) -> bool {
+        match &self.time_format {
+            TimeFormat::Spreadsheet => true,
+            TimeFormat::Custom(pattern) => !["%z", "%:z", "%::z", "%#z", "%Z", "%s"].iter().any(|spec| pattern.contains(spec)),
+            TimeFormat::Rfc3339 | TimeFormat::UnixMs => false,
+        }
+    }
+
+    fn render_time<Tz: TimeZone>(&self, time: DateTime<Tz>) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        match &self.time_format {
+            TimeFormat::Rfc3339 => time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            TimeFormat::Spreadsheet => time.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            TimeFormat::UnixMs => time.timestamp_millis().to_string(),
+            TimeFormat::Custom(pattern) => time.format(pattern).to_string(),
+        }
+    }
+
+    // A single latitude or longitude in decimal degrees or degrees-minutes-seconds. Grid formats
+    // only make sense for a whole position, so they fall back to decimal degrees here.
+    pub fn axis(&self, value: f64, is_latitude: bool) -> String {
+        match self.coordinates {
+            CoordinateFormat::Dms => {
+                let hemisphere = match (is_latitude, value < 0.0) {
+                    (true, false) => 'N',
+                    (true, true) => 'S',
+                    (false, false) => 'E',
+                    (false, true) => 'W',
+                };
+                let total_seconds = (value.abs() * 3600.0 * 100.0).round() / 100.0;
+                let degrees = (total_seconds / 3600.0).floor();
+                let minutes = ((total_seconds - degrees * 3600.0) / 60.0).floor();
+                let seconds = total_seconds - degrees * 3600.0 - minutes * 60.0;
+                let seconds = self.localize(format!("{:05.2}", seconds));
+                format!("{}°{:02}'{}\"{}", degrees, minutes, seconds, hemisphere)
+            }
+            _ => self.number(value, 6),
+        }
+    }
+
+    // A whole position in the selected coordinate format.
+    pub fn position(&self, lat: f64, lon: f64) -> String {
+        match self.coordinates {
+            CoordinateFormat::DecimalDegrees | CoordinateFormat::Dms => format!("{} {}", self.axis(lat, true), self.axis(lon, false)),
+            CoordinateFormat::Utm => match to_utm(lat, lon) {
+                Some(utm) => format!("{}{} {:.0} {:.0}", utm.zone, utm.band as char, utm.easting.floor(), utm.northing.floor()),
+                None => format!("{} {}", self.number(lat, 6), self.number(lon, 6)),
+            },
+            CoordinateFormat::Mgrs => match to_utm(lat, lon).and_then(|utm| to_mgrs(&utm)) {
+                Some(mgrs) => mgrs,
+                None => format!("{} {}", self.number(lat, 6), self.number(lon, 6)),
+            },
+        }
+    }
+
+    pub fn altitude(&self, feet: f64) -> String {
+        match self.units {
+            UnitSystem::Imperial => self.number(feet, 0),
+            UnitSystem::Metric => self.number(feet * FEET_TO_METERS, 1),
+        }
+    }
+
+    // Distances are kept in metres internally
+    pub fn distance(&self, meters: f64) -> String {
+        match self.units {
+            UnitSystem::Imperial => self.number(meters / FEET_TO_METERS, 0),
+            UnitSystem::Metric => self.number(meters, 0),
+        }
+    }
+
+    pub fn speed(&self, meters_per_second: f64) -> String {
+        match self.units {
+            UnitSystem::Imperial => self.number(meters_per_second / FEET_TO_METERS, 1),
+            UnitSystem::Metric => self.number(meters_per_second, 1),
+        }
+    }
+
+    pub fn length_unit(&self) -> &'static str {
+        match self.units {
+            UnitSystem::Imperial => "ft",
+            UnitSystem::Metric => "m",
+        }
+    }
+}
+
+pub struct Utm {
+    pub zone: u8,
+    pub band: u8,
+    pub easting: f64,
+    pub northing: f64,
+}
+
+// Krüger series to fourth order in n, accurate to well under a millimetre within a zone. UTM isn't
+// defined beyond 84°N and 80°S (UPS covers the poles), so those return None.
+pub fn to_utm(lat: f64, lon: f64) -> Option<Utm> {
+    if !(-80.0..=84.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return None;
+    }
+    let band = UTM_BANDS[(((lat + 80.0) / 8.0).floor() as usize).min(UTM_BANDS.len() - 1)];
+    let mut zone = (((lon + 180.0) / 6.0).floor() as u8 % 60) + 1;
+    // Norway and Svalbard exceptions
+    if band == b'V' && (3.0..12.0).contains(&lon) {
+        zone = 32;
+    }
+    if band == b'X' && (0.0..42.0).contains(&lon) {
+        zone = match lon {
+            l if l < 9.0 => 31,
+            l if l < 21.0 => 33,
+            l if l < 33.0 => 35,
+            _ => 37,
+        };
+    }
+
+    let central = ((zone as f64 - 1.0) * 6.0 - 180.0 + 3.0).to_radians();
+    let phi = lat.to_radians();
+    let lambda = lon.to_radians() - central;
+    let n = FLATTENING / (2.0 - FLATTENING);
+    let a = SEMI_MAJOR_M / (1.0 + n) * (1.0 + n.powi(2) / 4.0 + n.powi(4) / 64.0);
+    let alpha = [
+        n / 2.0 - 2.0 * n.powi(2) / 3.0 + 5.0 * n.powi(3) / 16.0 + 41.0 * n.powi(4) / 180.0,
+        13.0 * n.powi(2) / 48.0 - 3.0 * n.powi(3) / 5.0 + 557.0 * n.powi(4) / 1440.0,
+        61.0 * n.powi(3) / 240.0 - 103.0 * n.powi(4) / 140.0,
+        49_561.0 * n.powi(4) / 161_280.0,
+    ];
+
+    let e = 2.0 * n.sqrt() / (1.0 + n);
+    let t = (phi.sin().atanh() - e * (e * phi.sin()).atanh()).sinh();
+    let xi = t.atan2(lambda.cos());
+    let eta = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+    let (mut x, mut y) = (eta, xi);
+    for (j, alpha) in alpha.iter().enumerate() {
+        let k = 2.0 * (j as f64 + 1.0);
+        x += alpha * (k * xi).cos() * (k * eta).sinh();
+        y += alpha * (k * xi).sin() * (k * eta).cosh();
+    }
+
+    let northing = UTM_SCALE * a * y;
+    Some(Utm {
+        zone,
+        band,
+        easting: UTM_FALSE_EASTING + UTM_SCALE * a * x,
+        northing: if lat < 0.0 { northing + UTM_FALSE_NORTHING_SOUTH } else { northing },
+    })
+}
+
+// Military grid reference to 1 m, e.g. "18T UN 12345 67890".
+pub fn to_mgrs(utm: &Utm) -> Option<String> {
+    let column_set = MGRS_COLUMN_SETS[(utm.zone as usize - 1) % 3];
+    // Eastings run from 100 km to 900 km, so the first column letter is the 100 km square
+    let column_index = (utm.easting / 100_000.0).floor() as usize;
+    let column = *column_set.get(column_index.checked_sub(1)?)?;
+    // Even zones start the row letters five squares later
+    let row_offset = if utm.zone.is_multiple_of(2) { 5 } else { 0 };
+    let row = MGRS_ROW_LETTERS[((utm.northing / 100_000.0).floor() as usize + row_offset) % MGRS_ROW_LETTERS.len()];
+    Some(format!(
+        "{}{} {}{} {:05} {:05}",
+        utm.zone,
+        utm.band as char,
+        column as char,
+        row as char,
+        (utm.easting % 100_000.0).floor() as u32,
+        (utm.northing % 100_000.0).floor() as u32,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_references_match_known_points() {
+        // RIT, Sydney Opera House, Bergen (zone 32 exception) and Svalbard (zone 33 exception)
+        let cases = [
+            (43.0847, -77.6744, "18T 282308 4773692", "18T TN 82308 73692"),
+            (-33.8568, 151.2153, "56H 334900 6252288", "56H LH 34900 52288"),
+            (60.5, 5.5, "32V 307793 6712209", "32V LN 07793 12209"),
+            (78.2, 15.6, "33X 513696 8680760", "33X WG 13696 80760"),
+        ];
+        for (lat, lon, utm, mgrs) in cases {
+            let dialect = |coordinates| CsvDialect { coordinates, ..Default::default() };
+            assert_eq!(dialect(CoordinateFormat::Utm).position(lat, lon), utm);
+            assert_eq!(dialect(CoordinateFormat::Mgrs).position(lat, lon), mgrs);
+        }
+        assert!(to_utm(85.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn european_dialect_formats_values() {
+        let dialect = CsvDialect {
+            delimiter: ';',
+            decimal_separator: ',',
+            time_zone: CsvTimeZone::Offset(120),
+            time_format: TimeFormat::Spreadsheet,
+            units: UnitSystem::Metric,
+            coordinates: CoordinateFormat::Dms,
+        };
+        assert!(dialect.validate().is_ok());
+        assert_eq!(dialect.time(1_717_250_400_000), "2024-06-01 16:00:00.000");
+        assert_eq!(dialect.utc_offset(1_717_250_400_000), "+02:00");
+        assert!(dialect.needs_offset_column());
+        assert_eq!(dialect.altitude(1000.0), "304,8");
+        assert_eq!(dialect.axis(-77.6744, false), "77°40'27,84\"W");
+        assert_eq!(dialect.number(43.0847, 6), "43,084700");
+        assert!(CsvDialect { decimal_separator: ';', ..dialect.clone() }.validate().is_err());
+        assert!(CsvDialect { time_format: TimeFormat::Custom("%Q".into()), ..dialect }.validate().is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use serde_json::{json, Map, Value};
+
+use crate::telemetry::DataPacket;
+
+use crate::geo::FEET_TO_METERS;
+
+fn coordinate(lat: f64, lon: f64, altitude_ft: Option<i32>) -> Value {
+    match altitude_ft {
+        Some(alt) => json!([lon, lat, alt as f64 * FEET_TO_METERS]),
+        None => json!([lon, lat]),
+    }
+}
+
+// Same choice the map makes: the filtered estimate, and nothing for rejected fixes.
+fn track_coordinate(pkt: &DataPacket) -> Option<Value> {
+    match &pkt.filtered {
+        Some(filtered) if filtered.rejected.is_some() => None,
+        Some(filtered) => Some(coordinate(filtered.latitude, filtered.longitude, pkt.altitude_ft)),
+        None => Some(coordinate(pkt.latitude? as f64, pkt.longitude? as f64, pkt.altitude_ft)),
+    }
+}
+
+// A LineString per node followed by a Point per packet. Point properties are the packet exactly as
+// the backend sees it, so nothing is lost to the frontend's display formatting.
+pub fn build_geojson(packets: &[DataPacket]) -> Value {
+    let mut nodes: Vec<(String, Vec<&DataPacket>)> = Vec::new();
+    for pkt in packets {
+        let key = pkt.node_key();
+        match nodes.iter_mut().find(|(node, _)| *node == key) {
+            Some((_, list)) => list.push(pkt),
+            None => nodes.push((key, vec![pkt])),
+        }
+    }
+
+    let mut features = Vec::new();
+    for (node, list) in &mut nodes {
+        list.sort_by_key(|p| p.timestamp_ms);
+        let coordinates: Vec<Value> = list.iter().filter_map(|p| track_coordinate(p)).collect();
+        if coordinates.len() >= 2 {
+            features.push(json!({
+                "type": "Feature",
+                "properties": {
+                    "node": node,
+                    "kind": "track",
+                    "start_ms": list.first().map(|p| p.timestamp_ms),
+                    "end_ms": list.last().map(|p| p.timestamp_ms),
+                },
+                "geometry": { "type": "LineString", "coordinates": coordinates },
+            }));
+        }
+    }
+
+    for (node, list) in &nodes {
+        for pkt in list {
+            let mut properties = match serde_json::to_value(pkt) {
+                Ok(Value::Object(map)) => map,
+                _ => Map::new(),
+            };
+            properties.insert("node".into(), json!(node));
+            let geometry = match (pkt.latitude, pkt.longitude) {
+                (Some(lat), Some(lon)) => json!({
+                    "type": "Point",
+                    "coordinates": coordinate(lat as f64, lon as f64, pkt.altitude_ft),
+                }),
+                _ => Value::Null,
+            };
+            features.push(json!({ "type": "Feature", "properties": properties, "geometry": geometry }));
+        }
+    }
+
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::fixtures::packet;
+    use crate::telemetry::{FilteredPosition, RejectReason};
+
+    #[test]
+    fn writes_track_and_packet_points() {
+        let tracked = |ts, lat: f32, rejected| DataPacket {
+            callsign: Some("KD2ABC".into()),
+            raw_lines: vec!["Lat: 43.0".into()],
+            filtered: Some(FilteredPosition { latitude: lat as f64, longitude: -77.5, altitude_ft: Some(1_000.0), rejected }),
+            ..packet(2, ts, lat, -77.5, 1_000)
+        };
+        let packets = vec![tracked(2_000, 43.25, None), tracked(1_000, 43.0, None), tracked(3_000, 45.0, Some(RejectReason::ImpossibleJump))];
+        let doc = build_geojson(&packets);
+        let features = doc["features"].as_array().unwrap();
+        assert_eq!(features.len(), 4);
+
+        let track = &features[0];
+        assert_eq!(track["properties"]["node"], "KD2ABC-2");
+        assert_eq!(track["geometry"]["coordinates"].as_array().unwrap().len(), 2);
+        assert_eq!(track["geometry"]["coordinates"][0][1], 43.0);
+
+        let point = &features[1]["properties"];
+        assert_eq!(point["callsign"], "KD2ABC");
+        assert_eq!(point["raw_lines"][0], "Lat: 43.0");
+        assert_eq!(point["timestamp_ms"], 1_000);
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Write;
+
+use super::{group_by_node, iso_time, track_position, xml_escape as escape, ExportRecord, NodeTrack};
+use crate::geo::{self, FEET_TO_METERS};
+
+// Descent rate is measured over this much of the end of the track
+const DESCENT_WINDOW_MS: i64 = 10_000;
+const MIN_DESCENT_SPAN_MS: i64 = 2_000;
+const MIN_DESCENT_RATE_MPS: f64 = 1.0;
+// Anything closer to the pad elevation than this is treated as already on the ground
+const LANDED_ABOVE_PAD_M: f64 = 30.0;
+
+struct Sample {
+    ts: i64,
+    lat: f64,
+    lon: f64,
+    alt_m: f64,
+}
+
+struct Landing {
+    lat: f64,
+    lon: f64,
+    ts: i64,
+}
+
+// Straight-line extrapolation of the recent descent down to pad elevation, using the horizontal
+// drift over the same stretch. Good enough to send a recovery team in the right direction.
+fn predict_landing(samples: &[Sample]) -> Option<Landing> {
+    let ground = samples.first()?.alt_m;
+    let last = samples.last()?;
+    if last.alt_m - ground < LANDED_ABOVE_PAD_M {
+        return None;
+    }
+    let reference = samples.iter().find(|s| last.ts - s.ts <= DESCENT_WINDOW_MS)?;
+    let dt_ms = last.ts - reference.ts;
+    if dt_ms < MIN_DESCENT_SPAN_MS {
+        return None;
+    }
+    let dt = dt_ms as f64 / 1000.0;
+    let descent = (reference.alt_m - last.alt_m) / dt;
+    if descent < MIN_DESCENT_RATE_MPS {
+        return None;
+    }
+
+    let (east, north) = geo::to_local((reference.lat, reference.lon), (last.lat, last.lon));
+    let time_to_ground = (last.alt_m - ground) / descent;
+    let (lat, lon) = geo::from_local((last.lat, last.lon), east / dt * time_to_ground, north / dt * time_to_ground);
+
+    Some(Landing { lat, lon, ts: last.ts + (time_to_ground * 1000.0) as i64 })
+}
+
+// GPX's own <fix> vocabulary; estimated fixes have no equivalent so they are left out.
+fn gpx_fix(pkt: &ExportRecord) -> Option<&'static str> {
+    match pkt.fix_status.as_deref()?.to_ascii_uppercase().as_str() {
+        "NOFIX" => Some("none"),
+        "DIFF" => Some("dgps"),
+        "FIX" if pkt.altitude_ft.is_some() => Some("3d"),
+        "FIX" => Some("2d"),
+        _ => None,
+    }
+}
+
+// Elements shared by <trkpt> and <wpt>, in the order the GPX 1.1 schema requires.
+fn write_point_body(out: &mut String, pkt: &ExportRecord, name: Option<&str>, indent: &str) {
+    if let Some(alt) = pkt.altitude_ft {
+        let _ = writeln!(out, "{}<ele>{:.1}</ele>", indent, alt as f64 * FEET_TO_METERS);
+    }
+    let _ = writeln!(out, "{}<time>{}</time>", indent, iso_time(pkt.ts));
+    if let Some(name) = name {
+        let _ = writeln!(out, "{}<name>{}</name>", indent, escape(name));
+    }
+    if let Some(fix) = gpx_fix(pkt) {
+        let _ = writeln!(out, "{}<fix>{}</fix>", indent, fix);
+    }
+    if let Some(sats) = pkt.sats {
+        let _ = writeln!(out, "{}<sat>{}</sat>", indent, sats);
+    }
+
+    let _ = writeln!(out, "{}<extensions>", indent);
+    if let Some(sats) = pkt.sats {
+        let _ = writeln!(out, "{}  <dispatch:satellites>{}</dispatch:satellites>", indent, sats);
+    }
+    if let Some(fix) = &pkt.fix_status {
+        let _ = writeln!(out, "{}  <dispatch:fixStatus>{}</dispatch:fixStatus>", indent, escape(fix));
+    }
+    if let Some(rssi) = pkt.rssi {
+        let _ = writeln!(out, "{}  <dispatch:rssi>{}</dispatch:rssi>", indent, rssi);
+    }
+    if let Some(snr) = pkt.snr {
+        let _ = writeln!(out, "{}  <dispatch:snr>{}</dispatch:snr>", indent, snr);
+    }
+    let _ = writeln!(out, "{}</extensions>", indent);
+}
+
+fn write_waypoints(out: &mut String, track: &NodeTrack, with_prediction: bool) {
+    let Some((last, (lat, lon))) = track.packets.iter().rev().find_map(|p| Some((*p, track_position(p)?))) else {
+        return;
+    };
+    let _ = writeln!(out, "  <wpt lat=\"{:.6}\" lon=\"{:.6}\">", lat, lon);
+    write_point_body(out, last, Some(&format!("{} last known", track.node)), "    ");
+    out.push_str("  </wpt>\n");
+
+    if !with_prediction {
+        return;
+    }
+    let samples: Vec<Sample> = track
+        .packets
+        .iter()
+        .filter_map(|p| {
+            let (lat, lon) = track_position(p)?;
+            Some(Sample { ts: p.ts, lat, lon, alt_m: p.altitude_ft? as f64 * FEET_TO_METERS })
+        })
+        .collect();
+    if let Some(landing) = predict_landing(&samples) {
+        let _ = write!(
+            out,
+            "  <wpt lat=\"{:.6}\" lon=\"{:.6}\">\n    <time>{}</time>\n    <name>{} predicted landing</name>\n    \
+             <desc>Extrapolated from the last {} s of descent</desc>\n  </wpt>\n",
+            landing.lat,
+            landing.lon,
+            iso_time(landing.ts),
+            escape(track.node),
+            DESCENT_WINDOW_MS / 1000,
+        );
+    }
+}
+
+pub fn build_gpx(packets: &[ExportRecord], waypoints_only: bool) -> String {
+    let tracks = group_by_node(packets);
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<gpx version=\"1.1\" creator=\"Dispatch\" xmlns=\"http://www.topografix.com/GPX/1/1\" \
+         xmlns:dispatch=\"https://github.com/AarC10/Dispatch-GSW\">\n",
+    );
+    let _ = writeln!(out, "  <metadata>\n    <time>{}</time>\n  </metadata>", iso_time(chrono::Utc::now().timestamp_millis()));
+
+    // The schema wants every <wpt> ahead of the first <trk>
+    for track in &tracks {
+        write_waypoints(&mut out, track, !waypoints_only);
+    }
+
+    if !waypoints_only {
+        for track in &tracks {
+            let _ = writeln!(out, "  <trk>\n    <name>{}</name>\n    <trkseg>", escape(track.node));
+            for pkt in &track.packets {
+                let Some((lat, lon)) = track_position(pkt) else {
+                    continue;
+                };
+                let _ = writeln!(out, "      <trkpt lat=\"{:.6}\" lon=\"{:.6}\">", lat, lon);
+                write_point_body(&mut out, pkt, None, "        ");
+                out.push_str("      </trkpt>\n");
+            }
+            out.push_str("    </trkseg>\n  </trk>\n");
+        }
+    }
+
+    out.push_str("</gpx>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::fixtures::record;
+
+    #[test]
+    fn predicts_landing_from_descent() {
+        // 1000 ft of pad elevation, then descending 100 ft/s while drifting north
+        let mut packets = vec![record("Node 1", 0, 43.0, Some(1_000))];
+        for i in 0..5 {
+            packets.push(record("Node 1", 100_000 + i * 1_000, 43.0 + i as f64 * 0.0001, Some(3_000 - i as i32 * 100)));
+        }
+        let doc = build_gpx(&packets, false);
+
+        assert!(doc.contains("<name>Node 1 last known</name>"));
+        assert!(doc.contains("<sat>9</sat>"));
+        assert!(doc.contains("<dispatch:fixStatus>FIX</dispatch:fixStatus>"));
+        assert_eq!(doc.matches("<trkpt").count(), 6);
+
+        // 1600 ft left at 100 ft/s is 16 s, another 0.0016 degrees north
+        let landing = doc.split("predicted landing").next().unwrap();
+        assert!(landing.contains("<wpt lat=\"43.002000\""));
+        assert!(doc.find("<wpt").unwrap() < doc.find("<trk>").unwrap());
+    }
+
+    #[test]
+    fn waypoints_only_skips_tracks_and_prediction() {
+        let packets = vec![record("Node 1", 0, 43.0, Some(1_000)), record("Node 1", 5_000, 43.1, Some(5_000)), record("Node 1", 6_000, 43.1, Some(4_900))];
+        let doc = build_gpx(&packets, true);
+        assert_eq!(doc.matches("<wpt").count(), 1);
+        assert!(!doc.contains("<trk>"));
+        assert!(doc.contains("<wpt lat=\"43.100000\""));
+    }
+}
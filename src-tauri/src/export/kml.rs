@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+use super::{group_by_node, iso_time, node_rgb, track_position, xml_escape as escape, ExportRecord, NodeTrack};
+use crate::geo::FEET_TO_METERS;
+
+struct TrackPoint {
+    lat: f64,
+    lon: f64,
+    alt_m: f64,
+    ts: i64,
+}
+
+// KML colors are aabbggrr
+fn kml_color(index: usize) -> String {
+    let (r, g, b) = node_rgb(index);
+    format!("ff{:02x}{:02x}{:02x}", b, g, r)
+}
+
+// Packets without an altitude reuse the last reported one so the line doesn't dive to sea level.
+// The flag is false when the node never reported altitude at all.
+fn track_points(track: &NodeTrack) -> (Vec<TrackPoint>, bool) {
+    let first_alt = track.packets.iter().find_map(|p| p.altitude_ft);
+    let mut last_alt = first_alt.unwrap_or(0);
+    let points = track
+        .packets
+        .iter()
+        .filter_map(|pkt| {
+            let (lat, lon) = track_position(pkt)?;
+            last_alt = pkt.altitude_ft.unwrap_or(last_alt);
+            Some(TrackPoint { lat, lon, alt_m: last_alt as f64 * FEET_TO_METERS, ts: pkt.ts })
+        })
+        .collect();
+    (points, first_alt.is_some())
+}
+
+fn write_point(out: &mut String, name: &str, style: &str, altitude_mode: &str, point: &TrackPoint) {
+    let _ = write!(
+        out,
+        "      <Placemark>\n        <name>{}</name>\n        <TimeStamp><when>{}</when></TimeStamp>\n        \
+         <description>{:.6}, {:.6} at {:.0} m</description>\n        <styleUrl>#{}</styleUrl>\n        \
+         <Point><altitudeMode>{}</altitudeMode><coordinates>{:.6},{:.6},{:.1}</coordinates></Point>\n      </Placemark>\n",
+        escape(name),
+        iso_time(point.ts),
+        point.lat,
+        point.lon,
+        point.alt_m,
+        style,
+        altitude_mode,
+        point.lon,
+        point.lat,
+        point.alt_m,
+    );
+}
+
+pub fn build_kml(packets: &[ExportRecord], include_track: bool) -> String {
+    let tracks = group_by_node(packets);
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\" xmlns:gx=\"http://www.google.com/kml/ext/2.2\">\n");
+    out.push_str("  <Document>\n    <name>Dispatch flight export</name>\n");
+
+    for track in &tracks {
+        let color = kml_color(track.index);
+        let _ = write!(
+            out,
+            "    <Style id=\"node-{}\">\n      <LineStyle><color>{}</color><width>3</width></LineStyle>\n      \
+             <IconStyle><color>{}</color></IconStyle>\n    </Style>\n",
+            track.index, color, color,
+        );
+    }
+
+    for track in &tracks {
+        let (points, has_altitude) = track_points(track);
+        let altitude_mode = if has_altitude { "absolute" } else { "clampToGround" };
+        let style = format!("node-{}", track.index);
+        let node = escape(track.node);
+
+        let _ = writeln!(out, "    <Folder>\n      <name>{}</name>", node);
+
+        if points.len() > 1 {
+            let coordinates: Vec<String> =
+                points.iter().map(|p| format!("{:.6},{:.6},{:.1}", p.lon, p.lat, p.alt_m)).collect();
+            let _ = write!(
+                out,
+                "      <Placemark>\n        <name>{} path</name>\n        <styleUrl>#{}</styleUrl>\n        \
+                 <LineString><altitudeMode>{}</altitudeMode><coordinates>{}</coordinates></LineString>\n      </Placemark>\n",
+                node,
+                style,
+                altitude_mode,
+                coordinates.join(" "),
+            );
+        }
+
+        if let (Some(first), Some(last)) = (points.first(), points.last()) {
+            write_point(&mut out, "First fix", &style, altitude_mode, first);
+            if has_altitude {
+                // First of equal maxima, i.e. when the node actually got there
+                let apogee = points.iter().fold(first, |best, p| if p.alt_m > best.alt_m { p } else { best });
+                write_point(&mut out, "Apogee", &style, altitude_mode, apogee);
+            }
+            write_point(&mut out, "Last known position", &style, altitude_mode, last);
+        }
+
+        if include_track && !points.is_empty() {
+            let _ = write!(
+                out,
+                "      <Placemark>\n        <name>{} track</name>\n        <styleUrl>#{}</styleUrl>\n        \
+                 <gx:Track>\n          <altitudeMode>{}</altitudeMode>\n",
+                node, style, altitude_mode,
+            );
+            for p in &points {
+                let _ = writeln!(out, "          <when>{}</when>", iso_time(p.ts));
+            }
+            for p in &points {
+                let _ = writeln!(out, "          <gx:coord>{:.6} {:.6} {:.1}</gx:coord>", p.lon, p.lat, p.alt_m);
+            }
+            out.push_str("        </gx:Track>\n      </Placemark>\n");
+        }
+
+        out.push_str("    </Folder>\n");
+    }
+
+    out.push_str("  </Document>\n</kml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::fixtures::record;
+
+    #[test]
+    fn writes_folder_per_node_with_apogee_and_track() {
+        let packets = vec![
+            record("Node 1", 1_000, 43.0, Some(500)),
+            record("Node 2", 1_500, 44.0, None),
+            record("Node 1", 2_000, 43.1, Some(10_000)),
+            record("Node 1", 3_000, 43.2, None),
+        ];
+        let doc = build_kml(&packets, true);
+
+        assert_eq!(doc.matches("<Folder>").count(), 2);
+        assert!(doc.contains("<color>ff1c1ae4</color>"));
+        // 10000 ft apogee, carried forward for the packet that lost altitude
+        assert!(doc.contains("<coordinates>-77.500000,43.100000,3048.0</coordinates>"));
+        assert!(doc.contains("-77.500000,43.200000,3048.0"));
+        assert_eq!(doc.matches("<name>Apogee</name>").count(), 1);
+        assert!(doc.contains("<when>1970-01-01T00:00:02.000Z</when>"));
+        assert!(doc.contains("<altitudeMode>clampToGround</altitudeMode>"));
+
+        assert!(!build_kml(&packets, false).contains("gx:Track"));
+    }
+}
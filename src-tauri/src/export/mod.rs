@@ -0,0 +1,749 @@
+pub mod archive;
+mod dialect;
+mod geojson;
+mod gpx;
+mod kml;
+mod report;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use dirs::download_dir;
+
+use crate::db;
+use crate::geo::{self, FEET_TO_METERS};
+use crate::session::{self, PacketQuery};
+use crate::telemetry::DataPacket;
+
+pub use dialect::{CoordinateFormat, CsvDialect};
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn iso_time(ts: i64) -> String {
+    DateTime::from_timestamp_millis(ts)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+// Legacy payload built from the UI's packet list; kept for the export_packets_* commands.
+#[derive(Debug, Deserialize)]
+pub struct FrontendPacket {
+    pub node_id: String,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub altitude_ft: Option<i32>,
+    pub rssi: Option<f64>,
+    pub snr: Option<f64>,
+    pub fix_status: Option<String>,
+    pub sats: Option<u64>,
+    pub ts: i64,
+    pub filtered_lat: Option<f64>,
+    pub filtered_lon: Option<f64>,
+    pub rejected: Option<String>,
+}
+
+// Common shape every exporter works from, whether the packets came from the session store or from
+// the frontend's legacy payload.
+#[derive(Debug, Clone, Default)]
+pub struct ExportRecord {
+    pub node: String,
+    pub node_id: Option<u8>,
+    pub callsign: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub altitude_ft: Option<i32>,
+    pub rssi: Option<f64>,
+    pub snr: Option<f64>,
+    pub fix_status: Option<String>,
+    pub sats: Option<u64>,
+    pub ts: i64,
+    pub filtered_lat: Option<f64>,
+    pub filtered_lon: Option<f64>,
+    pub rejected: Option<String>,
+    pub device_uptime_ms: Option<u64>,
+    pub receivers: Vec<String>,
+    pub raw_lines: Vec<String>,
+    // Filled in by derive_fields for the derived CSV columns
+    pub derived: Derived,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Derived {
+    pub altitude_agl_ft: Option<f64>,
+    pub distance_from_pad_m: Option<f64>,
+    pub bearing_from_pad: Option<f64>,
+    pub ground_speed_mps: Option<f64>,
+    pub vertical_speed_mps: Option<f64>,
+}
+
+impl From<FrontendPacket> for ExportRecord {
+    fn from(pkt: FrontendPacket) -> Self {
+        ExportRecord {
+            node: pkt.node_id,
+            lat: pkt.lat,
+            lon: pkt.lon,
+            altitude_ft: pkt.altitude_ft,
+            rssi: pkt.rssi,
+            snr: pkt.snr,
+            fix_status: pkt.fix_status,
+            sats: pkt.sats,
+            ts: pkt.ts,
+            filtered_lat: pkt.filtered_lat,
+            filtered_lon: pkt.filtered_lon,
+            rejected: pkt.rejected,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&DataPacket> for ExportRecord {
+    fn from(pkt: &DataPacket) -> Self {
+        // Going through the decimal text keeps f32 coordinates from gaining spurious digits
+        let widen = |v: f32| v.to_string().parse::<f64>().unwrap_or(v as f64);
+        let filtered = pkt.filtered.as_ref();
+        ExportRecord {
+            node: pkt.node_key(),
+            node_id: pkt.node_id,
+            callsign: pkt.callsign.clone(),
+            lat: pkt.latitude.map(widen),
+            lon: pkt.longitude.map(widen),
+            altitude_ft: pkt.altitude_ft,
+            rssi: pkt.receiver_rssi.map(f64::from),
+            snr: pkt.receiver_snr.map(f64::from),
+            fix_status: Some(pkt.fix_status.label().to_string()),
+            sats: pkt.satellites_count.map(u64::from),
+            ts: pkt.timestamp_ms,
+            filtered_lat: filtered.map(|f| f.latitude),
+            filtered_lon: filtered.map(|f| f.longitude),
+            rejected: filtered.and_then(|f| f.rejected).map(|r| format!("{r:?}")),
+            device_uptime_ms: pkt.device_uptime_ms,
+            receivers: pkt.receptions.iter().map(|r| r.port.clone()).collect(),
+            raw_lines: pkt.raw_lines.clone(),
+            derived: Derived::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CsvColumn {
+    Time,
+    // Added after Time by csv_columns() when the time format has no offset of its own
+    UtcOffset,
+    Node,
+    Latitude,
+    Longitude,
+    AltitudeFt,
+    Rssi,
+    Snr,
+    Fix,
+    Satellites,
+    FilteredLatitude,
+    FilteredLongitude,
+    Rejected,
+    NodeId,
+    Callsign,
+    DeviceUptimeMs,
+    Receivers,
+    RawLines,
+    // Latitude and longitude together, for the UTM and MGRS coordinate formats
+    Position,
+    FilteredPosition,
+    // Derived per node from the session's packets
+    AltitudeAgl,
+    DistanceFromPad,
+    BearingFromPad,
+    GroundSpeed,
+    VerticalSpeed,
+}
+
+// The default CSV columns: the original export's columns in their original order, followed by the
+// position filter's output
+const DEFAULT_COLUMNS: [CsvColumn; 12] = [
+    CsvColumn::Time,
+    CsvColumn::Node,
+    CsvColumn::Latitude,
+    CsvColumn::Longitude,
+    CsvColumn::AltitudeFt,
+    CsvColumn::Rssi,
+    CsvColumn::Snr,
+    CsvColumn::Fix,
+    CsvColumn::Satellites,
+    CsvColumn::FilteredLatitude,
+    CsvColumn::FilteredLongitude,
+    CsvColumn::Rejected,
+];
+
+pub const ALL_COLUMNS: [CsvColumn; 17] = [
+    CsvColumn::Time,
+    CsvColumn::Node,
+    CsvColumn::Latitude,
+    CsvColumn::Longitude,
+    CsvColumn::AltitudeFt,
+    CsvColumn::Rssi,
+    CsvColumn::Snr,
+    CsvColumn::Fix,
+    CsvColumn::Satellites,
+    CsvColumn::FilteredLatitude,
+    CsvColumn::FilteredLongitude,
+    CsvColumn::Rejected,
+    CsvColumn::NodeId,
+    CsvColumn::Callsign,
+    CsvColumn::DeviceUptimeMs,
+    CsvColumn::Receivers,
+    CsvColumn::RawLines,
+];
+
+pub const DERIVED_COLUMNS: [CsvColumn; 5] = [
+    CsvColumn::AltitudeAgl,
+    CsvColumn::DistanceFromPad,
+    CsvColumn::BearingFromPad,
+    CsvColumn::GroundSpeed,
+    CsvColumn::VerticalSpeed,
+];
+
+impl CsvColumn {
+    pub fn from_header(header: &str) -> Option<CsvColumn> {
+        ALL_COLUMNS.into_iter().chain([CsvColumn::UtcOffset]).find(|c| c.header().eq_ignore_ascii_case(header.trim()))
+    }
+
+    pub fn header(self) -> &'static str {
+        match self {
+            CsvColumn::Time => "Time",
+            CsvColumn::UtcOffset => "UTC Offset",
+            CsvColumn::Node => "Node",
+            CsvColumn::Latitude => "Latitude",
+            CsvColumn::Longitude => "Longitude",
+            CsvColumn::AltitudeFt => "Altitude (ft)",
+            CsvColumn::Rssi => "RSSI (dBm)",
+            CsvColumn::Snr => "SNR (dB)",
+            CsvColumn::Fix => "Fix",
+            CsvColumn::Satellites => "Satellites in View",
+            CsvColumn::FilteredLatitude => "Filtered Latitude",
+            CsvColumn::FilteredLongitude => "Filtered Longitude",
+            CsvColumn::Rejected => "Rejected",
+            CsvColumn::NodeId => "Node ID",
+            CsvColumn::Callsign => "Callsign",
+            CsvColumn::DeviceUptimeMs => "Receiver Uptime (ms)",
+            CsvColumn::Receivers => "Receivers",
+            CsvColumn::RawLines => "Raw Lines",
+            CsvColumn::Position => "Position",
+            CsvColumn::FilteredPosition => "Filtered Position",
+            CsvColumn::AltitudeAgl => "Altitude AGL (ft)",
+            CsvColumn::DistanceFromPad => "Distance from Pad (ft)",
+            CsvColumn::BearingFromPad => "Bearing from Pad (deg)",
+            CsvColumn::GroundSpeed => "Ground Speed (ft/s)",
+            CsvColumn::VerticalSpeed => "Vertical Speed (ft/s)",
+        }
+    }
+
+    // Header with the dialect's length unit in place of feet
+    pub fn header_for(self, dialect: &CsvDialect) -> String {
+        self.header().replace("(ft", &format!("({}", dialect.length_unit()))
+    }
+
+    pub fn value(self, pkt: &ExportRecord) -> String {
+        self.format_value(pkt, &CsvDialect::default())
+    }
+
+    pub fn format_value(self, pkt: &ExportRecord, dialect: &CsvDialect) -> String {
+        let axis = |v: Option<f64>, is_latitude| v.map(|v| dialect.axis(v, is_latitude)).unwrap_or_default();
+        let position = |lat: Option<f64>, lon: Option<f64>| match (lat, lon) {
+            (Some(lat), Some(lon)) => dialect.position(lat, lon),
+            _ => String::new(),
+        };
+        let derived = pkt.derived;
+        match self {
+            CsvColumn::Time => dialect.time(pkt.ts),
+            CsvColumn::UtcOffset => dialect.utc_offset(pkt.ts),
+            CsvColumn::Node => pkt.node.clone(),
+            CsvColumn::Latitude => axis(pkt.lat, true),
+            CsvColumn::Longitude => axis(pkt.lon, false),
+            CsvColumn::AltitudeFt => pkt.altitude_ft.map(|v| dialect.altitude(v as f64)).unwrap_or_default(),
+            CsvColumn::Rssi => pkt.rssi.map(|v| dialect.localize(format!("{v}"))).unwrap_or_default(),
+            CsvColumn::Snr => pkt.snr.map(|v| dialect.localize(format!("{v}"))).unwrap_or_default(),
+            CsvColumn::Fix => pkt.fix_status.clone().unwrap_or_default(),
+            CsvColumn::Satellites => pkt.sats.map(|v| v.to_string()).unwrap_or_default(),
+            CsvColumn::FilteredLatitude => axis(pkt.filtered_lat, true),
+            CsvColumn::FilteredLongitude => axis(pkt.filtered_lon, false),
+            CsvColumn::Rejected => pkt.rejected.clone().unwrap_or_default(),
+            CsvColumn::NodeId => pkt.node_id.map(|v| v.to_string()).unwrap_or_default(),
+            CsvColumn::Callsign => pkt.callsign.clone().unwrap_or_default(),
+            CsvColumn::DeviceUptimeMs => pkt.device_uptime_ms.map(|v| v.to_string()).unwrap_or_default(),
+            CsvColumn::Receivers => pkt.receivers.join(";"),
+            CsvColumn::RawLines => pkt.raw_lines.join(" | "),
+            CsvColumn::Position => position(pkt.lat, pkt.lon),
+            CsvColumn::FilteredPosition => position(pkt.filtered_lat, pkt.filtered_lon),
+            CsvColumn::AltitudeAgl => derived.altitude_agl_ft.map(|v| dialect.altitude(v)).unwrap_or_default(),
+            CsvColumn::DistanceFromPad => derived.distance_from_pad_m.map(|v| dialect.distance(v)).unwrap_or_default(),
+            CsvColumn::BearingFromPad => derived.bearing_from_pad.map(|v| dialect.number(v, 0)).unwrap_or_default(),
+            CsvColumn::GroundSpeed => derived.ground_speed_mps.map(|v| dialect.speed(v)).unwrap_or_default(),
+            CsvColumn::VerticalSpeed => derived.vertical_speed_mps.map(|v| dialect.speed(v)).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Csv,
+    Kml,
+    Gpx,
+    Geojson,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Kml => "kml",
+            ExportFormat::Gpx => "gpx",
+            ExportFormat::Geojson => "geojson",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    // Time range and node subset
+    #[serde(flatten)]
+    pub query: PacketQuery,
+    // CSV only; defaults to the classic column set
+    pub columns: Option<Vec<CsvColumn>>,
+    // CSV only; separators, time zone, units and coordinate format
+    pub dialect: Option<CsvDialect>,
+    // CSV only; appended to the columns when not already selected
+    pub include_callsign: Option<bool>,
+    pub include_raw_lines: Option<bool>,
+    pub include_derived: Option<bool>,
+    // KML only
+    pub include_track: Option<bool>,
+    // GPX only
+    pub waypoints_only: Option<bool>,
+}
+
+// Exports from the backend session store, which holds every packet of the session at full detail.
+#[tauri::command]
+pub async fn export_session(_app: AppHandle, options: ExportOptions, path: Option<String>) -> Result<String, String> {
+    let packets = session::query(&options.query)?;
+    if packets.is_empty() {
+        return Err("No packets to export".into());
+    }
+
+    let target = path.map(PathBuf::from).unwrap_or_else(|| default_export_path(options.format.extension()));
+    let records = || packets.iter().map(ExportRecord::from).collect::<Vec<_>>();
+    match options.format {
+        ExportFormat::Csv => {
+            let dialect = options.dialect.clone().unwrap_or_default();
+            dialect.validate()?;
+            let columns = csv_columns(&options, &dialect);
+            let mut records = records();
+            derive_fields(&mut records);
+            write_csv(&target, &records, &columns, &dialect).map_err(|e| format!("Failed to write CSV: {e}"))?
+        }
+        ExportFormat::Kml => write_kml(&target, &records(), options.include_track.unwrap_or(true))?,
+        ExportFormat::Gpx => write_gpx(&target, &records(), options.waypoints_only.unwrap_or(false))?,
+        ExportFormat::Geojson => write_geojson(&target, &packets)?,
+    }
+    Ok(target.to_string_lossy().into_owned())
+}
+
+fn csv_columns(options: &ExportOptions, dialect: &CsvDialect) -> Vec<CsvColumn> {
+    let mut columns = options.columns.clone().unwrap_or_else(|| {
+        let grid = matches!(dialect.coordinates, CoordinateFormat::Utm | CoordinateFormat::Mgrs);
+        DEFAULT_COLUMNS
+            .into_iter()
+            .filter_map(|column| match column {
+                // A grid reference can't be split into latitude and longitude
+                CsvColumn::Latitude if grid => Some(CsvColumn::Position),
+                CsvColumn::FilteredLatitude if grid => Some(CsvColumn::FilteredPosition),
+                CsvColumn::Longitude | CsvColumn::FilteredLongitude if grid => None,
+                other => Some(other),
+            })
+            .collect()
+    });
+    let mut extra = Vec::new();
+    if options.include_callsign.unwrap_or(false) {
+        extra.push(CsvColumn::Callsign);
+    }
+    if options.include_derived.unwrap_or(false) {
+        extra.extend(DERIVED_COLUMNS);
+    }
+    if options.include_raw_lines.unwrap_or(false) {
+        extra.push(CsvColumn::RawLines);
+    }
+    for column in extra {
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    // Spreadsheet times are local to the dialect's zone, so the file says which offset that was
+    if dialect.needs_offset_column() && !columns.contains(&CsvColumn::UtcOffset) {
+        if let Some(time) = columns.iter().position(|&c| c == CsvColumn::Time) {
+            columns.insert(time + 1, CsvColumn::UtcOffset);
+        }
+    }
+    columns
+}
+
+// Fills in each record's derived fields from the packets before it of the same node: altitude
+// above the first reported altitude, distance and bearing from the first accepted fix, and speeds
+// between consecutive samples.
+fn derive_fields(records: &mut [ExportRecord]) {
+    struct NodeState {
+        ground_ft: Option<f64>,
+        pad: Option<(f64, f64)>,
+        last_position: Option<(i64, (f64, f64))>,
+        last_altitude: Option<(i64, f64)>,
+    }
+
+    let mut order: Vec<usize> = (0..records.len()).collect();
+    order.sort_by_key(|&i| records[i].ts);
+    let mut nodes: HashMap<String, NodeState> = HashMap::new();
+    for i in order {
+        let record = &records[i];
+        let state = nodes
+            .entry(record.node.clone())
+            .or_insert(NodeState { ground_ft: None, pad: None, last_position: None, last_altitude: None });
+        let mut derived = Derived::default();
+
+        if let Some(altitude) = record.altitude_ft.map(f64::from) {
+            let ground = *state.ground_ft.get_or_insert(altitude);
+            derived.altitude_agl_ft = Some(altitude - ground);
+            if let Some((ts, previous)) = state.last_altitude.filter(|(ts, _)| *ts < record.ts) {
+                derived.vertical_speed_mps = Some((altitude - previous) * FEET_TO_METERS / ((record.ts - ts) as f64 / 1000.0));
+            }
+            state.last_altitude = Some((record.ts, altitude));
+        }
+        if let Some(position) = track_position(record) {
+            let pad = *state.pad.get_or_insert(position);
+            let (distance, bearing) = geo::distance_bearing(pad, position);
+            derived.distance_from_pad_m = Some(distance);
+            derived.bearing_from_pad = (distance > 0.0).then_some(bearing);
+            if let Some((ts, previous)) = state.last_position.filter(|(ts, _)| *ts < record.ts) {
+                let (moved, _) = geo::distance_bearing(previous, position);
+                derived.ground_speed_mps = Some(moved / ((record.ts - ts) as f64 / 1000.0));
+            }
+            state.last_position = Some((record.ts, position));
+        }
+        records[i].derived = derived;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    // A session from the history database; the current session when not given
+    pub session_id: Option<i64>,
+    pub title: Option<String>,
+    // Launch pad [latitude, longitude]; each node's first fix when not given
+    pub pad: Option<[f64; 2]>,
+}
+
+// Writes a flight summary report for one session.
+#[tauri::command]
+pub async fn export_report(_app: AppHandle, options: ReportOptions, path: Option<String>) -> Result<String, String> {
+    let (name, notes, packets) = match options.session_id {
+        Some(id) => {
+            let (summary, packets) = db::session_packets(id)?;
+            (summary.name, summary.notes, packets)
+        }
+        None => (None, None, session::query(&PacketQuery::default())?),
+    };
+    if packets.is_empty() {
+        return Err("No packets to report on".into());
+    }
+
+    let records: Vec<ExportRecord> = packets.iter().map(ExportRecord::from).collect();
+    let started = records.iter().map(|r| r.ts).min().unwrap_or_default();
+    let title = options
+        .title
+        .or(name)
+        .unwrap_or_else(|| format!("Flight report {}", iso_time(started).get(..10).unwrap_or_default()));
+    let input = report::ReportInput { title: &title, notes: notes.as_deref(), pad: options.pad.map(|[lat, lon]| (lat, lon)) };
+    let markdown = options.format == ReportFormat::Markdown;
+    let target = path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_export_path(if markdown { "md" } else { "html" }));
+    std::fs::write(&target, report::build_report(&records, &input, markdown)).map_err(|e| format!("Failed to write report: {e}"))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveOptions {
+    // A session from the history database; the one currently shown when not given
+    pub session_id: Option<i64>,
+}
+
+// Writes a zip of everything recorded in a session for handing to teammates and reviewers.
+#[tauri::command]
+pub async fn export_archive(_app: AppHandle, options: Option<ArchiveOptions>, path: Option<String>) -> Result<String, String> {
+    let session_id = options.unwrap_or_default().session_id.or_else(db::shown_session);
+    let (summary, packets, history) = match session_id {
+        Some(id) => {
+            let (summary, packets) = db::session_packets(id)?;
+            (Some(summary), packets, db::session_history(id)?)
+        }
+        // Without the history database only the packets themselves are available
+        None => (None, session::query(&PacketQuery::default())?, db::SessionHistory::default()),
+    };
+    if packets.is_empty() {
+        return Err("No packets to export".into());
+    }
+
+    let started_ms = summary.as_ref().map(|s| s.started_ms).unwrap_or(packets[0].timestamp_ms);
+    let session = archive::ArchiveSession {
+        name: summary.as_ref().and_then(|s| s.name.as_deref()),
+        notes: summary.as_ref().and_then(|s| s.notes.as_deref()),
+        started_ms,
+        ended_ms: summary.as_ref().and_then(|s| s.ended_ms),
+        ports: summary.as_ref().map(|s| s.ports.clone()).unwrap_or_default(),
+        packets: &packets,
+        history: &history,
+    };
+    let target = path.map(PathBuf::from).unwrap_or_else(|| default_export_path("zip"));
+    archive::write_archive(&target, &session).map_err(|e| format!("Failed to write archive: {e}"))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub async fn export_packets_csv(_app: AppHandle, packets: Vec<FrontendPacket>, path: Option<String>) -> Result<String, String> {
+    if packets.is_empty() {
+        return Err("No packets to export".into());
+    }
+
+    let target = path.map(PathBuf::from).unwrap_or_else(|| default_export_path("csv"));
+    let records: Vec<ExportRecord> = packets.into_iter().map(ExportRecord::from).collect();
+    write_csv(&target, &records, &DEFAULT_COLUMNS, &CsvDialect::default()).map_err(|e| format!("Failed to write CSV: {e}"))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+// Writes a Google Earth flight replay; `include_track` adds a timestamped gx:Track per node.
+#[tauri::command]
+pub async fn export_packets_kml(
+    _app: AppHandle,
+    packets: Vec<FrontendPacket>,
+    path: Option<String>,
+    include_track: Option<bool>,
+) -> Result<String, String> {
+    if packets.is_empty() {
+        return Err("No packets to export".into());
+    }
+
+    let target = path.map(PathBuf::from).unwrap_or_else(|| default_export_path("kml"));
+    let records: Vec<ExportRecord> = packets.into_iter().map(ExportRecord::from).collect();
+    write_kml(&target, &records, include_track.unwrap_or(true))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+// Writes a GPX 1.1 file for handheld GPS units; `waypoints_only` produces just the last known
+// position of each node.
+#[tauri::command]
+pub async fn export_packets_gpx(
+    _app: AppHandle,
+    packets: Vec<FrontendPacket>,
+    path: Option<String>,
+    waypoints_only: Option<bool>,
+) -> Result<String, String> {
+    if packets.is_empty() {
+        return Err("No packets to export".into());
+    }
+
+    let target = path.map(PathBuf::from).unwrap_or_else(|| default_export_path("gpx"));
+    let records: Vec<ExportRecord> = packets.into_iter().map(ExportRecord::from).collect();
+    write_gpx(&target, &records, waypoints_only.unwrap_or(false))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+// Takes the packets as the backend emitted them so every DataPacket field ends up in the properties.
+#[tauri::command]
+pub async fn export_packets_geojson(_app: AppHandle, packets: Vec<DataPacket>, path: Option<String>) -> Result<String, String> {
+    if packets.is_empty() {
+        return Err("No packets to export".into());
+    }
+
+    let target = path.map(PathBuf::from).unwrap_or_else(|| default_export_path("geojson"));
+    write_geojson(&target, &packets)?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+fn write_kml(path: &PathBuf, records: &[ExportRecord], include_track: bool) -> Result<(), String> {
+    std::fs::write(path, kml::build_kml(records, include_track)).map_err(|e| format!("Failed to write KML: {e}"))
+}
+
+fn write_gpx(path: &PathBuf, records: &[ExportRecord], waypoints_only: bool) -> Result<(), String> {
+    std::fs::write(path, gpx::build_gpx(records, waypoints_only)).map_err(|e| format!("Failed to write GPX: {e}"))
+}
+
+fn write_geojson(path: &PathBuf, packets: &[DataPacket]) -> Result<(), String> {
+    let doc = serde_json::to_string_pretty(&geojson::build_geojson(packets)).map_err(|e| e.to_string())?;
+    std::fs::write(path, doc).map_err(|e| format!("Failed to write GeoJSON: {e}"))
+}
+
+fn default_export_path(extension: &str) -> PathBuf {
+    let default_name = format!("packets-{}.{}", Utc::now().format("%Y%m%dT%H%M%S"), extension);
+    let mut base = download_dir().unwrap_or_else(|| dirs::home_dir().unwrap_or(PathBuf::from(".")));
+    base.push(&default_name);
+    base
+}
+
+// Same palette as colorForIndex() in the frontend so exported tracks match the map
+const PALETTE: [(u8, u8, u8); 9] = [
+    (0xe4, 0x1a, 0x1c),
+    (0x37, 0x7e, 0xb8),
+    (0x4d, 0xaf, 0x4a),
+    (0x98, 0x4e, 0xa3),
+    (0xff, 0x7f, 0x00),
+    (0xff, 0xff, 0x33),
+    (0xa6, 0x56, 0x28),
+    (0xf7, 0x81, 0xbf),
+    (0x99, 0x99, 0x99),
+];
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = lightness - c / 2.0;
+    let channel = |v: f64| ((v + m) * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+// Color of the `index`-th node to be heard, matching the map
+fn node_rgb(index: usize) -> (u8, u8, u8) {
+    PALETTE
+        .get(index)
+        .copied()
+        .unwrap_or_else(|| hsl_to_rgb((index as f64 * 137.508) % 360.0, 0.7, 0.5))
+}
+
+// Packets of one node in time order; `index` is the order the node was first heard in, which is
+// also how the map assigns colors.
+struct NodeTrack<'a> {
+    node: &'a str,
+    index: usize,
+    packets: Vec<&'a ExportRecord>,
+}
+
+fn group_by_node(packets: &[ExportRecord]) -> Vec<NodeTrack<'_>> {
+    let mut tracks: Vec<NodeTrack> = Vec::new();
+    for pkt in packets {
+        match tracks.iter_mut().find(|t| t.node == pkt.node) {
+            Some(track) => track.packets.push(pkt),
+            None => tracks.push(NodeTrack { node: &pkt.node, index: tracks.len(), packets: vec![pkt] }),
+        }
+    }
+    for track in &mut tracks {
+        track.packets.sort_by_key(|p| p.ts);
+    }
+    tracks
+}
+
+// Position to plot for a packet: filtered when available, nothing for rejected fixes.
+fn track_position(pkt: &ExportRecord) -> Option<(f64, f64)> {
+    if pkt.rejected.is_some() {
+        return None;
+    }
+    match (pkt.filtered_lat, pkt.filtered_lon) {
+        (Some(lat), Some(lon)) => Some((lat, lon)),
+        _ => Some((pkt.lat?, pkt.lon?)),
+    }
+}
+
+fn write_csv(path: &PathBuf, packets: &[ExportRecord], columns: &[CsvColumn], dialect: &CsvDialect) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(path)?;
+    write_csv_to(BufWriter::new(file), packets, columns, dialect)
+}
+
+fn write_csv_to<W: Write>(
+    out: W,
+    packets: &[ExportRecord],
+    columns: &[CsvColumn],
+    dialect: &CsvDialect,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = csv::WriterBuilder::new().delimiter(dialect.delimiter as u8).from_writer(out);
+
+    writer.write_record(columns.iter().map(|c| c.header_for(dialect)))?;
+    for pkt in packets.iter() {
+        DateTime::from_timestamp_millis(pkt.ts).ok_or_else(|| format!("invalid timestamp: {}", pkt.ts))?;
+        writer.write_record(columns.iter().map(|c| c.format_value(pkt, dialect)))?;
+    }
+
+    writer.flush()?;
+    writer.into_inner().map_err(|e| e.to_string())?.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{FilteredPosition, FixStatus, Reception, RejectReason};
+
+    #[test]
+    fn session_packets_keep_backend_detail() {
+        let pkt = DataPacket {
+            node_id: Some(4),
+            callsign: Some("KD2YIE".into()),
+            latitude: Some(43.0847),
+            longitude: Some(-77.6744),
+            fix_status: FixStatus::Diff,
+            timestamp_ms: 1_700_000_000_000,
+            raw_lines: vec!["Node 4".into(), "Fix status: DIFF".into()],
+            filtered: Some(FilteredPosition { latitude: 43.0, longitude: -77.0, altitude_ft: None, rejected: Some(RejectReason::LowSatellites) }),
+            receptions: vec![Reception { port: "COM3".into(), rssi: None, snr: None, arrival_ms: 0, device_uptime_ms: None }],
+            ..Default::default()
+        };
+        let record = ExportRecord::from(&pkt);
+        let columns = [CsvColumn::Node, CsvColumn::Latitude, CsvColumn::Fix, CsvColumn::Rejected, CsvColumn::Callsign, CsvColumn::Receivers];
+        let values: Vec<String> = columns.iter().map(|c| c.value(&record)).collect();
+        assert_eq!(values, vec!["KD2YIE-4", "43.084700", "DIFF", "LowSatellites", "KD2YIE", "COM3"]);
+        assert_eq!(CsvColumn::RawLines.value(&record), "Node 4 | Fix status: DIFF");
+    }
+}
+
+#[cfg(test)]
+pub(super) mod fixtures {
+    use super::ExportRecord;
+
+    // A good fix as the exporters see it; tests override whatever they care about
+    pub fn record(node: &str, ts: i64, lat: f64, altitude_ft: Option<i32>) -> ExportRecord {
+        ExportRecord {
+            node: node.into(),
+            lat: Some(lat),
+            lon: Some(-77.5),
+            altitude_ft,
+            rssi: Some(-90.0),
+            snr: Some(6.0),
+            fix_status: Some("FIX".into()),
+            sats: Some(9),
+            ts,
+            ..Default::default()
+        }
+    }
+}
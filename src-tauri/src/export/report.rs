@@ -0,0 +1,465 @@
+use std::fmt::Write;
+
+use super::{group_by_node, iso_time, node_rgb, track_position, xml_escape as escape, ExportRecord, NodeTrack};
+use crate::geo::{self, FEET_TO_METERS};
+
+// Anything closer to the pad elevation than this counts as on the ground
+const ON_GROUND_FT: i32 = 100;
+// Final descent rate is measured over the last stretch before touchdown
+const FINAL_DESCENT_WINDOW_MS: i64 = 10_000;
+const MIN_DESCENT_SPAN_MS: i64 = 2_000;
+
+const PLOT_WIDTH: f64 = 640.0;
+const PLOT_HEIGHT: f64 = 420.0;
+const CHART_HEIGHT: f64 = 200.0;
+const MARGIN: f64 = 40.0;
+// Smallest area the track plot shows, so a rocket that never left the pad isn't zoomed to noise
+const MIN_PLOT_SPAN_M: f64 = 200.0;
+
+pub struct ReportInput<'a> {
+    pub title: &'a str,
+    pub notes: Option<&'a str>,
+    // Launch pad; each node's first accepted fix when not given
+    pub pad: Option<(f64, f64)>,
+}
+
+struct Stats {
+    min: f64,
+    mean: f64,
+    max: f64,
+}
+
+fn stats(values: impl Iterator<Item = f64>) -> Option<Stats> {
+    let values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    Some(Stats {
+        min: values.iter().copied().fold(f64::INFINITY, f64::min),
+        mean: values.iter().sum::<f64>() / values.len() as f64,
+        max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+struct NodeSummary<'a> {
+    node: &'a str,
+    color: String,
+    packets: usize,
+    positions: usize,
+    rejected: usize,
+    no_fix: usize,
+    longest_gap_ms: i64,
+    launch_ms: Option<i64>,
+    // (altitude ft, time)
+    apogee: Option<(i32, i64)>,
+    avg_descent_fps: Option<f64>,
+    final_descent_fps: Option<f64>,
+    pad: Option<(f64, f64)>,
+    landing: Option<(f64, f64)>,
+    rssi: Option<Stats>,
+    snr: Option<Stats>,
+    track: Vec<(f64, f64)>,
+    altitude: Vec<(i64, f64)>,
+    rssi_series: Vec<(i64, f64)>,
+    snr_series: Vec<(i64, f64)>,
+}
+
+fn summarize<'a>(track: &NodeTrack<'a>, pad: Option<(f64, f64)>) -> NodeSummary<'a> {
+    let packets = &track.packets;
+    let positions: Vec<(f64, f64)> = packets.iter().filter_map(|p| track_position(p)).collect();
+    let alts: Vec<(i64, i32)> = packets
+        .iter()
+        .filter(|p| p.rejected.is_none())
+        .filter_map(|p| Some((p.ts, p.altitude_ft?)))
+        .collect();
+    let pad_alt = alts.first().map(|&(_, alt)| alt);
+    let apogee = alts.iter().max_by_key(|&&(_, alt)| alt).map(|&(ts, alt)| (alt, ts));
+    let flew = matches!((apogee, pad_alt), (Some((top, _)), Some(pad)) if top - pad > ON_GROUND_FT);
+
+    let mut launch_ms = None;
+    let mut avg_descent_fps = None;
+    let mut final_descent_fps = None;
+    if let (true, Some((top, top_ms)), Some(pad_alt)) = (flew, apogee, pad_alt) {
+        launch_ms = alts.iter().find(|&&(_, alt)| alt - pad_alt > ON_GROUND_FT).map(|&(ts, _)| ts);
+        let descent: Vec<(i64, i32)> = alts.iter().copied().filter(|&(ts, _)| ts > top_ms).collect();
+        let touchdown = descent.iter().find(|&&(_, alt)| alt - pad_alt < ON_GROUND_FT).or(descent.last()).copied();
+        if let Some((down_ms, down_alt)) = touchdown {
+            let secs = (down_ms - top_ms) as f64 / 1000.0;
+            avg_descent_fps = Some((top - down_alt) as f64 / secs);
+            let reference = descent.iter().find(|&&(ts, _)| down_ms - ts <= FINAL_DESCENT_WINDOW_MS);
+            if let Some(&(ref_ms, ref_alt)) = reference.filter(|&&(ts, _)| down_ms - ts >= MIN_DESCENT_SPAN_MS) {
+                final_descent_fps = Some((ref_alt - down_alt) as f64 / ((down_ms - ref_ms) as f64 / 1000.0));
+            }
+        }
+    }
+
+    let (r, g, b) = node_rgb(track.index);
+    NodeSummary {
+        node: track.node,
+        color: format!("#{:02x}{:02x}{:02x}", r, g, b),
+        packets: packets.len(),
+        positions: positions.len(),
+        rejected: packets.iter().filter(|p| p.rejected.is_some()).count(),
+        no_fix: packets.iter().filter(|p| p.fix_status.as_deref() == Some("NOFIX")).count(),
+        longest_gap_ms: packets.windows(2).map(|w| w[1].ts - w[0].ts).max().unwrap_or(0),
+        launch_ms,
+        apogee: apogee.filter(|_| flew),
+        avg_descent_fps,
+        final_descent_fps,
+        pad: pad.or_else(|| positions.first().copied()),
+        landing: positions.last().copied(),
+        rssi: stats(packets.iter().filter_map(|p| p.rssi)),
+        snr: stats(packets.iter().filter_map(|p| p.snr)),
+        altitude: alts.iter().map(|&(ts, alt)| (ts, alt as f64)).collect(),
+        rssi_series: packets.iter().filter_map(|p| Some((p.ts, p.rssi?))).collect(),
+        snr_series: packets.iter().filter_map(|p| Some((p.ts, p.snr?))).collect(),
+        track: positions,
+    }
+}
+
+fn compass_point(bearing: f64) -> &'static str {
+    const POINTS: [&str; 16] = ["N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW"];
+    POINTS[((bearing / 22.5).round() as usize) % 16]
+}
+
+// "m:ss", or "h:mm:ss" for long sessions
+fn clock(ms: i64) -> String {
+    let secs = ms.max(0) / 1000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+fn or_dash(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "—".into())
+}
+
+fn stats_cell(stats: &Option<Stats>) -> String {
+    or_dash(stats.as_ref().map(|s| format!("{:.0} / {:.1} / {:.0}", s.min, s.mean, s.max)))
+}
+
+struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn html(&self, out: &mut String) {
+        out.push_str("<table>\n<tr>");
+        for header in &self.headers {
+            let _ = write!(out, "<th>{}</th>", escape(header));
+        }
+        out.push_str("</tr>\n");
+        for row in &self.rows {
+            out.push_str("<tr>");
+            for cell in row {
+                let _ = write!(out, "<td>{}</td>", escape(cell));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+    }
+
+    fn markdown(&self, out: &mut String) {
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        out.push_str(&line(self.headers.iter().map(|h| h.to_string()).collect()));
+        out.push_str(&line(self.headers.iter().map(|_| "---".to_string()).collect()));
+        for row in &self.rows {
+            out.push_str(&line(row.iter().map(|c| c.replace('|', "\\|")).collect()));
+        }
+    }
+}
+
+enum Section {
+    Table(Table),
+    Svg(String),
+}
+
+fn flight_table(nodes: &[NodeSummary]) -> Table {
+    let rows = nodes
+        .iter()
+        .map(|n| {
+            let from_pad = match (n.pad, n.landing) {
+                (Some(pad), Some(landing)) => {
+                    let (distance, bearing) = geo::distance_bearing(pad, landing);
+                    Some(format!("{:.0} m ({:.0} ft) at {:.0}° {}", distance, distance / FEET_TO_METERS, bearing, compass_point(bearing)))
+                }
+                _ => None,
+            };
+            vec![
+                n.node.to_string(),
+                or_dash(n.apogee.map(|(alt, _)| format!("{alt} ft"))),
+                or_dash(n.apogee.map(|(_, ts)| match n.launch_ms {
+                    Some(launch) => format!("{} (T+{})", iso_time(ts), clock(ts - launch)),
+                    None => iso_time(ts),
+                })),
+                or_dash(n.avg_descent_fps.map(|v| format!("{:.0} ft/s ({:.1} m/s)", v, v * FEET_TO_METERS))),
+                or_dash(n.final_descent_fps.map(|v| format!("{:.0} ft/s ({:.1} m/s)", v, v * FEET_TO_METERS))),
+                or_dash(n.landing.map(|(lat, lon)| format!("{lat:.6}, {lon:.6}"))),
+                or_dash(from_pad),
+            ]
+        })
+        .collect();
+    Table {
+        headers: vec!["Node", "Max altitude", "Apogee", "Avg descent", "Final descent", "Landing", "From pad"],
+        rows,
+    }
+}
+
+fn packet_table(nodes: &[NodeSummary]) -> Table {
+    let rows = nodes
+        .iter()
+        .map(|n| {
+            vec![
+                n.node.to_string(),
+                n.packets.to_string(),
+                n.positions.to_string(),
+                n.rejected.to_string(),
+                n.no_fix.to_string(),
+                format!("{:.1} s", n.longest_gap_ms as f64 / 1000.0),
+                stats_cell(&n.rssi),
+                stats_cell(&n.snr),
+            ]
+        })
+        .collect();
+    Table {
+        headers: vec!["Node", "Packets", "Positions", "Rejected", "No fix", "Longest gap", "RSSI dBm (min/avg/max)", "SNR dB (min/avg/max)"],
+        rows,
+    }
+}
+
+fn svg_open(out: &mut String, height: f64) {
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"{w}\" height=\"{h}\" font-family=\"sans-serif\" font-size=\"11\">",
+        w = PLOT_WIDTH,
+        h = height
+    );
+    let _ = writeln!(out, "<rect width=\"{}\" height=\"{}\" fill=\"#ffffff\" stroke=\"#cccccc\"/>", PLOT_WIDTH, height);
+}
+
+fn polyline(out: &mut String, color: &str, points: impl Iterator<Item = (f64, f64)>) {
+    let points: Vec<String> = points.map(|(x, y)| format!("{x:.1},{y:.1}")).collect();
+    if points.len() > 1 {
+        let _ = writeln!(out, "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>", color, points.join(" "));
+    }
+}
+
+// Ground track on a local flat projection around the pad, with a scale bar and north arrow.
+fn track_svg(nodes: &[NodeSummary], origin: (f64, f64)) -> String {
+    let project = |point: (f64, f64)| geo::to_local(origin, point);
+    let all: Vec<(f64, f64)> = nodes
+        .iter()
+        .flat_map(|n| n.track.iter().copied().chain(n.pad))
+        .map(project)
+        .chain(std::iter::once((0.0, 0.0)))
+        .collect();
+    let (min_e, max_e) = all.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+    let (min_n, max_n) = all.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+    let span_e = (max_e - min_e).max(MIN_PLOT_SPAN_M);
+    let span_n = (max_n - min_n).max(MIN_PLOT_SPAN_M);
+    let scale = ((PLOT_WIDTH - 2.0 * MARGIN) / span_e).min((PLOT_HEIGHT - 2.0 * MARGIN) / span_n);
+    let mid = ((min_e + max_e) / 2.0, (min_n + max_n) / 2.0);
+    let to_svg = |point: (f64, f64)| {
+        let (e, n) = project(point);
+        (PLOT_WIDTH / 2.0 + (e - mid.0) * scale, PLOT_HEIGHT / 2.0 - (n - mid.1) * scale)
+    };
+
+    let mut out = String::new();
+    svg_open(&mut out, PLOT_HEIGHT);
+    for node in nodes {
+        polyline(&mut out, &node.color, node.track.iter().map(|&p| to_svg(p)));
+    }
+    for node in nodes {
+        if let Some(pad) = node.pad {
+            let (x, y) = to_svg(pad);
+            let _ = writeln!(out, "<polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"#000000\"/>", x, y - 6.0, x - 5.0, y + 4.0, x + 5.0, y + 4.0);
+        }
+        if let Some(landing) = node.landing {
+            let (x, y) = to_svg(landing);
+            let _ = writeln!(out, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"5\" fill=\"{}\" stroke=\"#000000\"/>", x, y, node.color);
+            let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>", x + 8.0, y + 4.0, escape(node.node));
+        }
+    }
+
+    // Largest round length that fits in a third of the plot
+    let bar_m = [10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 10_000.0, 20_000.0, 50_000.0]
+        .into_iter()
+        .rev()
+        .find(|m| m * scale <= (PLOT_WIDTH - 2.0 * MARGIN) / 3.0)
+        .unwrap_or(10.0);
+    let bar_px = bar_m * scale;
+    let (bx, by) = (MARGIN / 2.0, PLOT_HEIGHT - MARGIN / 2.0);
+    let _ = writeln!(out, "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#000000\" stroke-width=\"2\"/>", bx, by, bx + bar_px, by);
+    let label = if bar_m >= 1_000.0 { format!("{} km", bar_m / 1_000.0) } else { format!("{bar_m} m") };
+    let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>", bx, by - 5.0, label);
+    let (nx, ny) = (PLOT_WIDTH - MARGIN / 2.0, MARGIN / 2.0);
+    let _ = writeln!(out, "<polygon points=\"{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}\" fill=\"#000000\"/>", nx, ny - 8.0, nx - 5.0, ny + 6.0, nx + 5.0, ny + 6.0);
+    let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">N</text>", nx, ny + 18.0);
+    out.push_str("</svg>\n");
+    out
+}
+
+// One value over session time, a (color, samples) line per node. None when no node has any samples.
+fn chart_svg(title: &str, series: &[(&str, &[(i64, f64)])], start_ms: i64) -> Option<String> {
+    let samples: Vec<(i64, f64)> = series.iter().flat_map(|(_, s)| s.iter().copied()).collect();
+    if samples.is_empty() {
+        return None;
+    }
+    let end_ms = samples.iter().map(|s| s.0).max()?.max(start_ms + 1);
+    let (mut lo, mut hi) = samples.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), s| (lo.min(s.1), hi.max(s.1)));
+    if hi - lo < 1.0 {
+        lo -= 1.0;
+        hi += 1.0;
+    }
+    let (left, right, top, bottom) = (MARGIN + 10.0, PLOT_WIDTH - MARGIN / 2.0, MARGIN / 2.0 + 10.0, CHART_HEIGHT - MARGIN / 2.0 - 5.0);
+    let to_svg = |(ts, v): (i64, f64)| {
+        (
+            left + (ts - start_ms) as f64 / (end_ms - start_ms) as f64 * (right - left),
+            bottom - (v - lo) / (hi - lo) * (bottom - top),
+        )
+    };
+
+    let mut out = String::new();
+    svg_open(&mut out, CHART_HEIGHT);
+    let _ = writeln!(out, "<text x=\"{:.1}\" y=\"16\" font-weight=\"bold\">{}</text>", left, escape(title));
+    let _ = writeln!(out, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"none\" stroke=\"#999999\"/>", left, top, right - left, bottom - top);
+    let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{:.0}</text>", left - 4.0, top + 4.0, hi);
+    let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{:.0}</text>", left - 4.0, bottom, lo);
+    let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\">0:00</text>", left, bottom + 14.0);
+    let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>", right, bottom + 14.0, clock(end_ms - start_ms));
+    for (color, samples) in series {
+        polyline(&mut out, color, samples.iter().map(|&s| to_svg(s)));
+    }
+    out.push_str("</svg>\n");
+    Some(out)
+}
+
+// A flight summary for the club and RSO. The SVG plots are inlined so the file stands alone.
+pub fn build_report(records: &[ExportRecord], input: &ReportInput, markdown: bool) -> String {
+    let tracks = group_by_node(records);
+    let nodes: Vec<NodeSummary> = tracks.iter().map(|t| summarize(t, input.pad)).collect();
+    let start_ms = records.iter().map(|r| r.ts).min().unwrap_or(0);
+    let end_ms = records.iter().map(|r| r.ts).max().unwrap_or(0);
+    let origin = input.pad.or_else(|| nodes.iter().find_map(|n| n.pad));
+
+    let mut sections: Vec<(&str, Section)> = vec![
+        ("Flight", Section::Table(flight_table(&nodes))),
+        ("Packets", Section::Table(packet_table(&nodes))),
+    ];
+    if let Some(origin) = origin {
+        sections.push(("Ground track", Section::Svg(track_svg(&nodes, origin))));
+    }
+    let series = |pick: for<'n> fn(&'n NodeSummary<'_>) -> &'n Vec<(i64, f64)>| {
+        nodes.iter().map(|n| (n.color.as_str(), pick(n).as_slice())).collect::<Vec<_>>()
+    };
+    let charts = [
+        chart_svg("Altitude (ft)", &series(|n| &n.altitude), start_ms),
+        chart_svg("RSSI (dBm)", &series(|n| &n.rssi_series), start_ms),
+        chart_svg("SNR (dB)", &series(|n| &n.snr_series), start_ms),
+    ];
+    for chart in charts.into_iter().flatten() {
+        sections.push(("Over time", Section::Svg(chart)));
+    }
+
+    let node_list = nodes.iter().map(|n| n.node).collect::<Vec<_>>().join(", ");
+    let overview = [
+        format!("Session: {} to {} ({})", iso_time(start_ms), iso_time(end_ms), clock(end_ms - start_ms)),
+        format!("Nodes seen: {} ({})", nodes.len(), if node_list.is_empty() { "none" } else { &node_list }),
+        format!("Packets: {}", records.len()),
+        format!("Generated {} by Dispatch {}", iso_time(chrono::Utc::now().timestamp_millis()), env!("CARGO_PKG_VERSION")),
+    ];
+
+    let mut out = String::new();
+    if markdown {
+        let _ = writeln!(out, "# {}\n", input.title);
+        for line in &overview {
+            let _ = writeln!(out, "- {line}");
+        }
+        let mut previous = "";
+        for (heading, section) in &sections {
+            if *heading != previous {
+                let _ = writeln!(out, "\n## {heading}\n");
+                previous = heading;
+            }
+            match section {
+                Section::Table(table) => table.markdown(&mut out),
+                Section::Svg(svg) => {
+                    out.push_str(svg);
+                    out.push('\n');
+                }
+            }
+        }
+        if let Some(notes) = input.notes {
+            let _ = writeln!(out, "\n## Notes\n\n{notes}");
+        }
+    } else {
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+             body {{ font-family: sans-serif; margin: 2em; color: #222; }}\n\
+             table {{ border-collapse: collapse; margin-bottom: 1em; }}\n\
+             th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}\n\
+             th {{ background: #f0f0f0; }}\n\
+             svg {{ display: block; margin-bottom: 1em; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n<ul>\n",
+            title = escape(input.title)
+        );
+        for line in &overview {
+            let _ = writeln!(out, "<li>{}</li>", escape(line));
+        }
+        out.push_str("</ul>\n");
+        let mut previous = "";
+        for (heading, section) in &sections {
+            if *heading != previous {
+                let _ = writeln!(out, "<h2>{}</h2>", escape(heading));
+                previous = heading;
+            }
+            match section {
+                Section::Table(table) => table.html(&mut out),
+                Section::Svg(svg) => out.push_str(svg),
+            }
+        }
+        if let Some(notes) = input.notes {
+            let _ = writeln!(out, "<h2>Notes</h2>\n<p>{}</p>", escape(notes));
+        }
+        out.push_str("</body>\n</html>\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::fixtures::record;
+
+    #[test]
+    fn summarizes_apogee_descent_and_landing() {
+        // Pad at 500 ft, up to 3500 ft at T+10 s, then down at 50 ft/s drifting north
+        let mut packets = vec![record("Node 1", 0, 43.0, Some(500)), record("Node 1", 5_000, 43.0, Some(2_000)), record("Node 1", 10_000, 43.0, Some(3_500))];
+        for i in 1..=60 {
+            packets.push(record("Node 1", 10_000 + i * 1_000, 43.0 + i as f64 * 0.0001, Some(3_500 - i as i32 * 50)));
+        }
+        let tracks = group_by_node(&packets);
+        let node = summarize(&tracks[0], None);
+
+        assert_eq!(node.apogee, Some((3_500, 10_000)));
+        assert_eq!(node.launch_ms, Some(5_000));
+        assert_eq!(node.avg_descent_fps.map(f64::round), Some(50.0));
+        assert_eq!(node.final_descent_fps.map(f64::round), Some(50.0));
+        let (distance, bearing) = geo::distance_bearing(node.pad.unwrap(), node.landing.unwrap());
+        assert!((distance - 667.0).abs() < 2.0, "{distance}");
+        assert_eq!(compass_point(bearing), "N");
+
+        let input = ReportInput { title: "Test flight", notes: Some("Nominal"), pad: None };
+        let html = build_report(&packets, &input, false);
+        assert!(html.contains("<td>3500 ft</td>"));
+        assert_eq!(html.matches("<svg").count(), 4);
+        let md = build_report(&packets, &input, true);
+        assert!(md.starts_with("# Test flight"));
+        assert!(md.contains("| Node 1 | 3500 ft |"));
+        assert!(md.contains("## Notes\n\nNominal"));
+    }
+}
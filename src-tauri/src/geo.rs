@@ -0,0 +1,48 @@
+// Distances and local projections shared by the filter, geofences and exporters.
+
+pub const EARTH_RADIUS_M: f64 = 6_371_000.0;
+pub const FEET_TO_METERS: f64 = 0.3048;
+
+// Local east/north metres of `point` relative to `origin`, both (lat, lon). Equirectangular, which
+// is good to a few metres over a launch site.
+pub fn to_local(origin: (f64, f64), point: (f64, f64)) -> (f64, f64) {
+    let east = (point.1 - origin.1).to_radians() * EARTH_RADIUS_M * origin.0.to_radians().cos();
+    let north = (point.0 - origin.0).to_radians() * EARTH_RADIUS_M;
+    (east, north)
+}
+
+// Inverse of to_local()
+pub fn from_local(origin: (f64, f64), east: f64, north: f64) -> (f64, f64) {
+    let lat = origin.0 + (north / EARTH_RADIUS_M).to_degrees();
+    let lon = origin.1 + (east / (EARTH_RADIUS_M * origin.0.to_radians().cos())).to_degrees();
+    (lat, lon)
+}
+
+// Great-circle distance in metres and initial bearing in degrees from `from` to `to`.
+pub fn distance_bearing(from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let (dlat, dlon) = (lat2 - lat1, lon2 - lon1);
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    let distance = 2.0 * EARTH_RADIUS_M * a.sqrt().asin();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    (distance, (y.atan2(x).to_degrees() + 360.0) % 360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projections_agree() {
+        let pad = (42.7, -77.19);
+        let (east, north) = to_local(pad, (42.71, -77.18));
+        let (lat, lon) = from_local(pad, east, north);
+        assert!((lat - 42.71).abs() < 1e-9 && (lon + 77.18).abs() < 1e-9);
+
+        let (distance, bearing) = distance_bearing(pad, (42.71, -77.18));
+        assert!((distance - east.hypot(north)).abs() < 1.0);
+        assert!((bearing - east.atan2(north).to_degrees()).abs() < 0.1);
+    }
+}
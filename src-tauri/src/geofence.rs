@@ -0,0 +1,512 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::alerts;
+use crate::geo;
+use crate::telemetry::DataPacket;
+
+const FENCES_FILE: &str = "geofences.json";
+// A node has to be this far past a boundary before it counts as crossing it, so GPS noise
+// along an edge doesn't flap between enter and exit
+const CROSSING_MARGIN_M: f64 = 10.0;
+
+lazy_static! {
+    static ref RE_KML_PLACEMARK: Regex = Regex::new(r"(?s)<Placemark\b.*?</Placemark>").unwrap();
+    static ref RE_KML_NAME: Regex = Regex::new(r"(?s)<name>\s*(.*?)\s*</name>").unwrap();
+    static ref RE_KML_OUTER: Regex = Regex::new(r"(?s)<outerBoundaryIs>.*?<coordinates>(.*?)</coordinates>").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FenceKind {
+    // Waiver boundary: the tracker is expected to stay inside
+    KeepIn,
+    // Roads, water, private land: the tracker is expected to stay out
+    KeepOut,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FenceShape {
+    // Outer ring as [lat, lon] pairs; the closing vertex is optional
+    Polygon { points: Vec<[f64; 2]> },
+    #[serde(rename_all = "camelCase")]
+    Circle { center: [f64; 2], radius_m: f64 },
+}
+
+impl FenceShape {
+    // A polygon needs three distinct corners and a circle a positive radius, or every fix would
+    // read as outside it
+    pub fn validate(&self) -> Result<(), String> {
+        let finite = |p: &[f64; 2]| p.iter().all(|v| v.is_finite());
+        match self {
+            FenceShape::Polygon { points } => {
+                let open_len = match (points.first(), points.last()) {
+                    (Some(first), Some(last)) if points.len() > 1 && first == last => points.len() - 1,
+                    _ => points.len(),
+                };
+                if open_len < 3 {
+                    return Err("A polygon needs at least 3 points".into());
+                }
+                if !points.iter().all(finite) {
+                    return Err("Polygon points must be numbers".into());
+                }
+            }
+            FenceShape::Circle { center, radius_m } => {
+                if !finite(center) {
+                    return Err("The circle's center must be numbers".into());
+                }
+                if !(radius_m.is_finite() && *radius_m > 0.0) {
+                    return Err(format!("Invalid circle radius {radius_m}"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Geofence {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: FenceKind,
+    pub shape: FenceShape,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FenceStatus {
+    pub fence_id: String,
+    pub fence_name: String,
+    pub inside: bool,
+    // Unsigned distance to the fence edge
+    pub distance_m: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeFenceStatus {
+    pub node: String,
+    pub fences: Vec<FenceStatus>,
+    pub nearest: Option<FenceStatus>,
+}
+
+#[derive(Default)]
+struct GeofenceState {
+    fences: Vec<Geofence>,
+    // node -> fence id -> inside, after crossing hysteresis
+    inside: BTreeMap<String, BTreeMap<String, bool>>,
+    status: BTreeMap<String, NodeFenceStatus>,
+}
+
+static STATE: OnceLock<Mutex<GeofenceState>> = OnceLock::new();
+
+fn get_state() -> &'static Mutex<GeofenceState> {
+    STATE.get_or_init(|| Mutex::new(GeofenceState::default()))
+}
+
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq == 0.0 { 0.0 } else { (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0) };
+    (p.0 - (a.0 + t * dx)).hypot(p.1 - (a.1 + t * dy))
+}
+
+// Signed distance to the fence edge in metres: positive inside, negative outside.
+pub fn signed_distance(shape: &FenceShape, lat: f64, lon: f64) -> f64 {
+    let here = (lat, lon);
+    match shape {
+        FenceShape::Circle { center, radius_m } => {
+            let (e, n) = geo::to_local(here, (center[0], center[1]));
+            radius_m - e.hypot(n)
+        }
+        FenceShape::Polygon { points } => {
+            if points.len() < 3 {
+                return f64::NEG_INFINITY;
+            }
+            // Work in a plane centred on the tracker so the point itself is (0, 0)
+            let ring: Vec<(f64, f64)> = points.iter().map(|p| geo::to_local(here, (p[0], p[1]))).collect();
+            let mut inside = false;
+            let mut nearest = f64::INFINITY;
+            for i in 0..ring.len() {
+                let a = ring[i];
+                let b = ring[(i + 1) % ring.len()];
+                if (a.1 > 0.0) != (b.1 > 0.0) && 0.0 < a.0 + (0.0 - a.1) * (b.0 - a.0) / (b.1 - a.1) {
+                    inside = !inside;
+                }
+                nearest = nearest.min(segment_distance((0.0, 0.0), a, b));
+            }
+            if inside {
+                nearest
+            } else {
+                -nearest
+            }
+        }
+    }
+}
+
+fn parse_kind(value: Option<&Value>, fallback: FenceKind) -> FenceKind {
+    match value.and_then(Value::as_str).map(|s| s.to_ascii_lowercase()) {
+        Some(s) if s == "keepin" || s == "keep_in" || s == "boundary" || s == "waiver" => FenceKind::KeepIn,
+        Some(s) if s == "keepout" || s == "keep_out" || s == "hazard" => FenceKind::KeepOut,
+        _ => fallback,
+    }
+}
+
+fn geojson_ring(ring: &Value) -> Option<Vec<[f64; 2]>> {
+    ring.as_array()?
+        .iter()
+        .map(|pos| {
+            let pos = pos.as_array()?;
+            // GeoJSON positions are [lon, lat]
+            Some([pos.get(1)?.as_f64()?, pos.first()?.as_f64()?])
+        })
+        .collect()
+}
+
+fn collect_geojson(value: &Value, name: &str, kind: FenceKind, out: &mut Vec<Geofence>) {
+    match value.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => {
+            for feature in value.get("features").and_then(Value::as_array).into_iter().flatten() {
+                collect_geojson(feature, name, kind, out);
+            }
+        }
+        Some("Feature") => {
+            let props = value.get("properties");
+            let name = props.and_then(|p| p.get("name")).and_then(Value::as_str).unwrap_or(name);
+            let kind = parse_kind(props.and_then(|p| p.get("kind")), kind);
+            let Some(geometry) = value.get("geometry") else {
+                return;
+            };
+            // Circles have no GeoJSON geometry of their own; a Point with a radius stands in
+            if geometry.get("type").and_then(Value::as_str) == Some("Point") {
+                let radius = props.and_then(|p| p.get("radius_m").or_else(|| p.get("radius"))).and_then(Value::as_f64);
+                let coords = geometry.get("coordinates").and_then(Value::as_array);
+                if let (Some(radius_m), Some(coords)) = (radius, coords) {
+                    if let (Some(lon), Some(lat)) = (coords.first().and_then(Value::as_f64), coords.get(1).and_then(Value::as_f64)) {
+                        out.push(Geofence {
+                            id: String::new(),
+                            name: name.to_string(),
+                            kind,
+                            shape: FenceShape::Circle { center: [lat, lon], radius_m },
+                        });
+                    }
+                }
+                return;
+            }
+            collect_geojson(geometry, name, kind, out);
+        }
+        Some("Polygon") => {
+            if let Some(points) = value.get("coordinates").and_then(|c| c.get(0)).and_then(geojson_ring) {
+                out.push(Geofence { id: String::new(), name: name.to_string(), kind, shape: FenceShape::Polygon { points } });
+            }
+        }
+        Some("MultiPolygon") => {
+            for polygon in value.get("coordinates").and_then(Value::as_array).into_iter().flatten() {
+                if let Some(points) = polygon.get(0).and_then(geojson_ring) {
+                    out.push(Geofence { id: String::new(), name: name.to_string(), kind, shape: FenceShape::Polygon { points } });
+                }
+            }
+        }
+        Some("GeometryCollection") => {
+            for geometry in value.get("geometries").and_then(Value::as_array).into_iter().flatten() {
+                collect_geojson(geometry, name, kind, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn parse_geojson(text: &str, default_name: &str, kind: FenceKind) -> Result<Vec<Geofence>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| format!("Invalid GeoJSON: {e}"))?;
+    let mut fences = Vec::new();
+    collect_geojson(&value, default_name, kind, &mut fences);
+    fences.retain(|f| f.shape.validate().is_ok());
+    Ok(fences)
+}
+
+// Only the outer ring of each Placemark polygon is used; KML has no circle primitive.
+pub fn parse_kml(text: &str, default_name: &str, kind: FenceKind) -> Vec<Geofence> {
+    RE_KML_PLACEMARK
+        .find_iter(text)
+        .flat_map(|placemark| {
+            let block = placemark.as_str();
+            let name = RE_KML_NAME
+                .captures(block)
+                .and_then(|c| c.get(1))
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_else(|| default_name.to_string());
+            RE_KML_OUTER
+                .captures_iter(block)
+                .filter_map(|c| {
+                    let points: Vec<[f64; 2]> = c[1]
+                        .split_whitespace()
+                        .filter_map(|tuple| {
+                            let mut parts = tuple.split(',');
+                            let lon = parts.next()?.parse::<f64>().ok()?;
+                            let lat = parts.next()?.parse::<f64>().ok()?;
+                            Some([lat, lon])
+                        })
+                        .collect();
+                    let shape = FenceShape::Polygon { points };
+                    shape.validate().is_ok().then(|| Geofence { id: String::new(), name: name.clone(), kind, shape })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn fences_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| format!("No config directory: {e}"))?;
+    Ok(dir.join(FENCES_FILE))
+}
+
+fn save_fences(app: &AppHandle, fences: &[Geofence]) -> Result<(), String> {
+    let path = fences_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(fences).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to save geofences: {e}"))
+}
+
+// Restores fences saved by a previous session, if any.
+pub fn load_fences(app: &AppHandle) {
+    let Ok(path) = fences_path(app) else {
+        return;
+    };
+    let Ok(text) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<Vec<Geofence>>(&text) {
+        Ok(mut fences) => {
+            fences.retain(|f| f.shape.validate().is_ok());
+            if let Ok(mut state) = get_state().lock() {
+                state.fences = fences;
+            }
+        }
+        Err(e) => eprintln!("Ignoring unreadable {}: {e}", path.display()),
+    }
+}
+
+fn assign_ids(existing: &[Geofence], fences: &mut [Geofence]) {
+    let stamp = Utc::now().timestamp_millis();
+    for (i, fence) in fences.iter_mut().enumerate() {
+        if fence.id.is_empty() || existing.iter().any(|f| f.id == fence.id) {
+            fence.id = format!("fence-{stamp}-{i}");
+        }
+    }
+}
+
+// Where a node now is relative to a fence, after crossing hysteresis, and the violation to report
+// if that changed anything. A first sample that is already on the wrong side counts as a violation.
+fn crossing(fence: &Geofence, was_inside: Option<bool>, distance: f64) -> (bool, Option<(bool, String)>) {
+    let inside = match was_inside {
+        Some(true) => distance > -CROSSING_MARGIN_M,
+        Some(false) => distance > CROSSING_MARGIN_M,
+        None => distance > 0.0,
+    };
+    // Leaving a keep-in fence or entering a keep-out fence is the violation
+    let violation = match fence.kind {
+        FenceKind::KeepIn => !inside,
+        FenceKind::KeepOut => inside,
+    };
+    let change = match was_inside {
+        Some(was) if was != inside => Some((violation, format!("{} {}", if inside { "entered" } else { "left" }, fence.name))),
+        None if violation => Some((violation, format!("{} {}", if inside { "inside" } else { "outside" }, fence.name))),
+        _ => None,
+    };
+    (inside, change)
+}
+
+pub fn process_packet(app: &AppHandle, pkt: &DataPacket) {
+    // Rejected fixes would make a tracker hop across a boundary and straight back
+    let (lat, lon) = match (&pkt.filtered, pkt.latitude, pkt.longitude) {
+        (Some(filtered), _, _) if filtered.rejected.is_none() => (filtered.latitude, filtered.longitude),
+        (None, Some(lat), Some(lon)) => (lat as f64, lon as f64),
+        _ => return,
+    };
+    let node = pkt.node_key();
+
+    let (status, transitions) = {
+        let Ok(mut state) = get_state().lock() else {
+            return;
+        };
+        if state.fences.is_empty() {
+            return;
+        }
+        let state = &mut *state;
+        let inside_map = state.inside.entry(node.clone()).or_default();
+        let mut transitions = Vec::new();
+        let mut fences = Vec::new();
+        for fence in &state.fences {
+            let distance = signed_distance(&fence.shape, lat, lon);
+            let (inside, change) = crossing(fence, inside_map.get(&fence.id).copied(), distance);
+            inside_map.insert(fence.id.clone(), inside);
+            if let Some((violation, message)) = change {
+                transitions.push((fence.id.clone(), violation, message));
+            }
+            fences.push(FenceStatus {
+                fence_id: fence.id.clone(),
+                fence_name: fence.name.clone(),
+                inside,
+                distance_m: distance.abs(),
+            });
+        }
+        let nearest = fences.iter().min_by(|a, b| a.distance_m.total_cmp(&b.distance_m)).cloned();
+        let status = NodeFenceStatus { node: node.clone(), fences, nearest };
+        state.status.insert(node.clone(), status.clone());
+        (status, transitions)
+    };
+
+    for (fence_id, violation, message) in transitions {
+        alerts::geofence_transition(app, &node, &fence_id, violation, message, pkt.timestamp_ms);
+    }
+    let _ = app.emit("geofence-status", status);
+}
+
+#[tauri::command]
+pub fn list_geofences() -> Result<Vec<Geofence>, String> {
+    let state = get_state().lock().map_err(|e| format!("geofence lock error: {}", e))?;
+    Ok(state.fences.clone())
+}
+
+#[tauri::command]
+pub fn add_geofence(app: AppHandle, fence: Geofence) -> Result<Geofence, String> {
+    fence.shape.validate().map_err(|e| format!("{}: {e}", fence.name))?;
+    let mut state = get_state().lock().map_err(|e| format!("geofence lock error: {}", e))?;
+    let mut fence = fence;
+    assign_ids(&state.fences, std::slice::from_mut(&mut fence));
+    state.fences.push(fence.clone());
+    save_fences(&app, &state.fences)?;
+    Ok(fence)
+}
+
+#[tauri::command]
+pub fn remove_geofence(app: AppHandle, id: String) -> Result<(), String> {
+    let mut state = get_state().lock().map_err(|e| format!("geofence lock error: {}", e))?;
+    let before = state.fences.len();
+    state.fences.retain(|f| f.id != id);
+    if state.fences.len() == before {
+        return Err(format!("Unknown geofence: {id}"));
+    }
+    for inside in state.inside.values_mut() {
+        inside.remove(&id);
+    }
+    save_fences(&app, &state.fences)?;
+    drop(state);
+    alerts::forget_geofence(&app, &id, Utc::now().timestamp_millis());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn import_geofences(app: AppHandle, path: String, kind: Option<FenceKind>) -> Result<Vec<Geofence>, String> {
+    let file = Path::new(&path);
+    let text = fs::read_to_string(file).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    let default_name = file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "Geofence".into());
+    let kind = kind.unwrap_or(FenceKind::KeepOut);
+
+    let is_kml = file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("kml"));
+    let mut fences = if is_kml {
+        parse_kml(&text, &default_name, kind)
+    } else {
+        parse_geojson(&text, &default_name, kind)?
+    };
+    if fences.is_empty() {
+        return Err("No polygons or circles found".into());
+    }
+
+    let mut state = get_state().lock().map_err(|e| format!("geofence lock error: {}", e))?;
+    assign_ids(&state.fences, &mut fences);
+    state.fences.extend(fences.iter().cloned());
+    save_fences(&app, &state.fences)?;
+    Ok(fences)
+}
+
+#[tauri::command]
+pub fn get_geofence_status() -> Result<Vec<NodeFenceStatus>, String> {
+    let state = get_state().lock().map_err(|e| format!("geofence lock error: {}", e))?;
+    Ok(state.status.values().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> FenceShape {
+        // Roughly 1.1 km on a side around 42.70, -77.19
+        FenceShape::Polygon {
+            points: vec![[42.695, -77.195], [42.705, -77.195], [42.705, -77.185], [42.695, -77.185]],
+        }
+    }
+
+    #[test]
+    fn polygon_inside_outside_and_edge_distance() {
+        let center = signed_distance(&square(), 42.700, -77.190);
+        assert!(center > 400.0 && center < 600.0);
+
+        // ~111 m north of the top edge
+        let outside = signed_distance(&square(), 42.706, -77.190);
+        assert!((outside + 111.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn first_sample_in_violation_is_reported() {
+        let fence = |kind| Geofence { id: "f".into(), name: "Waiver".into(), kind, shape: square() };
+        assert_eq!(crossing(&fence(FenceKind::KeepIn), None, -500.0), (false, Some((true, "outside Waiver".into()))));
+        assert_eq!(crossing(&fence(FenceKind::KeepIn), None, 500.0), (true, None));
+        assert_eq!(crossing(&fence(FenceKind::KeepOut), None, 500.0), (true, Some((true, "inside Waiver".into()))));
+        // Within the margin of the edge nothing changes
+        assert_eq!(crossing(&fence(FenceKind::KeepIn), Some(true), -5.0), (true, None));
+        assert_eq!(crossing(&fence(FenceKind::KeepIn), Some(false), 20.0), (true, Some((false, "entered Waiver".into()))));
+    }
+
+    #[test]
+    fn circle_distance() {
+        let shape = FenceShape::Circle { center: [42.7, -77.19], radius_m: 200.0 };
+        assert!((signed_distance(&shape, 42.7, -77.19) - 200.0).abs() < 1e-6);
+        assert!(signed_distance(&shape, 42.71, -77.19) < -800.0);
+    }
+
+    #[test]
+    fn parses_geojson_and_kml() {
+        let geojson = r#"{"type":"FeatureCollection","features":[
+            {"type":"Feature","properties":{"name":"Waiver","kind":"boundary"},
+             "geometry":{"type":"Polygon","coordinates":[[[-77.195,42.695],[-77.185,42.695],[-77.185,42.705],[-77.195,42.695]]]}},
+            {"type":"Feature","properties":{"name":"Pond","radius_m":50},
+             "geometry":{"type":"Point","coordinates":[-77.19,42.70]}}]}"#;
+        let fences = parse_geojson(geojson, "file", FenceKind::KeepOut).unwrap();
+        assert_eq!(fences.len(), 2);
+        assert_eq!(fences[0].kind, FenceKind::KeepIn);
+        assert_eq!(fences[1].shape, FenceShape::Circle { center: [42.70, -77.19], radius_m: 50.0 });
+
+        // A two-point ring and a zero radius are dropped rather than imported
+        let degenerate = r#"{"type":"FeatureCollection","features":[
+            {"type":"Feature","properties":{},
+             "geometry":{"type":"Polygon","coordinates":[[[-77.195,42.695],[-77.185,42.695],[-77.195,42.695]]]}},
+            {"type":"Feature","properties":{"radius_m":0},"geometry":{"type":"Point","coordinates":[-77.19,42.70]}}]}"#;
+        assert!(parse_geojson(degenerate, "file", FenceKind::KeepOut).unwrap().is_empty());
+        assert!(FenceShape::Circle { center: [42.7, -77.19], radius_m: f64::NAN }.validate().is_err());
+        assert!(FenceShape::Circle { center: [42.7, -77.19], radius_m: -5.0 }.validate().is_err());
+        assert!(FenceShape::Polygon { points: vec![[42.7, -77.19], [42.71, f64::INFINITY], [42.71, -77.18]] }.validate().is_err());
+        assert!(square().validate().is_ok());
+
+        let kml = "<kml><Document><Placemark><name>Route 20</name><Polygon><outerBoundaryIs><LinearRing>\
+            <coordinates>-77.19,42.70,0 -77.18,42.70,0 -77.18,42.71,0 -77.19,42.70,0</coordinates>\
+            </LinearRing></outerBoundaryIs></Polygon></Placemark></Document></kml>";
+        let fences = parse_kml(kml, "file", FenceKind::KeepOut);
+        assert_eq!(fences.len(), 1);
+        assert_eq!(fences[0].name, "Route 20");
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::{self, ConfigChange, SessionEvent, SessionHistory};
+use crate::dedup::Deduplicator;
+use crate::deputy_interpreter::PacketAssembler;
+use crate::export::archive::{self, ArchiveManifest};
+use crate::export::CsvColumn;
+use crate::geo::FEET_TO_METERS;
+use crate::serial::{self, blocking};
+use crate::session;
+use crate::telemetry::{DataPacket, FixStatus, Reception};
+use crate::tracker;
+
+// Port name given to lines of a transcript that doesn't record which receiver they came from
+const TRANSCRIPT_PORT: &str = "transcript";
+// Delimiters tried when reading a CSV file; the one that splits the header into the most known columns wins
+const CSV_DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportFormat {
+    Csv,
+    Jsonl,
+    // Serial transcript: the auto-log raw-NNN.log files, or a plain terminal capture
+    Raw,
+    // Session archive written by export_archive
+    Archive,
+}
+
+impl ImportFormat {
+    fn detect(path: &Path) -> ImportFormat {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+            Some("csv") => ImportFormat::Csv,
+            Some("zip") => ImportFormat::Archive,
+            Some("jsonl" | "ndjson" | "json") => ImportFormat::Jsonl,
+            _ => ImportFormat::Raw,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowFailure {
+    // 1-based line in the source file
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub format: ImportFormat,
+    pub packets: usize,
+    pub failures: Vec<RowFailure>,
+    // Transcript lines that weren't part of any packet, e.g. other log output
+    pub skipped_lines: usize,
+    // History database entry, when the database is available
+    pub session_id: Option<i64>,
+}
+
+#[derive(Default)]
+struct Parsed {
+    packets: Vec<DataPacket>,
+    // Transcript lines, alerts and config changes, kept with the session
+    history: SessionHistory,
+    failures: Vec<RowFailure>,
+    skipped_lines: usize,
+    // Archives carry their own session name and notes
+    name: Option<String>,
+    notes: Option<String>,
+}
+
+impl Parsed {
+    fn accept(&mut self, line: usize, pkt: DataPacket) {
+        match validate(&pkt) {
+            Ok(()) => self.packets.push(pkt),
+            Err(reason) => self.failures.push(RowFailure { line, reason }),
+        }
+    }
+}
+
+fn validate(pkt: &DataPacket) -> Result<(), String> {
+    if pkt.node_id.is_none() && pkt.callsign.is_none() {
+        return Err("no node ID or callsign".into());
+    }
+    if DateTime::from_timestamp_millis(pkt.timestamp_ms).is_none() {
+        return Err(format!("invalid timestamp {}", pkt.timestamp_ms));
+    }
+    if pkt.latitude.is_some_and(|lat| !(-90.0..=90.0).contains(&lat)) {
+        return Err(format!("latitude {:?} out of range", pkt.latitude));
+    }
+    if pkt.longitude.is_some_and(|lon| !(-180.0..=180.0).contains(&lon)) {
+        return Err(format!("longitude {:?} out of range", pkt.longitude));
+    }
+    if pkt.latitude.is_some() != pkt.longitude.is_some() {
+        return Err("latitude without longitude".into());
+    }
+    Ok(())
+}
+
+// Accepts either decimal separator. A comma inside a value only survives in files that don't use
+// it as the delimiter, where it can only be a decimal comma.
+fn number<T: FromStr>(column: CsvColumn, value: &str) -> Result<T, String> {
+    value
+        .replace(',', ".")
+        .parse()
+        .map_err(|_| format!("{}: {:?} is not a valid number", column.header(), value))
+}
+
+// Decimal degrees, or degrees-minutes-seconds such as 77°40'27.84"W
+fn coordinate(column: CsvColumn, value: &str) -> Result<f64, String> {
+    let Some(hemisphere) = value.chars().last().filter(|c| "NSEW".contains(*c)) else {
+        return number(column, value);
+    };
+    let invalid = || format!("{}: {:?} is not a valid coordinate", column.header(), value);
+    let body = &value[..value.len() - 1];
+    let (degrees, rest) = body.split_once('°').ok_or_else(invalid)?;
+    let (minutes, rest) = rest.split_once('\'').ok_or_else(invalid)?;
+    let seconds = rest.strip_suffix('"').ok_or_else(invalid)?;
+    let parts: Vec<f64> = [degrees, minutes, seconds].iter().map(|p| number(column, p)).collect::<Result<_, _>>()?;
+    let magnitude = parts[0] + parts[1] / 60.0 + parts[2] / 3600.0;
+    Ok(if hemisphere == 'S' || hemisphere == 'W' { -magnitude } else { magnitude })
+}
+
+// Any of the export time formats: RFC 3339, Unix milliseconds, or a spreadsheet time in `offset`
+// (UTC when the file has no UTC Offset column). A custom strftime `pattern` is tried first.
+fn parse_time(value: &str, offset: Option<FixedOffset>, pattern: Option<&str>) -> Result<i64, String> {
+    let offset = offset.unwrap_or(FixedOffset::east_opt(0).unwrap());
+    let local = |naive: NaiveDateTime| offset.from_local_datetime(&naive).single().map(|t| t.timestamp_millis());
+    if let Some(pattern) = pattern {
+        let parsed = DateTime::parse_from_str(value, pattern)
+            .map(|t| t.timestamp_millis())
+            .ok()
+            .or_else(|| NaiveDateTime::parse_from_str(value, pattern).ok().and_then(local));
+        if let Some(ts) = parsed {
+            return Ok(ts);
+        }
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp_millis());
+    }
+    if let Ok(ms) = value.parse::<i64>() {
+        return Ok(ms);
+    }
+    NaiveDateTime::parse_from_str(&value.replace(',', "."), "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .and_then(local)
+        .ok_or_else(|| format!("Time: {value:?} is not a recognised time"))
+}
+
+// "+02:00" as written in the UTC Offset column
+fn parse_offset(value: &str) -> Result<FixedOffset, String> {
+    let invalid = || format!("UTC Offset: {value:?} is not a valid offset");
+    let (sign, rest) = match value.as_bytes().first() {
+        Some(b'+') => (1, &value[1..]),
+        Some(b'-') => (-1, &value[1..]),
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+// "KD2YIE-4", "Node 4" or a bare callsign, as CsvColumn::Node writes them
+fn apply_node_key(pkt: &mut DataPacket, node: &str) {
+    if let Some(id) = node.strip_prefix("Node ") {
+        pkt.node_id = id.trim().parse().ok();
+    } else if let Some((call, id)) = node.rsplit_once('-').filter(|(_, id)| id.parse::<u8>().is_ok()) {
+        pkt.callsign = Some(call.to_string());
+        pkt.node_id = id.parse().ok();
+    } else if node != "unknown" {
+        pkt.callsign = Some(node.to_string());
+    }
+}
+
+// How a CSV export was written, as far as reading it back needs to know
+struct CsvLayout {
+    delimiter: u8,
+    columns: Vec<Option<CsvColumn>>,
+    // Altitudes in metres, from a metric export
+    metric: bool,
+    // strftime pattern of a custom time format
+    time_pattern: Option<String>,
+}
+
+impl CsvLayout {
+    // Picks the delimiter that splits the header line into the most known columns. Metric headers
+    // name the same columns with "(m" in place of "(ft".
+    fn sniff(header: &str, time_pattern: Option<String>) -> CsvLayout {
+        let column_for = |name: &str| CsvColumn::from_header(name).or_else(|| CsvColumn::from_header(&name.replace("(m", "(ft")));
+        let (delimiter, names) = CSV_DELIMITERS
+            .into_iter()
+            .map(|d| (d, header.trim_end().split(d as char).map(|n| n.trim().trim_matches('"')).collect::<Vec<_>>()))
+            .max_by_key(|(d, names)| (names.iter().filter(|n| column_for(n).is_some()).count(), *d == b','))
+            .unwrap();
+        CsvLayout {
+            delimiter,
+            columns: names.iter().map(|n| column_for(n)).collect(),
+            metric: names.iter().any(|n| n.eq_ignore_ascii_case("Altitude (m)")),
+            time_pattern,
+        }
+    }
+}
+
+fn packet_from_row(layout: &CsvLayout, row: &csv::StringRecord) -> Result<DataPacket, String> {
+    let mut pkt = DataPacket::default();
+    let mut time = None;
+    let mut offset = None;
+    let mut node = None;
+    let columns = &layout.columns;
+    for (column, value) in columns.iter().zip(row.iter()) {
+        let (Some(column), value) = (*column, value.trim()) else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+        match column {
+            // Read once the whole row is in, as it may depend on the UTC Offset column
+            CsvColumn::Time => time = Some(value),
+            CsvColumn::UtcOffset => offset = Some(parse_offset(value)?),
+            CsvColumn::Node => node = Some(value),
+            CsvColumn::Latitude => pkt.latitude = Some(coordinate(column, value)? as f32),
+            CsvColumn::Longitude => pkt.longitude = Some(coordinate(column, value)? as f32),
+            CsvColumn::AltitudeFt if layout.metric => {
+                pkt.altitude_ft = Some((number::<f64>(column, value)? / FEET_TO_METERS).round() as i32)
+            }
+            CsvColumn::AltitudeFt => pkt.altitude_ft = Some(number(column, value)?),
+            CsvColumn::Rssi => pkt.receiver_rssi = Some(number::<f64>(column, value)?.round() as i16),
+            CsvColumn::Snr => pkt.receiver_snr = Some(number::<f64>(column, value)?.round() as i8),
+            CsvColumn::Fix => {
+                pkt.fix_status = FixStatus::from_label(value).ok_or_else(|| format!("Fix: unknown status {value:?}"))?
+            }
+            CsvColumn::Satellites => pkt.satellites_count = Some(number(column, value)?),
+            CsvColumn::NodeId => pkt.node_id = Some(number(column, value)?),
+            CsvColumn::Callsign => pkt.callsign = Some(value.to_string()),
+            CsvColumn::DeviceUptimeMs => pkt.device_uptime_ms = Some(number(column, value)?),
+            CsvColumn::Receivers => {
+                pkt.receptions = value
+                    .split(';')
+                    .map(|port| Reception { port: port.to_string(), rssi: None, snr: None, arrival_ms: 0, device_uptime_ms: None })
+                    .collect()
+            }
+            // Header lines themselves contain " | ", so the joined form can't be split back apart
+            CsvColumn::RawLines => pkt.raw_lines = vec![value.to_string()],
+            // The position filter is rerun on import
+            CsvColumn::FilteredLatitude | CsvColumn::FilteredLongitude | CsvColumn::Rejected => {}
+            // Grid references and derived values aren't read back
+            CsvColumn::Position
+            | CsvColumn::FilteredPosition
+            | CsvColumn::AltitudeAgl
+            | CsvColumn::DistanceFromPad
+            | CsvColumn::BearingFromPad
+            | CsvColumn::GroundSpeed
+            | CsvColumn::VerticalSpeed => {}
+        }
+    }
+
+    pkt.timestamp_ms = parse_time(time.ok_or("missing Time")?, offset, layout.time_pattern.as_deref())?;
+    if pkt.node_id.is_none() && pkt.callsign.is_none() {
+        if let Some(node) = node {
+            apply_node_key(&mut pkt, node);
+        }
+    }
+    for reception in &mut pkt.receptions {
+        reception.arrival_ms = pkt.timestamp_ms;
+    }
+    Ok(pkt)
+}
+
+fn parse_csv(text: &str, time_pattern: Option<String>) -> Result<Parsed, String> {
+    let layout = CsvLayout::sniff(text.lines().next().unwrap_or_default(), time_pattern);
+    if !layout.columns.contains(&Some(CsvColumn::Time)) {
+        return Err("Not a Dispatch CSV export: there is no Time column".into());
+    }
+    let mut reader = csv::ReaderBuilder::new().delimiter(layout.delimiter).flexible(true).from_reader(text.as_bytes());
+    reader.headers().map_err(|e| format!("Failed to read CSV header: {e}"))?;
+
+    let mut parsed = Parsed::default();
+    for (i, row) in reader.records().enumerate() {
+        let line = i + 2;
+        match row.map_err(|e| e.to_string()).and_then(|row| packet_from_row(&layout, &row)) {
+            Ok(pkt) => parsed.accept(line, pkt),
+            Err(reason) => parsed.failures.push(RowFailure { line, reason }),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_jsonl(text: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<DataPacket>(line) {
+            Ok(pkt) => parsed.accept(i + 1, pkt),
+            Err(e) => parsed.failures.push(RowFailure { line: i + 1, reason: e.to_string() }),
+        }
+    }
+    parsed
+}
+
+// Auto-log lines are "<RFC 3339 time>\t<port>\t<line>"; anything else is taken as a bare capture.
+fn split_log_line(line: &str) -> Option<(i64, &str, &str)> {
+    let mut parts = line.splitn(3, '\t');
+    let ts = DateTime::parse_from_rfc3339(parts.next()?).ok()?.timestamp_millis();
+    Some((ts, parts.next()?, parts.next()?))
+}
+
+// Replays a transcript through the same assembler the serial reader uses. A bare capture has no
+// wall-clock times, so packets are placed by receiver uptime, ending at `end_ms`.
+fn parse_raw(text: &str, end_ms: i64) -> Parsed {
+    let lines: Vec<(Option<i64>, &str, &str)> = text
+        .lines()
+        .map(|line| match split_log_line(line) {
+            Some((ts, port, line)) => (Some(ts), port, line),
+            None => (None, TRANSCRIPT_PORT, line),
+        })
+        .collect();
+    let ports: BTreeSet<&str> = lines.iter().map(|(_, port, _)| *port).collect();
+    let timed = lines.iter().all(|(ts, _, _)| ts.is_some());
+
+    let mut parsed = Parsed::default();
+    let mut assemblers: BTreeMap<&str, PacketAssembler> = BTreeMap::new();
+    let mut dedup = Deduplicator::default();
+    let mut assembled: Vec<(usize, DataPacket)> = Vec::new();
+    for (i, (ts, port, line)) in lines.iter().enumerate() {
+        let ts = ts.unwrap_or(end_ms);
+        parsed.history.lines.push((ts, port.to_string(), line.to_string()));
+        let mut emit = |mut pkt: DataPacket| {
+            if timed {
+                pkt.timestamp_ms = ts;
+            }
+            pkt.receptions = vec![Reception {
+                port: port.to_string(),
+                rssi: pkt.receiver_rssi,
+                snr: pkt.receiver_snr,
+                arrival_ms: pkt.timestamp_ms,
+                device_uptime_ms: pkt.device_uptime_ms,
+            }];
+            assembled.extend(dedup.offer(pkt, ports.len() > 1).map(|pkt| (i + 1, pkt)));
+        };
+        if assemblers.entry(port).or_default().push_line(line, &mut emit).is_err() {
+            parsed.skipped_lines += 1;
+        }
+        if timed {
+            assembled.extend(dedup.drain_expired(ts).into_iter().map(|pkt| (i + 1, pkt)));
+        }
+    }
+    for assembler in assemblers.values_mut() {
+        if let Some(mut pkt) = assembler.finish() {
+            if let Some(&(Some(ts), _, _)) = lines.last() {
+                pkt.timestamp_ms = ts;
+            }
+            assembled.push((lines.len(), pkt));
+        }
+    }
+    assembled.extend(dedup.drain_expired(i64::MAX).into_iter().map(|pkt| (lines.len(), pkt)));
+
+    if !timed {
+        let last_uptime = assembled.iter().filter_map(|(_, p)| p.device_uptime_ms).max();
+        for (_, pkt) in &mut assembled {
+            pkt.timestamp_ms = match (last_uptime, pkt.device_uptime_ms) {
+                (Some(last), Some(uptime)) => end_ms - (last - uptime) as i64,
+                _ => end_ms,
+            };
+            for reception in &mut pkt.receptions {
+                reception.arrival_ms = pkt.timestamp_ms;
+            }
+        }
+    }
+    for (line, pkt) in assembled {
+        parsed.accept(line, pkt);
+    }
+    parsed
+}
+
+fn parse_archive(path: &Path) -> Result<Parsed, Box<dyn std::error::Error>> {
+    let mut zip = zip::ZipArchive::new(File::open(path)?)?;
+    let mut read = |name: &str| -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let mut file = match zip.by_name(name) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        Ok(Some(text))
+    };
+
+    let manifest: ArchiveManifest = serde_json::from_str(&read(archive::MANIFEST_FILE)?.ok_or("Not a Dispatch session archive")?)?;
+    if manifest.format != archive::ARCHIVE_FORMAT {
+        return Err("Not a Dispatch session archive".into());
+    }
+    if manifest.version > archive::ARCHIVE_VERSION {
+        return Err(format!("Archive was written by a newer Dispatch ({})", manifest.app_version).into());
+    }
+
+    let mut parsed = parse_jsonl(&read(archive::PACKETS_FILE)?.ok_or("Archive has no packets")?);
+    if let Some(raw) = read(archive::RAW_FILE)? {
+        parsed.history.lines = raw
+            .lines()
+            .filter_map(split_log_line)
+            .map(|(ts, port, line)| (ts, port.to_string(), line.to_string()))
+            .collect();
+    }
+    if let Some(events) = read(archive::EVENTS_FILE)? {
+        parsed.history.events = events.lines().filter_map(|l| serde_json::from_str::<SessionEvent>(l).ok()).collect();
+    }
+    if let Some(changes) = read(archive::CONFIG_FILE)? {
+        parsed.history.config_changes = changes.lines().filter_map(|l| serde_json::from_str::<ConfigChange>(l).ok()).collect();
+    }
+    parsed.name = manifest.name;
+    parsed.notes = manifest.notes;
+    Ok(parsed)
+}
+
+// Reads a CSV export, JSONL log, serial transcript or session archive back into the session store (and the history
+// database) and shows it as the current session. `time_pattern` is the strftime pattern of a CSV
+// exported with a custom time format.
+#[tauri::command]
+pub async fn import_session(
+    app: AppHandle,
+    path: String,
+    format: Option<ImportFormat>,
+    time_pattern: Option<String>,
+) -> Result<ImportReport, String> {
+    blocking(move || {
+        if serial::open_port_count() > 0 {
+            return Err("Disconnect before importing a session".into());
+        }
+        let path = Path::new(&path);
+        let format = format.unwrap_or_else(|| ImportFormat::detect(path));
+        let read = || fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()));
+        let mut parsed = match format {
+            ImportFormat::Csv => parse_csv(&read()?, time_pattern)?,
+            ImportFormat::Jsonl => parse_jsonl(&read()?),
+            ImportFormat::Archive => parse_archive(path).map_err(|e| format!("Failed to read archive: {e}"))?,
+            ImportFormat::Raw => {
+                let text = read()?;
+                let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+                let end_ms = modified
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as i64)
+                    .unwrap_or_else(|| Utc::now().timestamp_millis());
+                parse_raw(&text, end_ms)
+            }
+        };
+
+        let mut report = ImportReport {
+            format,
+            packets: parsed.packets.len(),
+            failures: parsed.failures,
+            skipped_lines: parsed.skipped_lines,
+            session_id: None,
+        };
+        if parsed.packets.is_empty() {
+            return Ok(report);
+        }
+
+        parsed.packets.sort_by_key(|p| p.timestamp_ms);
+        tracker::load_history(&mut parsed.packets);
+        let name = parsed
+            .name
+            .unwrap_or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default());
+        report.session_id = match db::import_session(&name, parsed.notes.as_deref(), &parsed.packets, &parsed.history) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                eprintln!("Imported session not saved to history: {e}");
+                None
+            }
+        };
+        session::replace(parsed.packets[0].timestamp_ms, parsed.packets);
+        let _ = app.emit("session-loaded", report.clone());
+        Ok(report)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_are_rebuilt_and_bad_rows_reported() {
+        let text = "Time,Node,Latitude,Longitude,Altitude (ft),RSSI (dBm),SNR (dB),Fix,Satellites in View\n\
+                    2024-06-01T12:00:00.000Z,KD2YIE-4,43.084700,-77.674400,1200,-95,7,FIX,9\n\
+                    2024-06-01T12:00:01.000Z,Node 2,95.0,-77.0,,,,FIX,\n\
+                    2024-06-01T12:00:02.000Z,Node 2,43.0,-77.0,high,,,FIX,\n";
+        let parsed = parse_csv(text, None).unwrap();
+
+        assert_eq!(parsed.packets.len(), 1);
+        let pkt = &parsed.packets[0];
+        assert_eq!(pkt.callsign.as_deref(), Some("KD2YIE"));
+        assert_eq!(pkt.node_id, Some(4));
+        assert_eq!(pkt.altitude_ft, Some(1200));
+        assert_eq!(pkt.fix_status, FixStatus::Fix);
+        assert_eq!(pkt.timestamp_ms, 1_717_243_200_000);
+
+        let lines: Vec<usize> = parsed.failures.iter().map(|f| f.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+        assert!(parsed.failures[1].reason.contains("Altitude"));
+    }
+
+    #[test]
+    fn spreadsheet_dialect_csv_is_read_back() {
+        // The "semicolons, decimal commas" style with metric units and DMS coordinates
+        let text = "Time;UTC Offset;Node;Latitude;Longitude;Altitude (m);SNR (dB)\n\
+                    2024-06-01 14:00:00.000;+02:00;KD2YIE-4;43°05'04,92\"N;77°40'27,84\"W;304,8;7,5\n";
+        let parsed = parse_csv(text, None).unwrap();
+        assert!(parsed.failures.is_empty(), "{:?}", parsed.failures[0].reason);
+        let pkt = &parsed.packets[0];
+        assert_eq!(pkt.timestamp_ms, 1_717_243_200_000);
+        assert!((pkt.latitude.unwrap() - 43.0847).abs() < 1e-4);
+        assert!((pkt.longitude.unwrap() + 77.6744).abs() < 1e-4);
+        assert_eq!(pkt.altitude_ft, Some(1000));
+        assert_eq!(pkt.receiver_snr, Some(8));
+
+        assert_eq!(parse_time("1717243200000", None, None), Ok(1_717_243_200_000));
+        assert_eq!(parse_time("01/06/2024 12:00", None, Some("%d/%m/%Y %H:%M")), Ok(1_717_243_200_000));
+        assert!(parse_time("12:00", None, None).is_err());
+    }
+
+    #[test]
+    fn transcripts_are_reassembled_and_merged_across_receivers() {
+        let packet = |ts: &str, port: &str, rssi: i32| {
+            format!(
+                "{ts}\t{port}\t[00:00:10.000,000] <inf> LoraTransceiver: Node 1: (13 bytes | {rssi} dBm | 7 dB):\n\
+                 {ts}\t{port}\t\tLatitude: 43.0000\n\
+                 {ts}\t{port}\t\tLongitude: -77.0000\n\
+                 {ts}\t{port}\t\tFix status: FIX\n"
+            )
+        };
+        let text = format!(
+            "{}{}2024-06-01T12:00:00.300Z\tCOM3\tuart:~$ \n",
+            packet("2024-06-01T12:00:00.000Z", "COM3", -90),
+            packet("2024-06-01T12:00:00.100Z", "COM4", -80),
+        );
+        let parsed = parse_raw(&text, 0);
+
+        assert_eq!(parsed.packets.len(), 1);
+        assert_eq!(parsed.packets[0].receiver_rssi, Some(-80));
+        assert_eq!(parsed.packets[0].receptions.len(), 2);
+        assert_eq!(parsed.packets[0].timestamp_ms, 1_717_243_200_000);
+        assert_eq!(parsed.skipped_lines, 1);
+        assert_eq!(parsed.history.lines.len(), 9);
+    }
+
+    #[test]
+    fn archive_round_trips_packets_and_history() {
+        let packets = vec![DataPacket {
+            node_id: Some(1),
+            latitude: Some(43.0),
+            longitude: Some(-77.0),
+            fix_status: FixStatus::Fix,
+            timestamp_ms: 1_717_243_200_000,
+            ..Default::default()
+        }];
+        let history = SessionHistory {
+            lines: vec![(1_717_243_200_000, "COM3".into(), "Node 1: (13 bytes | -80 dBm | 7 dB):".into())],
+            events: vec![],
+            config_changes: vec![ConfigChange { timestamp_ms: 1_717_243_100_000, port: Some("COM3".into()), key: "frequency".into(), value: "915".into() }],
+        };
+        let session = archive::ArchiveSession {
+            name: Some("Test flight"),
+            notes: Some("Windy"),
+            started_ms: 1_717_243_000_000,
+            ended_ms: None,
+            ports: vec!["COM3".into()],
+            packets: &packets,
+            history: &history,
+        };
+        let path = std::env::temp_dir().join(format!("dispatch-archive-test-{}.zip", std::process::id()));
+        archive::write_archive(&path, &session).unwrap();
+        let parsed = parse_archive(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(parsed.packets.len(), 1);
+        assert_eq!(parsed.packets[0].node_id, Some(1));
+        assert_eq!(parsed.history.lines, history.lines);
+        assert_eq!(parsed.history.config_changes[0].value, "915");
+        assert_eq!(parsed.name.as_deref(), Some("Test flight"));
+        assert_eq!(parsed.notes.as_deref(), Some("Windy"));
+    }
+}
@@ -1,7 +1,23 @@
 mod telemetry;
 mod serial;
+mod config;
+mod console;
+mod smp;
 mod deputy_interpreter;
 mod export;
+mod geo;
+mod dedup;
+mod geofence;
+mod import;
+mod alerts;
+mod autolog;
+mod db;
+mod link_stats;
+mod overlay;
+mod pipeline;
+mod position_filter;
+mod session;
+mod tracker;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -9,7 +25,96 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![serial::list_serial_port_options, serial::list_serial_ports, serial::open_port, serial::close_port, serial::write_serial, export::export_packets_csv])
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            alerts::load_rules(app.handle());
+            autolog::load_config(app.handle());
+            db::open(app.handle());
+            geofence::load_fences(app.handle());
+            pipeline::spawn_housekeeping(app.handle().clone());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            serial::list_serial_port_options,
+            serial::list_serial_ports,
+            serial::open_port,
+            serial::close_port,
+            serial::write_serial,
+            config::config_probe,
+            config::config_get,
+            config::config_set,
+            config::config_save,
+            config::schema::config_schema,
+            config::identity::identify_device,
+            config::verify::config_verify,
+            config::reboot::reboot_device,
+            config::profiles::list_profiles,
+            config::profiles::save_profile,
+            config::profiles::delete_profile,
+            config::profiles::import_profile,
+            config::profiles::export_profile,
+            config::profiles::apply_profile,
+            config::backup::backup_device,
+            config::backup::list_backups,
+            config::backup::diff_backup,
+            config::backup::restore_backup,
+            config::provision::start_provisioning,
+            config::provision::cancel_provisioning,
+            config::provision::get_provisioning_status,
+            smp::smp_echo,
+            smp::smp_image_list,
+            smp::smp_image_upload,
+            smp::smp_image_test,
+            smp::smp_image_confirm,
+            smp::smp_reset,
+            console::console_open,
+            console::console_close,
+            console::console_send,
+            console::console_complete,
+            export::export_packets_csv,
+            export::export_packets_kml,
+            export::export_packets_gpx,
+            export::export_packets_geojson,
+            export::export_session,
+            export::export_report,
+            export::export_archive,
+            session::get_session_info,
+            session::get_session_packets,
+            session::clear_session,
+            db::list_sessions,
+            db::query_packets,
+            db::load_session,
+            db::rename_session,
+            db::archive_session,
+            db::delete_session,
+            import::import_session,
+            autolog::get_autolog_config,
+            autolog::set_autolog_config,
+            autolog::get_autolog_status,
+            tracker::get_position_filter_config,
+            tracker::set_position_filter_config,
+            tracker::reset_position_filters,
+            tracker::import_overlays,
+            tracker::list_overlays,
+            tracker::remove_overlay,
+            tracker::clear_overlays,
+            link_stats::get_link_stats,
+            link_stats::reset_link_stats,
+            alerts::get_alert_rules,
+            alerts::set_alert_rules,
+            alerts::set_alert_rule_node_enabled,
+            alerts::get_active_alerts,
+            alerts::reset_alerts,
+            alerts::get_alert_log,
+            alerts::clear_alert_log,
+            geofence::list_geofences,
+            geofence::add_geofence,
+            geofence::remove_geofence,
+            geofence::import_geofences,
+            geofence::get_geofence_status,
+            dedup::get_dedup_config,
+            dedup::set_dedup_config,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::telemetry::{DataPacket, Reception};
+
+// Sliding windows reported alongside the whole-session figures
+const WINDOWS_S: [u64; 2] = [60, 300];
+const MAX_ARRIVALS: usize = 100_000;
+const RSSI_BIN_DB: f64 = 5.0;
+const SNR_BIN_DB: f64 = 2.0;
+
+#[derive(Debug, Clone)]
+struct Arrival {
+    time_ms: i64,
+    uptime_ms: Option<u64>,
+    // Port whose clock `uptime_ms` comes from
+    receiver: Option<String>,
+    rssi: Option<i16>,
+    snr: Option<i8>,
+    receptions: Vec<Reception>,
+}
+
+#[derive(Debug, Default)]
+struct NodeLink {
+    arrivals: VecDeque<Arrival>,
+    total_received: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub histogram: Vec<HistogramBin>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiverStats {
+    pub port: String,
+    pub received: u64,
+    // Packets no other receiver heard
+    pub unique: u64,
+    pub rssi: Option<Distribution>,
+    pub snr: Option<Distribution>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStats {
+    // None means the whole session
+    pub window_s: Option<u64>,
+    pub received: u64,
+    pub missed: u64,
+    pub success_rate: Option<f64>,
+    pub rssi: Option<Distribution>,
+    pub snr: Option<Distribution>,
+    pub receivers: Vec<ReceiverStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkStats {
+    pub node: String,
+    pub interval_ms: Option<f64>,
+    pub last_packet_ms: i64,
+    pub since_last_ms: i64,
+    pub total_received: u64,
+    pub windows: Vec<WindowStats>,
+}
+
+static LINKS: OnceLock<Mutex<BTreeMap<String, NodeLink>>> = OnceLock::new();
+
+fn get_links() -> &'static Mutex<BTreeMap<String, NodeLink>> {
+    LINKS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+pub fn record(pkt: &DataPacket) {
+    let Ok(mut links) = get_links().lock() else {
+        return;
+    };
+    let link = links.entry(pkt.node_key()).or_default();
+    link.total_received += 1;
+    let first = pkt.receptions.first();
+    link.arrivals.push_back(Arrival {
+        time_ms: pkt.timestamp_ms,
+        uptime_ms: first.map_or(pkt.device_uptime_ms, |r| r.device_uptime_ms),
+        receiver: first.map(|r| r.port.clone()),
+        rssi: pkt.receiver_rssi,
+        snr: pkt.receiver_snr,
+        receptions: pkt.receptions.clone(),
+    });
+    if link.arrivals.len() > MAX_ARRIVALS {
+        link.arrivals.pop_front();
+    }
+}
+
+// Every node's figures as of `now_ms`; the windows are sorted from scratch, so this is called
+// on a timer rather than per packet.
+pub fn snapshot_all(now_ms: i64) -> Vec<LinkStats> {
+    let Ok(links) = get_links().lock() else {
+        return Vec::new();
+    };
+    links.iter().map(|(node, link)| link_stats(node, link, now_ms)).collect()
+}
+
+// Receiver uptime is immune to USB and scheduler jitter, so prefer it whenever both ends were
+// timed by the same receiver.
+fn delta_ms(a: &Arrival, b: &Arrival) -> f64 {
+    match (a.uptime_ms, b.uptime_ms) {
+        (Some(ua), Some(ub)) if ub > ua && a.receiver == b.receiver => (ub - ua) as f64,
+        _ => (b.time_ms - a.time_ms) as f64,
+    }
+}
+
+// The median inter-arrival delta is a multiple of the true interval as long as fewer than half the
+// packets are lost; dividing every delta by its nearest multiple then refines it.
+fn estimate_interval(deltas: &[f64]) -> Option<f64> {
+    let mut sorted: Vec<f64> = deltas.iter().copied().filter(|d| *d > 0.0).collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    let (sum, slots) = sorted.iter().fold((0.0, 0.0), |(sum, slots), d| {
+        let k = (d / median).round().max(1.0);
+        (sum + d, slots + k)
+    });
+    Some(sum / slots)
+}
+
+fn missed_between(delta: f64, interval: f64) -> u64 {
+    ((delta / interval).round() - 1.0).max(0.0) as u64
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let idx = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[idx]
+}
+
+fn distribution(mut values: Vec<f64>, bin_width: f64) -> Option<Distribution> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let mut bins: BTreeMap<i64, u32> = BTreeMap::new();
+    for v in &values {
+        *bins.entry((v / bin_width).floor() as i64).or_default() += 1;
+    }
+
+    Some(Distribution {
+        min: values[0],
+        mean: values.iter().sum::<f64>() / values.len() as f64,
+        max: values[values.len() - 1],
+        p10: percentile(&values, 0.1),
+        p50: percentile(&values, 0.5),
+        p90: percentile(&values, 0.9),
+        histogram: bins
+            .into_iter()
+            .map(|(bin, count)| HistogramBin {
+                lower: bin as f64 * bin_width,
+                upper: (bin + 1) as f64 * bin_width,
+                count,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Default)]
+struct ReceiverTally {
+    received: u64,
+    unique: u64,
+    rssi: Vec<f64>,
+    snr: Vec<f64>,
+}
+
+fn receiver_stats(arrivals: &[&Arrival]) -> Vec<ReceiverStats> {
+    let mut by_port: BTreeMap<&str, ReceiverTally> = BTreeMap::new();
+    for arrival in arrivals {
+        for reception in &arrival.receptions {
+            let tally = by_port.entry(reception.port.as_str()).or_default();
+            tally.received += 1;
+            if arrival.receptions.len() == 1 {
+                tally.unique += 1;
+            }
+            tally.rssi.extend(reception.rssi.map(f64::from));
+            tally.snr.extend(reception.snr.map(f64::from));
+        }
+    }
+    by_port
+        .into_iter()
+        .map(|(port, tally)| ReceiverStats {
+            port: port.to_string(),
+            received: tally.received,
+            unique: tally.unique,
+            rssi: distribution(tally.rssi, RSSI_BIN_DB),
+            snr: distribution(tally.snr, SNR_BIN_DB),
+        })
+        .collect()
+}
+
+fn window_stats(arrivals: &[&Arrival], window_s: Option<u64>, interval: Option<f64>, trailing_gap_ms: f64) -> WindowStats {
+    let received = arrivals.len() as u64;
+    let mut missed = 0;
+    if let Some(interval) = interval {
+        missed += arrivals.windows(2).map(|w| missed_between(delta_ms(w[0], w[1]), interval)).sum::<u64>();
+        // Packets that were due since the last arrival, allowing half an interval of jitter
+        missed += ((trailing_gap_ms / interval) - 0.5).floor().max(0.0) as u64;
+    }
+    let expected = received + missed;
+
+    WindowStats {
+        window_s,
+        received,
+        missed,
+        success_rate: (expected > 0).then(|| received as f64 / expected as f64),
+        rssi: distribution(arrivals.iter().filter_map(|a| a.rssi.map(f64::from)).collect(), RSSI_BIN_DB),
+        snr: distribution(arrivals.iter().filter_map(|a| a.snr.map(f64::from)).collect(), SNR_BIN_DB),
+        receivers: receiver_stats(arrivals),
+    }
+}
+
+fn link_stats(node: &str, link: &NodeLink, now_ms: i64) -> LinkStats {
+    let all: Vec<&Arrival> = link.arrivals.iter().collect();
+    let deltas: Vec<f64> = all.windows(2).map(|w| delta_ms(w[0], w[1])).collect();
+    let interval = estimate_interval(&deltas);
+    let last_packet_ms = all.last().map(|a| a.time_ms).unwrap_or(now_ms);
+    let since_last_ms = (now_ms - last_packet_ms).max(0);
+
+    let mut windows: Vec<WindowStats> = WINDOWS_S
+        .iter()
+        .map(|&secs| {
+            let cutoff = now_ms - (secs as i64) * 1000;
+            let recent: Vec<&Arrival> = all.iter().copied().filter(|a| a.time_ms >= cutoff).collect();
+            // Only count the silent stretch that falls inside the window
+            let gap = since_last_ms.min(secs as i64 * 1000) as f64;
+            window_stats(&recent, Some(secs), interval, gap)
+        })
+        .collect();
+    windows.push(window_stats(&all, None, interval, since_last_ms as f64));
+
+    LinkStats {
+        node: node.to_string(),
+        interval_ms: interval,
+        last_packet_ms,
+        since_last_ms,
+        total_received: link.total_received,
+        windows,
+    }
+}
+
+#[tauri::command]
+pub fn get_link_stats() -> Result<Vec<LinkStats>, String> {
+    let links = get_links().lock().map_err(|e| format!("link stats lock error: {}", e))?;
+    let now_ms = Utc::now().timestamp_millis();
+    Ok(links.iter().map(|(node, link)| link_stats(node, link, now_ms)).collect())
+}
+
+#[tauri::command]
+pub fn reset_link_stats() -> Result<(), String> {
+    let mut links = get_links().lock().map_err(|e| format!("link stats lock error: {}", e))?;
+    links.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrival(time_ms: i64, uptime_ms: Option<u64>, rssi: i16) -> Arrival {
+        Arrival {
+            time_ms,
+            uptime_ms,
+            receiver: Some("COM3".into()),
+            rssi: Some(rssi),
+            snr: Some(5),
+            receptions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn estimates_interval_and_counts_gaps() {
+        // 2 s cadence with the 3rd and 6th packets lost, plus host-side jitter
+        let mut link = NodeLink::default();
+        for (i, t) in [0, 2010, 6000, 7990, 12000, 14020].iter().enumerate() {
+            link.arrivals.push_back(arrival(*t, None, -80 - i as i16));
+            link.total_received += 1;
+        }
+
+        let stats = link_stats("Node 1", &link, 14_500);
+        assert!((stats.interval_ms.unwrap() - 2000.0).abs() < 10.0);
+
+        let session = stats.windows.last().unwrap();
+        assert_eq!(session.received, 6);
+        assert_eq!(session.missed, 2);
+        let rssi = session.rssi.as_ref().unwrap();
+        assert_eq!(rssi.min, -85.0);
+        assert_eq!(rssi.max, -80.0);
+    }
+
+    #[test]
+    fn prefers_receiver_uptime_and_counts_trailing_silence() {
+        let mut link = NodeLink::default();
+        // Host timestamps bunch up (USB buffering) but the receiver saw a steady 1 s cadence
+        for (i, t) in [0, 1500, 1600, 3000].iter().enumerate() {
+            link.arrivals.push_back(arrival(*t, Some(10_000 + i as u64 * 1000), -90));
+        }
+
+        let stats = link_stats("Node 2", &link, 6_000);
+        assert!((stats.interval_ms.unwrap() - 1000.0).abs() < 1.0);
+        assert_eq!(stats.since_last_ms, 3000);
+        // Due at +1 s and +2 s; the one at +3 s is still within jitter
+        assert_eq!(stats.windows.last().unwrap().missed, 2);
+    }
+}
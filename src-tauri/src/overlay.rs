@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Reference geometry drawn under the live trackers; it takes no part in tracking or alerts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlayKind {
+    Track,
+    LandingZone,
+}
+
+// Points are [lat, lon] like geofences, not GeoJSON's [lon, lat].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OverlayShape {
+    Line { points: Vec<[f64; 2]> },
+    Polygon { points: Vec<[f64; 2]> },
+    Point { position: [f64; 2], radius_m: Option<f64> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Overlay {
+    pub id: String,
+    pub name: String,
+    pub kind: OverlayKind,
+    pub shape: OverlayShape,
+}
+
+fn parse_kind(value: Option<&Value>) -> Option<OverlayKind> {
+    match value?.as_str()?.to_ascii_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "track" | "path" => Some(OverlayKind::Track),
+        "landingzone" | "landing" | "zone" => Some(OverlayKind::LandingZone),
+        _ => None,
+    }
+}
+
+fn position(value: &Value) -> Option<[f64; 2]> {
+    let coords = value.as_array()?;
+    Some([coords.get(1)?.as_f64()?, coords.first()?.as_f64()?])
+}
+
+fn positions(value: &Value) -> Option<Vec<[f64; 2]>> {
+    value.as_array()?.iter().map(position).collect()
+}
+
+struct FeatureContext<'a> {
+    name: &'a str,
+    kind: Option<OverlayKind>,
+    radius_m: Option<f64>,
+}
+
+fn push(out: &mut Vec<Overlay>, ctx: &FeatureContext, default_kind: OverlayKind, shape: OverlayShape) {
+    out.push(Overlay {
+        id: String::new(),
+        name: ctx.name.to_string(),
+        kind: ctx.kind.unwrap_or(default_kind),
+        shape,
+    });
+}
+
+fn collect(value: &Value, ctx: &FeatureContext, out: &mut Vec<Overlay>) {
+    let coordinates = value.get("coordinates");
+    match value.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => {
+            for feature in value.get("features").and_then(Value::as_array).into_iter().flatten() {
+                collect(feature, ctx, out);
+            }
+        }
+        Some("Feature") => {
+            let props = value.get("properties");
+            let prop = |key: &str| props.and_then(|p| p.get(key));
+            // Dispatch's own GeoJSON export carries one Point per packet; only its tracks are useful here
+            if prop("timestamp_ms").is_some() {
+                return;
+            }
+            let name = ["name", "node", "title"].iter().find_map(|key| prop(key).and_then(Value::as_str)).unwrap_or(ctx.name);
+            let feature = FeatureContext {
+                name,
+                kind: parse_kind(prop("kind")).or(ctx.kind),
+                radius_m: prop("radius_m").or_else(|| prop("radius")).and_then(Value::as_f64),
+            };
+            if let Some(geometry) = value.get("geometry") {
+                collect(geometry, &feature, out);
+            }
+        }
+        Some("LineString") => {
+            if let Some(points) = coordinates.and_then(positions).filter(|p| p.len() >= 2) {
+                push(out, ctx, OverlayKind::Track, OverlayShape::Line { points });
+            }
+        }
+        Some("MultiLineString") => {
+            for line in coordinates.and_then(Value::as_array).into_iter().flatten() {
+                if let Some(points) = positions(line).filter(|p| p.len() >= 2) {
+                    push(out, ctx, OverlayKind::Track, OverlayShape::Line { points });
+                }
+            }
+        }
+        Some("Polygon") => {
+            if let Some(points) = coordinates.and_then(|c| c.get(0)).and_then(positions).filter(|p| p.len() >= 3) {
+                push(out, ctx, OverlayKind::LandingZone, OverlayShape::Polygon { points });
+            }
+        }
+        Some("MultiPolygon") => {
+            for polygon in coordinates.and_then(Value::as_array).into_iter().flatten() {
+                if let Some(points) = polygon.get(0).and_then(positions).filter(|p| p.len() >= 3) {
+                    push(out, ctx, OverlayKind::LandingZone, OverlayShape::Polygon { points });
+                }
+            }
+        }
+        Some("Point") => {
+            if let Some(position) = coordinates.and_then(position) {
+                push(out, ctx, OverlayKind::LandingZone, OverlayShape::Point { position, radius_m: ctx.radius_m });
+            }
+        }
+        Some("GeometryCollection") => {
+            for geometry in value.get("geometries").and_then(Value::as_array).into_iter().flatten() {
+                collect(geometry, ctx, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Lines become tracks and areas or points become landing zones unless a feature's `kind` property
+// says otherwise.
+pub fn parse_geojson(text: &str, default_name: &str) -> Result<Vec<Overlay>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| format!("Invalid GeoJSON: {e}"))?;
+    let mut overlays = Vec::new();
+    collect(&value, &FeatureContext { name: default_name, kind: None, radius_m: None }, &mut overlays);
+    Ok(overlays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tracks_and_landing_zones() {
+        let text = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "properties": {"node": "Node 1"},
+                 "geometry": {"type": "LineString", "coordinates": [[-77.5, 43.0, 100], [-77.4, 43.1, 900]]}},
+                {"type": "Feature", "properties": {"name": "Field B", "radius_m": 150},
+                 "geometry": {"type": "Point", "coordinates": [-77.3, 43.2]}},
+                {"type": "Feature", "properties": {"kind": "landing_zone"},
+                 "geometry": {"type": "LineString", "coordinates": [[-77.5, 43.0], [-77.4, 43.1]]}},
+                {"type": "Feature", "properties": {"node": "Node 1", "timestamp_ms": 1000},
+                 "geometry": {"type": "Point", "coordinates": [-77.5, 43.0]}}
+            ]
+        }"#;
+        let overlays = parse_geojson(text, "prior-flight").unwrap();
+        assert_eq!(overlays.len(), 3);
+
+        assert_eq!(overlays[0].name, "Node 1");
+        assert_eq!(overlays[0].kind, OverlayKind::Track);
+        assert_eq!(overlays[0].shape, OverlayShape::Line { points: vec![[43.0, -77.5], [43.1, -77.4]] });
+
+        assert_eq!(overlays[1].kind, OverlayKind::LandingZone);
+        assert_eq!(overlays[1].shape, OverlayShape::Point { position: [43.2, -77.3], radius_m: Some(150.0) });
+
+        assert_eq!(overlays[2].name, "prior-flight");
+        assert_eq!(overlays[2].kind, OverlayKind::LandingZone);
+    }
+}
@@ -0,0 +1,67 @@
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter};
+
+use crate::alerts;
+use crate::autolog;
+use crate::db;
+use crate::dedup;
+use crate::geofence;
+use crate::link_stats;
+use crate::serial;
+use crate::session;
+use crate::telemetry::DataPacket;
+use crate::tracker;
+
+// Short enough that held duplicates are released promptly after their merge window
+const HOUSEKEEPING_PERIOD: Duration = Duration::from_millis(100);
+const ALERT_TICK_MS: i64 = 1000;
+// Also keeps "time since last packet" counting up between packets
+const LINK_STATS_TICK_MS: i64 = 1000;
+
+// Entry point for packets fresh off a serial port; copies heard by several receivers are merged first.
+pub fn submit_packet(app: &AppHandle, pkt: DataPacket) {
+    if let Some(pkt) = dedup::offer(pkt, serial::open_port_count() > 1) {
+        process_packet(app, pkt);
+    }
+}
+
+// Every assembled packet passes through here on its way to the frontend.
+pub fn process_packet(app: &AppHandle, mut pkt: DataPacket) {
+    tracker::ingest(&mut pkt);
+    link_stats::record(&pkt);
+    alerts::process_packet(app, &pkt);
+    geofence::process_packet(app, &pkt);
+    session::record(&pkt);
+    autolog::record_packet(&pkt);
+    db::record_packet(&pkt);
+
+    let _ = app.emit("serial-packet", pkt);
+}
+
+// Periodic work that has to happen even when no packets arrive, e.g. stale-node alerts.
+pub fn spawn_housekeeping(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_alert_tick = 0;
+        let mut last_link_stats_tick = 0;
+        loop {
+            thread::sleep(HOUSEKEEPING_PERIOD);
+            let now = Utc::now().timestamp_millis();
+            for pkt in dedup::drain_expired(now) {
+                process_packet(&app, pkt);
+            }
+            if now - last_alert_tick >= ALERT_TICK_MS {
+                last_alert_tick = now;
+                alerts::tick(&app, now);
+            }
+            if now - last_link_stats_tick >= LINK_STATS_TICK_MS {
+                last_link_stats_tick = now;
+                for stats in link_stats::snapshot_all(now) {
+                    let _ = app.emit("link-stats", stats);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+
+use crate::geo::{self, FEET_TO_METERS};
+use crate::telemetry::{DataPacket, FilteredPosition, FixStatus, RejectReason};
+
+// Keeps the velocity term sane when two packets land in the same millisecond
+const MIN_DT_S: f64 = 0.1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterConfig {
+    pub enabled: bool,
+    pub reject_estimated: bool,
+    pub min_satellites: u8,
+    // Fastest plausible ground speed; anything further than this from the prediction is a glitch
+    pub max_speed_mps: f64,
+    pub max_vertical_speed_mps: f64,
+    // Allowance for ordinary GPS noise on top of the speed gate
+    pub gate_slack_m: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    // After this many gated samples in a row, assume the tracker really moved and start over
+    pub reacquire_after: u32,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        FilterConfig {
+            enabled: true,
+            reject_estimated: true,
+            min_satellites: 4,
+            max_speed_mps: 343.0,
+            max_vertical_speed_mps: 343.0,
+            gate_slack_m: 50.0,
+            alpha: 0.6,
+            beta: 0.2,
+            reacquire_after: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AxisState {
+    pos: f64,
+    vel: f64,
+}
+
+impl AxisState {
+    fn predict(&self, dt: f64) -> f64 {
+        self.pos + self.vel * dt
+    }
+
+    fn correct(&mut self, predicted: f64, measured: f64, dt: f64, alpha: f64, beta: f64) {
+        let residual = measured - predicted;
+        self.pos = predicted + alpha * residual;
+        self.vel += beta * residual / dt;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FilterState {
+    origin_lat: f64,
+    origin_lon: f64,
+    east: AxisState,
+    north: AxisState,
+    // Altitude is tracked in metres so it shares units with the speed gates
+    up: Option<AxisState>,
+    last_ms: i64,
+}
+
+impl FilterState {
+    fn new(lat: f64, lon: f64, alt_m: Option<f64>, ts: i64) -> Self {
+        FilterState {
+            origin_lat: lat,
+            origin_lon: lon,
+            east: AxisState { pos: 0.0, vel: 0.0 },
+            north: AxisState { pos: 0.0, vel: 0.0 },
+            up: alt_m.map(|pos| AxisState { pos, vel: 0.0 }),
+            last_ms: ts,
+        }
+    }
+
+    // Projected around the first fix; plenty for a launch field
+    fn to_local(&self, lat: f64, lon: f64) -> (f64, f64) {
+        geo::to_local((self.origin_lat, self.origin_lon), (lat, lon))
+    }
+
+    fn to_geodetic(&self, east: f64, north: f64) -> (f64, f64) {
+        geo::from_local((self.origin_lat, self.origin_lon), east, north)
+    }
+
+    fn estimate(&self, rejected: Option<RejectReason>) -> FilteredPosition {
+        let (latitude, longitude) = self.to_geodetic(self.east.pos, self.north.pos);
+        FilteredPosition {
+            latitude,
+            longitude,
+            altitude_ft: self.up.as_ref().map(|up| up.pos / FEET_TO_METERS),
+            rejected,
+        }
+    }
+}
+
+// The last of a run of gated fixes, which later gated fixes must agree with to count towards reacquiring
+#[derive(Debug, Clone)]
+struct Candidate {
+    lat: f64,
+    lon: f64,
+    alt_m: Option<f64>,
+    ts: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PositionFilter {
+    state: Option<FilterState>,
+    candidate: Option<Candidate>,
+    consecutive_gated: u32,
+}
+
+impl PositionFilter {
+    pub fn reset(&mut self) {
+        self.state = None;
+        self.candidate = None;
+        self.consecutive_gated = 0;
+    }
+
+    // Returns None when the packet carries no position at all.
+    pub fn update(&mut self, config: &FilterConfig, pkt: &DataPacket) -> Option<FilteredPosition> {
+        let lat = pkt.latitude? as f64;
+        let lon = pkt.longitude? as f64;
+        let alt_m = pkt.altitude_ft.map(|ft| ft as f64 * FEET_TO_METERS);
+
+        if !config.enabled {
+            return Some(FilteredPosition {
+                latitude: lat,
+                longitude: lon,
+                altitude_ft: pkt.altitude_ft.map(f64::from),
+                rejected: None,
+            });
+        }
+
+        if let Some(reason) = quality_rejection(config, pkt) {
+            return Some(self.rejected(lat, lon, pkt.altitude_ft, reason));
+        }
+
+        let state = match self.state.as_mut() {
+            Some(state) => state,
+            None => {
+                let state = self.state.insert(FilterState::new(lat, lon, alt_m, pkt.timestamp_ms));
+                return Some(state.estimate(None));
+            }
+        };
+
+        let dt = ((pkt.timestamp_ms - state.last_ms) as f64 / 1000.0).max(MIN_DT_S);
+        let (east, north) = state.to_local(lat, lon);
+        let pred_east = state.east.predict(dt);
+        let pred_north = state.north.predict(dt);
+
+        let horizontal_err = (east - pred_east).hypot(north - pred_north);
+        let mut gated = None;
+        if horizontal_err > config.max_speed_mps * dt + config.gate_slack_m {
+            gated = Some(RejectReason::ImpossibleJump);
+        } else if let (Some(up), Some(alt)) = (state.up.as_ref(), alt_m) {
+            if (alt - up.predict(dt)).abs() > config.max_vertical_speed_mps * dt + config.gate_slack_m {
+                gated = Some(RejectReason::ImpossibleClimb);
+            }
+        }
+
+        if let Some(reason) = gated {
+            // Only a run of outliers that agree with each other means the tracker really moved;
+            // scattered garbage keeps starting the run over
+            let consistent = self.candidate.as_ref().is_some_and(|c| {
+                let dt = ((pkt.timestamp_ms - c.ts) as f64 / 1000.0).max(MIN_DT_S);
+                let (c_east, c_north) = state.to_local(c.lat, c.lon);
+                let horizontal_ok = (east - c_east).hypot(north - c_north) <= config.max_speed_mps * dt + config.gate_slack_m;
+                let vertical_ok = match (c.alt_m, alt_m) {
+                    (Some(c_alt), Some(alt)) => (alt - c_alt).abs() <= config.max_vertical_speed_mps * dt + config.gate_slack_m,
+                    _ => true,
+                };
+                horizontal_ok && vertical_ok
+            });
+            self.consecutive_gated = if consistent { self.consecutive_gated + 1 } else { 1 };
+            self.candidate = Some(Candidate { lat, lon, alt_m, ts: pkt.timestamp_ms });
+            if self.consecutive_gated < config.reacquire_after {
+                return Some(self.rejected(lat, lon, pkt.altitude_ft, reason));
+            }
+            // The "outliers" agree with each other, so the filter is what's wrong
+            self.consecutive_gated = 0;
+            self.candidate = None;
+            let state = self.state.insert(FilterState::new(lat, lon, alt_m, pkt.timestamp_ms));
+            return Some(state.estimate(None));
+        }
+
+        self.consecutive_gated = 0;
+        self.candidate = None;
+        state.east.correct(pred_east, east, dt, config.alpha, config.beta);
+        state.north.correct(pred_north, north, dt, config.alpha, config.beta);
+        match (state.up.as_mut(), alt_m) {
+            (Some(up), Some(alt)) => {
+                let predicted = up.predict(dt);
+                up.correct(predicted, alt, dt, config.alpha, config.beta);
+            }
+            (None, Some(alt)) => state.up = Some(AxisState { pos: alt, vel: 0.0 }),
+            _ => {}
+        }
+        state.last_ms = pkt.timestamp_ms;
+        Some(state.estimate(None))
+    }
+
+    // Rejected samples report the current estimate, or the raw fix if there is nothing to estimate from yet.
+    fn rejected(&self, lat: f64, lon: f64, altitude_ft: Option<i32>, reason: RejectReason) -> FilteredPosition {
+        match self.state.as_ref() {
+            Some(state) => state.estimate(Some(reason)),
+            None => FilteredPosition {
+                latitude: lat,
+                longitude: lon,
+                altitude_ft: altitude_ft.map(f64::from),
+                rejected: Some(reason),
+            },
+        }
+    }
+}
+
+fn quality_rejection(config: &FilterConfig, pkt: &DataPacket) -> Option<RejectReason> {
+    match pkt.fix_status {
+        FixStatus::NoFix => return Some(RejectReason::NoFix),
+        FixStatus::Est if config.reject_estimated => return Some(RejectReason::Estimated),
+        _ => {}
+    }
+    match pkt.satellites_count {
+        Some(sats) if sats < config.min_satellites => Some(RejectReason::LowSatellites),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::fixtures::packet;
+
+    #[test]
+    fn gates_garbled_digit_without_moving_estimate() {
+        let config = FilterConfig::default();
+        let mut filter = PositionFilter::default();
+        filter.update(&config, &packet(1, 0, 42.7043, -77.1872, 600));
+        filter.update(&config, &packet(1, 1000, 42.7044, -77.1873, 600));
+
+        // 42.70 -> 42.79 is a ~10 km jump in one second
+        let out = filter.update(&config, &packet(1, 2000, 42.7944, -77.1873, 600)).unwrap();
+        assert_eq!(out.rejected, Some(RejectReason::ImpossibleJump));
+        assert!((out.latitude - 42.7043).abs() < 0.001);
+
+        let out = filter.update(&config, &packet(1, 3000, 42.7045, -77.1874, 600)).unwrap();
+        assert_eq!(out.rejected, None);
+    }
+
+    #[test]
+    fn rejects_low_quality_fixes() {
+        let config = FilterConfig::default();
+        let mut filter = PositionFilter::default();
+
+        let mut est = packet(1, 0, 42.7, -77.1, 600);
+        est.fix_status = FixStatus::Est;
+        assert_eq!(filter.update(&config, &est).unwrap().rejected, Some(RejectReason::Estimated));
+
+        let mut sparse = packet(1, 0, 42.7, -77.1, 600);
+        sparse.satellites_count = Some(2);
+        assert_eq!(filter.update(&config, &sparse).unwrap().rejected, Some(RejectReason::LowSatellites));
+    }
+
+    #[test]
+    fn reacquires_after_persistent_jump() {
+        let config = FilterConfig::default();
+        let mut filter = PositionFilter::default();
+        filter.update(&config, &packet(1, 0, 42.7, -77.1, 600));
+
+        let mut last = None;
+        for i in 1..=config.reacquire_after as i64 {
+            last = filter.update(&config, &packet(1, i * 1000, 43.7, -77.1, 600));
+        }
+        let out = last.unwrap();
+        assert_eq!(out.rejected, None);
+        assert!((out.latitude - 43.7).abs() < 1e-4);
+    }
+
+    #[test]
+    fn scattered_outliers_never_reacquire() {
+        let config = FilterConfig::default();
+        let mut filter = PositionFilter::default();
+        filter.update(&config, &packet(1, 0, 42.7, -77.1, 600));
+
+        // Each garbled fix is far from the filter and from the one before it
+        for i in 1..=config.reacquire_after as i64 * 2 {
+            let lat = if i % 2 == 0 { 43.7 } else { 41.7 };
+            let out = filter.update(&config, &packet(1, i * 1000, lat, -77.1, 600)).unwrap();
+            assert_eq!(out.rejected, Some(RejectReason::ImpossibleJump));
+            assert!((out.latitude - 42.7).abs() < 1e-4);
+        }
+    }
+}
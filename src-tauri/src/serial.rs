@@ -6,8 +6,14 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use tauri::Emitter;
 
-use crate::deputy_interpreter::{parse_zephyr_line, RE_HEADER_NODE, RE_HEADER_LICENSED_NOFIX};
-use crate::telemetry::{DataPacket, FixStatus};
+use crate::autolog;
+use crate::config::{self, schema};
+use crate::console;
+use crate::db;
+use crate::pipeline;
+use crate::smp;
+use crate::deputy_interpreter::{PacketAssembler, ParserDialect};
+use crate::telemetry::{DataPacket, Reception};
 use serde_json::json;
 use serde::Serialize;
 
@@ -16,10 +22,27 @@ use winreg::enums::HKEY_LOCAL_MACHINE;
 #[cfg(windows)]
 use winreg::RegKey;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortMode {
+    // A ground station receiver: telemetry, session recording, auto-log and alerts
+    Receiver,
+    // A device only talked to through its config shell, e.g. while provisioning. Nothing it prints
+    // is recorded or parsed as telemetry.
+    ConfigOnly,
+}
+
+struct PortConnection {
+    stop_flag: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+    writer: Box<dyn serialport::SerialPort>,
+    baud_rate: u32,
+    mode: PortMode,
+}
+
+#[derive(Default)]
 struct SerialState {
-    stop_flag: Option<Arc<AtomicBool>>,
-    handle: Option<thread::JoinHandle<()>>,
-    writer: Option<Box<dyn serialport::SerialPort>>,
+    // Several receivers can be open at once, keyed by the name the frontend opened them with
+    ports: BTreeMap<String, PortConnection>,
 }
 
 #[derive(Serialize, Clone)]
@@ -32,13 +55,15 @@ pub struct SerialPortOption {
 static GLOBAL_STATE: OnceLock<Mutex<SerialState>> = OnceLock::new();
 
 fn get_state() -> &'static Mutex<SerialState> {
-    GLOBAL_STATE.get_or_init(|| {
-        Mutex::new(SerialState {
-            stop_flag: None,
-            handle: None,
-            writer: None,
-        })
-    })
+    GLOBAL_STATE.get_or_init(|| Mutex::new(SerialState::default()))
+}
+
+// Open receivers; config-only ports don't count
+pub fn open_port_count() -> usize {
+    get_state()
+        .lock()
+        .map(|state| state.ports.values().filter(|conn| conn.mode == PortMode::Receiver).count())
+        .unwrap_or(0)
 }
 
 #[cfg(windows)]
@@ -182,9 +207,14 @@ pub fn list_serial_ports() -> Result<Vec<String>, String> {
 
 #[tauri::command]
 pub fn open_port(app_handle: tauri::AppHandle, port_name: String, baud_rate: u32) -> Result<String, String> {
+    open(app_handle, port_name, baud_rate, PortMode::Receiver)
+}
+
+// Opens a port as a receiver, or only for config commands
+pub fn open(app_handle: tauri::AppHandle, port_name: String, baud_rate: u32, mode: PortMode) -> Result<String, String> {
     let state_mutex = get_state();
     let mut state = state_mutex.lock().map_err(|e| format!("state lock error: {}", e))?;
-    if state.handle.is_some() {
+    if state.ports.contains_key(&port_name) {
         return Err("Port already open".into());
     }
 
@@ -201,94 +231,63 @@ pub fn open_port(app_handle: tauri::AppHandle, port_name: String, baud_rate: u32
     let stop = Arc::new(AtomicBool::new(false));
     let stop_cloned = stop.clone();
     let app = app_handle.clone();
+    let receiver_port = port_name.clone();
 
     let handle = thread::spawn(move || {
         let mut reader = std::io::BufReader::new(port);
         let mut buf = String::new();
-        let mut current: Option<DataPacket> = None;
-
-        let emit_packet = |pkt: DataPacket| {
-            let _ = app.emit("serial-packet", pkt);
-        };
-
-        let merge_packet = |dst: &mut DataPacket, src: DataPacket| {
-            if src.node_id.is_some() {
-                dst.node_id = src.node_id;
-            }
-            if src.latitude.is_some() {
-                dst.latitude = src.latitude;
-            }
-            if src.longitude.is_some() {
-                dst.longitude = src.longitude;
-            }
-            if src.altitude_ft.is_some() {
-                dst.altitude_ft = src.altitude_ft;
-            }
-            if src.satellites_count.is_some() {
-                dst.satellites_count = src.satellites_count;
-            }
-            if src.receiver_rssi.is_some() {
-                dst.receiver_rssi = src.receiver_rssi;
-            }
-            if src.receiver_snr.is_some() {
-                dst.receiver_snr = src.receiver_snr;
-            }
-            if src.callsign.is_some() {
-                dst.callsign = src.callsign;
-            }
-            if !matches!(src.fix_status, FixStatus::Unknown) {
-                dst.fix_status = src.fix_status;
-            }
-            dst.timestamp_ms = src.timestamp_ms;
-            dst.raw_lines.extend(src.raw_lines);
+        let mut assembler = PacketAssembler::default();
+
+        let emit_packet = |mut pkt: DataPacket| {
+            pkt.receptions = vec![Reception {
+                port: receiver_port.clone(),
+                rssi: pkt.receiver_rssi,
+                snr: pkt.receiver_snr,
+                arrival_ms: pkt.timestamp_ms,
+                device_uptime_ms: pkt.device_uptime_ms,
+            }];
+            pipeline::submit_packet(&app, pkt);
         };
 
         while !stop_cloned.load(Ordering::Relaxed) {
-            buf.clear();
+            // A read that times out keeps what it got, so a line can arrive over several reads
             match reader.read_line(&mut buf) {
                 Ok(0) => {
                     continue;
                 }
                 Ok(_) => {
                     let line = buf.trim_end_matches(&['\r', '\n'][..]).to_string();
+                    buf.clear();
+                    // Firmware management frames are binary data for whoever asked, not console output
+                    if smp::observe_line(&receiver_port, &line) {
+                        continue;
+                    }
+                    if mode == PortMode::ConfigOnly {
+                        config::observe_line(&receiver_port, &line);
+                        continue;
+                    }
                     // Emit raw line for debug
                     let _ = app.emit("serial-line", line.clone());
+                    autolog::record_line(&receiver_port, &line);
+                    db::record_line(&receiver_port, &line);
+                    config::observe_line(&receiver_port, &line);
+                    console::observe_line(&receiver_port, &line);
 
-                    let is_packet_start = RE_HEADER_NODE.is_match(&line)
-                        || RE_HEADER_LICENSED_NOFIX.is_match(&line);
-                    let line_lower = line.to_lowercase();
-                    let is_packet_end = line_lower.contains("fix status:")
-                        || line_lower.contains("no fix acquired");
-
-                    if is_packet_start {
-                        if let Some(prev) = current.take() {
-                            emit_packet(prev);
-                        }
+                    if config::identity::dialect(&receiver_port) == ParserDialect::Console {
+                        continue;
                     }
-
-                    match parse_zephyr_line(&line) {
-                        Ok(pkt_part) => {
-                            if let Some(existing) = current.as_mut() {
-                                merge_packet(existing, pkt_part);
-                            } else {
-                                current = Some(pkt_part);
-                            }
-
-                            if is_packet_end {
-                                if let Some(done) = current.take() {
-                                    emit_packet(done);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            let _ = app.emit("serial-parse-error", json!({
-                                "line": line,
-                                "error": format!("{e:?}"),
-                            }));
-                        }
+                    if let Err(e) = assembler.push_line(&line, &emit_packet) {
+                        let _ = app.emit("serial-parse-error", json!({
+                            "line": line,
+                            "error": format!("{e:?}"),
+                        }));
                     }
                 }
                 Err(_) => {
+                    // The shell's prompt never ends in a newline; pick it up once the port goes quiet
+                    if !buf.is_empty() && console::observe_partial(&receiver_port, &buf) {
+                        buf.clear();
+                    }
                     // Just loop and check stop flag if theres an err
                     continue;
                 }
@@ -296,38 +295,120 @@ pub fn open_port(app_handle: tauri::AppHandle, port_name: String, baud_rate: u32
         }
 
         // Flush pending packets on shutdown
-        if let Some(pending) = current.take() {
+        if let Some(pending) = assembler.finish() {
             emit_packet(pending);
         }
     });
 
-    state.stop_flag = Some(stop);
-    state.handle = Some(handle);
-    state.writer = Some(writer);
+    if mode == PortMode::Receiver {
+        autolog::port_opened(&app_handle, &port_name, baud_rate);
+        db::port_opened(&port_name, baud_rate);
+    }
+    state.ports.insert(port_name, PortConnection {
+        stop_flag: stop,
+        handle,
+        writer,
+        baud_rate,
+        mode,
+    });
 
     Ok("ok".into())
 }
 
+// Closes one port, or every open port when no name is given.
 #[tauri::command]
-pub fn close_port() -> Result<String, String> {
-    let state_mutex = get_state();
-    let mut state = state_mutex.lock().map_err(|e| format!("state lock error: {}", e))?;
-    state.writer = None;
-    if let Some(stop) = state.stop_flag.take() {
-        stop.store(true, Ordering::Relaxed);
-    }
-    if let Some(handle) = state.handle.take() {
-        let _ = handle.join();
+pub fn close_port(port_name: Option<String>) -> Result<String, String> {
+    let closing: Vec<(String, PortConnection)> = {
+        let state_mutex = get_state();
+        let mut state = state_mutex.lock().map_err(|e| format!("state lock error: {}", e))?;
+        match port_name {
+            Some(name) => state.ports.remove_entry(&name).into_iter().collect(),
+            None => std::mem::take(&mut state.ports).into_iter().collect(),
+        }
+    };
+    // Join outside the lock; reader threads consult the port list while flushing packets
+    for (name, conn) in closing {
+        drop(conn.writer);
+        conn.stop_flag.store(true, Ordering::Relaxed);
+        let _ = conn.handle.join();
+        if conn.mode == PortMode::Receiver {
+            autolog::port_closed(&name);
+            db::port_closed(&name);
+        }
+        config::identity::forget(&name);
+        console::close(&name);
     }
     Ok("closed".into())
 }
 
-#[tauri::command]
-pub fn write_serial(data: String) -> Result<(), String> {
+// The port a command is meant for: the named one, or the only one open.
+pub fn resolve_port(port_name: Option<String>) -> Result<String, String> {
+    let state = get_state().lock().map_err(|e| format!("state lock error: {}", e))?;
+    match port_name {
+        Some(name) if state.ports.contains_key(&name) => Ok(name),
+        Some(_) => Err("Port not open".into()),
+        None if state.ports.len() > 1 => Err("Several ports are open; specify which one to write to".into()),
+        None => state.ports.keys().next().cloned().ok_or_else(|| "Port not open".into()),
+    }
+}
+
+pub fn port_baud_rate(port_name: &str) -> Option<u32> {
+    get_state().lock().ok()?.ports.get(port_name).map(|conn| conn.baud_rate)
+}
+
+pub fn port_mode(port_name: &str) -> Option<PortMode> {
+    get_state().lock().ok()?.ports.get(port_name).map(|conn| conn.mode)
+}
+
+// Resets boards whose USB bridge wires RTS to the MCU's reset line, with DTR held released so
+// boot-mode straps stay at their defaults.
+pub fn pulse_reset(port_name: &str) -> Result<(), String> {
     let state_mutex = get_state();
     let mut state = state_mutex.lock().map_err(|e| format!("state lock error: {}", e))?;
-    let writer = state.writer.as_mut().ok_or("Port not open")?;
-    let line = format!("{}\n", data);
-    writer.write_all(line.as_bytes()).map_err(|e| format!("Write error: {}", e))?;
+    let conn = state.ports.get_mut(port_name).ok_or("Port not open")?;
+    let line_error = |e: serialport::Error| format!("Failed to set control lines: {}", e);
+    conn.writer.write_data_terminal_ready(false).map_err(line_error)?;
+    conn.writer.write_request_to_send(true).map_err(line_error)?;
+    thread::sleep(std::time::Duration::from_millis(100));
+    conn.writer.write_request_to_send(false).map_err(line_error)?;
+    Ok(())
+}
+
+// Sends one line to an open port.
+pub fn write_line(port_name: &str, data: &str) -> Result<(), String> {
+    let state_mutex = get_state();
+    let mut state = state_mutex.lock().map_err(|e| format!("state lock error: {}", e))?;
+    let conn = state.ports.get_mut(port_name).ok_or("Port not open")?;
+    // Settings writes are checked against the schema and kept in the session history alongside the
+    // telemetry they affected
+    let mut words = data.split_whitespace();
+    let setting = match (words.next(), words.next(), words.clone().next()) {
+        (Some("config"), Some(key), Some(_)) => Some((key, schema::validate(key, &words.collect::<Vec<_>>().join(" "))?)),
+        _ => None,
+    };
+    let line = match &setting {
+        Some((key, value)) => format!("config {} {}\n", key, value),
+        None => format!("{}\n", data),
+    };
+    conn.writer.write_all(line.as_bytes()).map_err(|e| format!("Write error: {}", e))?;
+    if let Some((key, value)) = setting.filter(|_| conn.mode == PortMode::Receiver) {
+        db::record_config_change(Some(port_name), key, &value);
+    }
     Ok(())
 }
+
+// Runs a command's serial exchanges off the async runtime, which they would otherwise block for
+// seconds at a time
+pub async fn blocking<T, E>(work: impl FnOnce() -> Result<T, E> + Send + 'static) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(work).await.map_err(|e| E::from(format!("Serial request failed: {e}")))?
+}
+
+#[tauri::command]
+pub fn write_serial(data: String, port_name: Option<String>) -> Result<(), String> {
+    let name = resolve_port(port_name)?;
+    write_line(&name, &data)
+}
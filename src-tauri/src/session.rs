@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::DataPacket;
+
+// Roughly a full day of four trackers at one packet per second
+const MAX_SESSION_PACKETS: usize = 500_000;
+
+// Every packet of the current session as it left the pipeline, so exports aren't limited to what
+// the UI happens to keep.
+#[derive(Debug)]
+struct SessionStore {
+    started_ms: i64,
+    packets: VecDeque<DataPacket>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PacketQuery {
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    // Node keys as shown in the UI, e.g. "Node 4"; None means every node
+    pub nodes: Option<Vec<String>>,
+}
+
+impl PacketQuery {
+    pub fn matches(&self, pkt: &DataPacket) -> bool {
+        self.start_ms.is_none_or(|start| pkt.timestamp_ms >= start)
+            && self.end_ms.is_none_or(|end| pkt.timestamp_ms <= end)
+            && self.nodes.as_ref().is_none_or(|nodes| nodes.contains(&pkt.node_key()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub started_ms: i64,
+    pub packet_count: usize,
+    pub nodes: Vec<String>,
+    pub first_packet_ms: Option<i64>,
+    pub last_packet_ms: Option<i64>,
+}
+
+static SESSION: OnceLock<Mutex<SessionStore>> = OnceLock::new();
+
+fn get_session() -> &'static Mutex<SessionStore> {
+    SESSION.get_or_init(|| {
+        Mutex::new(SessionStore {
+            started_ms: Utc::now().timestamp_millis(),
+            packets: VecDeque::new(),
+        })
+    })
+}
+
+pub fn record(pkt: &DataPacket) {
+    let Ok(mut session) = get_session().lock() else {
+        return;
+    };
+    if session.packets.len() >= MAX_SESSION_PACKETS {
+        session.packets.pop_front();
+    }
+    session.packets.push_back(pkt.clone());
+}
+
+// Swaps in a previously recorded session.
+pub fn replace(started_ms: i64, packets: Vec<DataPacket>) {
+    let Ok(mut session) = get_session().lock() else {
+        return;
+    };
+    session.started_ms = started_ms;
+    session.packets = packets.into_iter().collect();
+}
+
+pub fn query(query: &PacketQuery) -> Result<Vec<DataPacket>, String> {
+    let session = get_session().lock().map_err(|e| format!("session lock error: {}", e))?;
+    Ok(session.packets.iter().filter(|p| query.matches(p)).cloned().collect())
+}
+
+#[tauri::command]
+pub fn get_session_info() -> Result<SessionInfo, String> {
+    let session = get_session().lock().map_err(|e| format!("session lock error: {}", e))?;
+    let mut nodes: Vec<String> = Vec::new();
+    for pkt in &session.packets {
+        let key = pkt.node_key();
+        if !nodes.contains(&key) {
+            nodes.push(key);
+        }
+    }
+    Ok(SessionInfo {
+        started_ms: session.started_ms,
+        packet_count: session.packets.len(),
+        nodes,
+        first_packet_ms: session.packets.iter().map(|p| p.timestamp_ms).min(),
+        last_packet_ms: session.packets.iter().map(|p| p.timestamp_ms).max(),
+    })
+}
+
+#[tauri::command]
+pub fn get_session_packets(query: Option<PacketQuery>) -> Result<Vec<DataPacket>, String> {
+    self::query(&query.unwrap_or_default())
+}
+
+// Starts a new session; packets already exported or logged elsewhere are unaffected.
+#[tauri::command]
+pub fn clear_session() -> Result<(), String> {
+    let mut session = get_session().lock().map_err(|e| format!("session lock error: {}", e))?;
+    session.packets.clear();
+    session.started_ms = Utc::now().timestamp_millis();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_filters_time_range_and_nodes() {
+        let pkt = |node_id: u8, ts: i64| DataPacket { node_id: Some(node_id), timestamp_ms: ts, ..Default::default() };
+        let query = PacketQuery { start_ms: Some(1_000), end_ms: Some(2_000), nodes: Some(vec!["Node 1".into()]) };
+        assert!(query.matches(&pkt(1, 1_000)));
+        assert!(query.matches(&pkt(1, 2_000)));
+        assert!(!query.matches(&pkt(1, 2_001)));
+        assert!(!query.matches(&pkt(2, 1_500)));
+        assert!(PacketQuery::default().matches(&pkt(2, 0)));
+    }
+}
@@ -0,0 +1,148 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+// SMP over a serial console: each packet is length-prefixed, CRC-checked, base64 encoded and split
+// into lines no longer than MAX_FRAME, the first marked 0x06 0x09 and the rest 0x04 0x14.
+pub const MAX_FRAME: usize = 127;
+const FIRST: &str = "\u{6}\u{9}";
+const CONTINUATION: &str = "\u{4}\u{14}";
+// Base64 per line, a multiple of 4 so each line decodes on its own
+const LINE_CHARS: usize = (MAX_FRAME - 3) / 4 * 4;
+
+pub const HEADER_LEN: usize = 8;
+
+// Replies carry the request's op plus one
+pub const OP_READ: u8 = 0;
+pub const OP_WRITE: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub op: u8,
+    pub flags: u8,
+    pub len: u16,
+    pub group: u16,
+    pub seq: u8,
+    pub id: u8,
+}
+
+impl Header {
+    pub fn to_bytes(self) -> [u8; HEADER_LEN] {
+        let [len_hi, len_lo] = self.len.to_be_bytes();
+        let [group_hi, group_lo] = self.group.to_be_bytes();
+        [self.op, self.flags, len_hi, len_lo, group_hi, group_lo, self.seq, self.id]
+    }
+
+    pub fn parse(packet: &[u8]) -> Option<Header> {
+        let b = packet.get(..HEADER_LEN)?;
+        Some(Header {
+            // The upper bits carry the SMP version
+            op: b[0] & 0x07,
+            flags: b[1],
+            len: u16::from_be_bytes([b[2], b[3]]),
+            group: u16::from_be_bytes([b[4], b[5]]),
+            seq: b[6],
+            id: b[7],
+        })
+    }
+}
+
+// CRC-16/XMODEM, as the mcumgr serial transport uses
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, byte| {
+        (0..8).fold(crc ^ ((*byte as u16) << 8), |crc, _| if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 })
+    })
+}
+
+pub fn is_frame(line: &str) -> bool {
+    line.starts_with(FIRST) || line.starts_with(CONTINUATION)
+}
+
+// The console lines carrying one SMP packet, without their newlines
+pub fn encode(packet: &[u8]) -> Vec<String> {
+    let mut body = Vec::with_capacity(packet.len() + 4);
+    body.extend_from_slice(&((packet.len() + 2) as u16).to_be_bytes());
+    body.extend_from_slice(packet);
+    body.extend_from_slice(&crc16(packet).to_be_bytes());
+    let text = STANDARD.encode(body);
+    text.as_bytes()
+        .chunks(LINE_CHARS)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let marker = if i == 0 { FIRST } else { CONTINUATION };
+            format!("{marker}{}", String::from_utf8_lossy(chunk))
+        })
+        .collect()
+}
+
+// Reassembles packets from console lines.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buf: Vec<u8>,
+    started: bool,
+}
+
+impl Decoder {
+    // The packet once its last line is in; a corrupt packet is an error and is dropped.
+    pub fn push(&mut self, line: &str) -> Result<Option<Vec<u8>>, String> {
+        let line = line.trim_end();
+        let chunk = if let Some(chunk) = line.strip_prefix(FIRST) {
+            self.buf.clear();
+            self.started = true;
+            chunk
+        } else if let Some(chunk) = line.strip_prefix(CONTINUATION).filter(|_| self.started) {
+            chunk
+        } else {
+            return Ok(None);
+        };
+        let decoded = STANDARD.decode(chunk).map_err(|e| {
+            self.started = false;
+            format!("Bad SMP frame: {e}")
+        })?;
+        self.buf.extend_from_slice(&decoded);
+
+        let Some(len) = self.buf.get(..2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize) else {
+            return Ok(None);
+        };
+        if self.buf.len() < len + 2 {
+            return Ok(None);
+        }
+        self.started = false;
+        let body = &self.buf[2..len + 2];
+        // The CRC over the packet and its own CRC is zero
+        if len < 2 || crc16(body) != 0 {
+            return Err("SMP frame failed its CRC check".into());
+        }
+        Ok(Some(body[..len - 2].to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_survive_framing() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+
+        let header = Header { op: OP_WRITE, flags: 0, len: 300, group: 1, seq: 42, id: 1 };
+        assert_eq!(Header::parse(&header.to_bytes()), Some(header));
+
+        let packet: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+        let lines = encode(&packet);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.len() < MAX_FRAME && is_frame(line)));
+
+        let mut decoder = Decoder::default();
+        let decoded: Vec<Option<Vec<u8>>> = lines.iter().map(|line| decoder.push(line).unwrap()).collect();
+        assert!(decoded[..lines.len() - 1].iter().all(Option::is_none));
+        assert_eq!(decoded.last().unwrap().as_deref(), Some(&packet[..]));
+
+        let mut corrupt = lines.clone();
+        let mut middle = corrupt[1].clone().into_bytes();
+        middle[10] = if middle[10] == b'A' { b'B' } else { b'A' };
+        corrupt[1] = String::from_utf8(middle).unwrap();
+        let mut decoder = Decoder::default();
+        let results: Vec<_> = corrupt.iter().map(|line| decoder.push(line)).collect();
+        assert!(results.last().unwrap().is_err());
+    }
+}
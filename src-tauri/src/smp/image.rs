@@ -0,0 +1,93 @@
+use sha2::{Digest, Sha256};
+
+const IMAGE_MAGIC: u32 = 0x96f3_b83d;
+const TLV_INFO_MAGIC: u16 = 0x6907;
+const TLV_PROT_INFO_MAGIC: u16 = 0x6908;
+const TLV_SHA256: u16 = 0x10;
+const MIN_HEADER_LEN: usize = 32;
+
+// What MCUboot will report for a signed image once it's in a slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub version: String,
+    // SHA-256 from the image's TLVs, which is the hash `image list` shows
+    pub hash: Vec<u8>,
+    pub size: usize,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// Mirrors how Zephyr's img_mgmt formats `ih_ver`
+pub fn format_version(major: u8, minor: u8, revision: u16, build: u32) -> String {
+    match build {
+        0 => format!("{major}.{minor}.{revision}"),
+        build => format!("{major}.{minor}.{revision}.{build}"),
+    }
+}
+
+// Reads a signed MCUboot image (e.g. zephyr.signed.bin), checking its embedded hash so a truncated
+// or corrupted file is caught before it goes anywhere near the device.
+pub fn parse(data: &[u8]) -> Result<ImageInfo, String> {
+    let not_image = || "Not a signed MCUboot image".to_string();
+    if u32_at(data, 0) != Some(IMAGE_MAGIC) {
+        return Err(not_image());
+    }
+    let hdr_size = u16_at(data, 8).ok_or_else(not_image)? as usize;
+    let protect_tlv_size = u16_at(data, 10).ok_or_else(not_image)? as usize;
+    let img_size = u32_at(data, 12).ok_or_else(not_image)? as usize;
+    // The version fields sit inside the fixed header, so a file shorter than that can't be read
+    if hdr_size < MIN_HEADER_LEN || data.len() < MIN_HEADER_LEN {
+        return Err(not_image());
+    }
+    let version = format_version(data[20], data[21], u16_at(data, 22).unwrap_or(0), u32_at(data, 24).unwrap_or(0));
+
+    // The hash covers the header, the body and any protected TLVs, which come before the others
+    let mut tlv_start = hdr_size + img_size;
+    if protect_tlv_size > 0 {
+        if u16_at(data, tlv_start) != Some(TLV_PROT_INFO_MAGIC) {
+            return Err("The image's protected TLVs are missing".into());
+        }
+        tlv_start += protect_tlv_size;
+    }
+    if u16_at(data, tlv_start) != Some(TLV_INFO_MAGIC) {
+        return Err("The image is truncated or has no TLVs".into());
+    }
+    let tlv_end = tlv_start + u16_at(data, tlv_start + 2).unwrap_or(0) as usize;
+    if tlv_end > data.len() {
+        return Err("The image is truncated".into());
+    }
+
+    let mut offset = tlv_start + 4;
+    let mut hash = None;
+    while offset + 4 <= tlv_end {
+        let kind = u16_at(data, offset).unwrap_or(0);
+        let len = u16_at(data, offset + 2).unwrap_or(0) as usize;
+        if kind == TLV_SHA256 {
+            hash = data.get(offset + 4..offset + 4 + len).map(<[u8]>::to_vec);
+        }
+        offset += 4 + len;
+    }
+    let hash = hash.ok_or("The image has no SHA-256 TLV")?;
+    if Sha256::digest(&data[..tlv_start]).as_slice() != hash.as_slice() {
+        return Err("The image's contents don't match its hash; the file is corrupt".into());
+    }
+    Ok(ImageInfo { version, hash, size: data.len() })
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("{text:?} is not a hex hash"));
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(|e| e.to_string())).collect()
+}
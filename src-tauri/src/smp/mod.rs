@@ -0,0 +1,550 @@
+pub mod frame;
+pub mod image;
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use ciborium::value::Value;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+
+use crate::serial::{self, blocking};
+use frame::{Decoder, Header, HEADER_LEN, OP_READ, OP_WRITE};
+
+const GROUP_OS: u16 = 0;
+const GROUP_IMAGE: u16 = 1;
+const OS_ECHO: u8 = 0;
+const OS_RESET: u8 = 5;
+const IMAGE_STATE: u8 = 0;
+const IMAGE_UPLOAD: u8 = 1;
+
+// Largest SMP packet the firmware's shell transport takes (CONFIG_MCUMGR_TRANSPORT_SHELL_MTU)
+const DEFAULT_MTU: usize = 256;
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+// The first chunk makes the device erase the whole slot before answering
+const FIRST_CHUNK_TIMEOUT_MS: u64 = 30_000;
+const CHUNK_ATTEMPTS: usize = 3;
+
+// One image slot as `image list` reports it
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageSlot {
+    pub image: u64,
+    pub slot: u64,
+    pub version: String,
+    pub hash: String,
+    pub bootable: bool,
+    // Marked to be tried on the next boot
+    pub pending: bool,
+    pub confirmed: bool,
+    pub active: bool,
+    pub permanent: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadReport {
+    pub port: String,
+    pub version: String,
+    pub hash: String,
+    pub size: usize,
+    pub elapsed_ms: u64,
+    // Where the device now lists the image, with the hash it computed itself
+    pub slot: ImageSlot,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadProgress {
+    pub port: String,
+    pub sent: usize,
+    pub total: usize,
+}
+
+// Carries SMP console lines to and from a device
+pub trait Link {
+    fn send_line(&mut self, line: &str) -> Result<(), String>;
+    // None once `timeout` passes without a line
+    fn receive_line(&mut self, timeout: Duration) -> Option<String>;
+}
+
+fn map(entries: Vec<(&str, Value)>) -> Value {
+    Value::Map(entries.into_iter().map(|(key, value)| (Value::Text(key.into()), value)).collect())
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value.as_map()?.iter().find(|(k, _)| k.as_text() == Some(key)).map(|(_, v)| v)
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    value.as_integer().and_then(|i| u64::try_from(i).ok())
+}
+
+fn rc_text(rc: u64) -> &'static str {
+    match rc {
+        1 => "unknown error",
+        2 => "out of memory",
+        3 => "invalid argument",
+        4 => "timed out",
+        5 => "no such entry",
+        6 => "not allowed in the current state",
+        7 => "message too large",
+        8 => "not supported",
+        9 => "corrupt data",
+        10 => "busy",
+        _ => "error",
+    }
+}
+
+// SMP v1 puts the result in "rc"; v2 nests it under "err"
+fn check_rc(body: &Value) -> Result<(), String> {
+    let rc = field(body, "rc").and_then(as_u64).or_else(|| field(body, "err").and_then(|err| field(err, "rc")).and_then(as_u64));
+    match rc {
+        None | Some(0) => Ok(()),
+        Some(rc) => Err(format!("The device refused the request: {} ({rc})", rc_text(rc))),
+    }
+}
+
+fn parse_images(body: &Value) -> Vec<ImageSlot> {
+    let flag = |image: &Value, key: &str| field(image, key).and_then(Value::as_bool).unwrap_or(false);
+    field(body, "images")
+        .and_then(Value::as_array)
+        .map(|images| {
+            images
+                .iter()
+                .map(|image| ImageSlot {
+                    image: field(image, "image").and_then(as_u64).unwrap_or(0),
+                    slot: field(image, "slot").and_then(as_u64).unwrap_or(0),
+                    version: field(image, "version").and_then(Value::as_text).unwrap_or_default().to_string(),
+                    hash: field(image, "hash").and_then(Value::as_bytes).map(|h| image::hex(h)).unwrap_or_default(),
+                    bootable: flag(image, "bootable"),
+                    pending: flag(image, "pending"),
+                    confirmed: flag(image, "confirmed"),
+                    active: flag(image, "active"),
+                    permanent: flag(image, "permanent"),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub struct Client<L: Link> {
+    link: L,
+    seq: u8,
+    pub mtu: usize,
+    pub timeout: Duration,
+}
+
+impl<L: Link> Client<L> {
+    pub fn new(link: L) -> Self {
+        Client { link, seq: 0, mtu: DEFAULT_MTU, timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS) }
+    }
+
+    // Sends one request and waits for the reply with the same sequence number, skipping anything
+    // else the device says meanwhile.
+    fn request(&mut self, op: u8, group: u16, id: u8, body: &Value, timeout: Duration) -> Result<Value, String> {
+        let mut payload = Vec::new();
+        ciborium::into_writer(body, &mut payload).map_err(|e| e.to_string())?;
+        let seq = self.seq;
+        self.seq = self.seq.wrapping_add(1);
+        let header = Header { op, flags: 0, len: payload.len() as u16, group, seq, id };
+        let mut packet = header.to_bytes().to_vec();
+        packet.extend_from_slice(&payload);
+        for line in frame::encode(&packet) {
+            self.link.send_line(&line)?;
+        }
+
+        let deadline = Instant::now() + timeout;
+        let mut decoder = Decoder::default();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!("No SMP reply within {} ms", timeout.as_millis()));
+            }
+            let Some(line) = self.link.receive_line(remaining) else { continue };
+            let Some(reply) = decoder.push(&line)? else { continue };
+            let Some(reply_header) = Header::parse(&reply) else { continue };
+            if reply_header.seq != seq || reply_header.group != group || reply_header.id != id || reply_header.op != op + 1 {
+                continue;
+            }
+            let body: Value = ciborium::from_reader(&reply[HEADER_LEN..]).map_err(|e| format!("Malformed SMP reply: {e}"))?;
+            check_rc(&body)?;
+            return Ok(body);
+        }
+    }
+
+    pub fn echo(&mut self, text: &str) -> Result<String, String> {
+        let reply = self.request(OP_WRITE, GROUP_OS, OS_ECHO, &map(vec![("d", Value::Text(text.into()))]), self.timeout)?;
+        Ok(field(&reply, "r").and_then(Value::as_text).unwrap_or_default().to_string())
+    }
+
+    pub fn reset(&mut self) -> Result<(), String> {
+        self.request(OP_WRITE, GROUP_OS, OS_RESET, &map(vec![]), self.timeout).map(|_| ())
+    }
+
+    pub fn image_list(&mut self) -> Result<Vec<ImageSlot>, String> {
+        let reply = self.request(OP_READ, GROUP_IMAGE, IMAGE_STATE, &map(vec![]), self.timeout)?;
+        Ok(parse_images(&reply))
+    }
+
+    // Marks an image to boot once on the next reset (test), or for good (confirm). Confirming
+    // without a hash keeps the image that's running now.
+    pub fn image_state(&mut self, hash: Option<&[u8]>, confirm: bool) -> Result<Vec<ImageSlot>, String> {
+        let mut entries = vec![("confirm", Value::Bool(confirm))];
+        if let Some(hash) = hash {
+            entries.push(("hash", Value::Bytes(hash.to_vec())));
+        }
+        let reply = self.request(OP_WRITE, GROUP_IMAGE, IMAGE_STATE, &map(entries), self.timeout)?;
+        Ok(parse_images(&reply))
+    }
+
+    fn upload_body(image: u32, data: &[u8], off: usize, end: usize, sha: &[u8]) -> Value {
+        let mut entries = vec![
+            ("image", Value::Integer(image.into())),
+            ("off", Value::Integer((off as u64).into())),
+            ("data", Value::Bytes(data[off..end].to_vec())),
+        ];
+        if off == 0 {
+            entries.push(("len", Value::Integer((data.len() as u64).into())));
+            entries.push(("sha", Value::Bytes(sha.to_vec())));
+        }
+        map(entries)
+    }
+
+    // Writes an image to the secondary slot in chunks sized to the device's MTU. The device says
+    // where it wants the next chunk, so a lost reply just resends from there.
+    pub fn upload(&mut self, image: u32, data: &[u8], mut progress: impl FnMut(usize, usize)) -> Result<(), String> {
+        let sha = Sha256::digest(data).to_vec();
+        let mut off = 0;
+        while off < data.len() {
+            let mut overhead = Vec::new();
+            ciborium::into_writer(&Self::upload_body(image, data, off, off, &sha), &mut overhead).map_err(|e| e.to_string())?;
+            // Leave room for the data's length prefix to grow
+            let room = self.mtu.saturating_sub(HEADER_LEN + overhead.len() + 4);
+            if room == 0 {
+                return Err(format!("An MTU of {} bytes leaves no room for image data", self.mtu));
+            }
+            let end = (off + room).min(data.len());
+            let body = Self::upload_body(image, data, off, end, &sha);
+            let timeout = if off == 0 { self.timeout.max(Duration::from_millis(FIRST_CHUNK_TIMEOUT_MS)) } else { self.timeout };
+
+            let mut attempt = 0;
+            let reply = loop {
+                attempt += 1;
+                match self.request(OP_WRITE, GROUP_IMAGE, IMAGE_UPLOAD, &body, timeout) {
+                    Ok(reply) => break reply,
+                    Err(e) if attempt >= CHUNK_ATTEMPTS => return Err(format!("Upload failed at byte {off}: {e}")),
+                    Err(_) => continue,
+                }
+            };
+            let next = field(&reply, "off").and_then(as_u64).ok_or("The device didn't say how much of the image it has")? as usize;
+            if next == off || next > data.len() {
+                return Err(format!("The device stopped accepting the image at byte {off}"));
+            }
+            off = next;
+            progress(off, data.len());
+        }
+        Ok(())
+    }
+}
+
+// Lines of SMP frames waiting for a request on each port
+static PENDING: OnceLock<Mutex<HashMap<String, Sender<String>>>> = OnceLock::new();
+
+fn get_pending() -> &'static Mutex<HashMap<String, Sender<String>>> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Takes SMP frames out of a port's output so they never reach the shell log or the telemetry
+// parser. Returns whether the line was one.
+pub fn observe_line(port: &str, line: &str) -> bool {
+    if !frame::is_frame(line) {
+        return false;
+    }
+    if let Ok(pending) = get_pending().lock() {
+        if let Some(tx) = pending.get(port) {
+            let _ = tx.send(line.to_string());
+        }
+    }
+    true
+}
+
+pub struct SerialLink {
+    port: String,
+    lines: Receiver<String>,
+}
+
+impl SerialLink {
+    pub fn open(port: &str) -> Result<Self, String> {
+        let mut pending = get_pending().lock().map_err(|e| format!("SMP lock error: {}", e))?;
+        if pending.contains_key(port) {
+            return Err(format!("{port} is busy with another firmware request"));
+        }
+        let (tx, rx) = mpsc::channel();
+        pending.insert(port.to_string(), tx);
+        Ok(SerialLink { port: port.to_string(), lines: rx })
+    }
+}
+
+impl Drop for SerialLink {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = get_pending().lock() {
+            pending.remove(&self.port);
+        }
+    }
+}
+
+impl Link for SerialLink {
+    fn send_line(&mut self, line: &str) -> Result<(), String> {
+        serial::write_line(&self.port, line)
+    }
+
+    fn receive_line(&mut self, timeout: Duration) -> Option<String> {
+        self.lines.recv_timeout(timeout).ok()
+    }
+}
+
+fn client(port_name: Option<String>) -> Result<(String, Client<SerialLink>), String> {
+    let port = serial::resolve_port(port_name)?;
+    let link = SerialLink::open(&port)?;
+    Ok((port, Client::new(link)))
+}
+
+#[tauri::command]
+pub async fn smp_echo(port_name: Option<String>, text: String) -> Result<String, String> {
+    blocking(move || client(port_name)?.1.echo(&text)).await
+}
+
+#[tauri::command]
+pub async fn smp_image_list(port_name: Option<String>) -> Result<Vec<ImageSlot>, String> {
+    blocking(move || client(port_name)?.1.image_list()).await
+}
+
+// Uploads a signed image and checks the device lists it with the hash the file carries. Progress
+// is reported through "firmware-upload" events.
+#[tauri::command]
+pub async fn smp_image_upload(
+    app: AppHandle,
+    port_name: Option<String>,
+    path: String,
+    image: Option<u32>,
+) -> Result<UploadReport, String> {
+    blocking(move || {
+        let data = fs::read(&path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+        let info = image::parse(&data)?;
+        let (port, mut client) = client(port_name)?;
+        let started = Instant::now();
+        let mut last_percent = None;
+        client.upload(image.unwrap_or(0), &data, |sent, total| {
+            let percent = sent * 100 / total;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app.emit("firmware-upload", UploadProgress { port: port.clone(), sent, total });
+            }
+        })?;
+
+        let hash = image::hex(&info.hash);
+        let slot = client
+            .image_list()?
+            .into_iter()
+            .find(|slot| slot.hash == hash)
+            .ok_or("The device doesn't list an image with the uploaded hash; the upload didn't verify")?;
+        Ok(UploadReport {
+            port,
+            version: info.version,
+            hash,
+            size: info.size,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            slot,
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn smp_image_test(port_name: Option<String>, hash: String) -> Result<Vec<ImageSlot>, String> {
+    blocking(move || client(port_name)?.1.image_state(Some(&image::from_hex(&hash)?), false)).await
+}
+
+#[tauri::command]
+pub async fn smp_image_confirm(port_name: Option<String>, hash: Option<String>) -> Result<Vec<ImageSlot>, String> {
+    blocking(move || {
+        let hash = hash.as_deref().map(image::from_hex).transpose()?;
+        client(port_name)?.1.image_state(hash.as_deref(), true)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn smp_reset(port_name: Option<String>) -> Result<(), String> {
+    blocking(move || client(port_name)?.1.reset()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    // Software stand-in for a Zephyr device running mcumgr with MCUboot's two slots
+    #[derive(Default)]
+    struct Responder {
+        decoder: Decoder,
+        outbox: VecDeque<String>,
+        images: Vec<ImageSlot>,
+        upload: Vec<u8>,
+        // Replies to drop, to exercise resending a chunk
+        drop_replies: usize,
+        requests: usize,
+        reset: bool,
+    }
+
+    impl Responder {
+        fn handle(&mut self, header: Header, body: Value) -> Value {
+            match (header.group, header.id, header.op) {
+                (GROUP_OS, OS_ECHO, _) => map(vec![("r", field(&body, "d").cloned().unwrap_or(Value::Null))]),
+                (GROUP_OS, OS_RESET, _) => {
+                    self.reset = true;
+                    map(vec![])
+                }
+                (GROUP_IMAGE, IMAGE_STATE, OP_READ) => self.image_state(),
+                (GROUP_IMAGE, IMAGE_STATE, _) => {
+                    let hash = field(&body, "hash").and_then(Value::as_bytes).map(|h| image::hex(h));
+                    let confirm = field(&body, "confirm").and_then(Value::as_bool).unwrap_or(false);
+                    let Some(slot) = self.images.iter_mut().find(|slot| Some(&slot.hash) == hash.as_ref()) else {
+                        return map(vec![("rc", Value::Integer(3.into()))]);
+                    };
+                    slot.pending = true;
+                    slot.permanent = confirm;
+                    self.image_state()
+                }
+                (GROUP_IMAGE, IMAGE_UPLOAD, _) => {
+                    let off = field(&body, "off").and_then(as_u64).unwrap() as usize;
+                    let data = field(&body, "data").and_then(Value::as_bytes).unwrap();
+                    if off == 0 {
+                        self.upload.clear();
+                    }
+                    if off == self.upload.len() {
+                        self.upload.extend_from_slice(data);
+                    }
+                    if let Ok(info) = image::parse(&self.upload) {
+                        self.images.retain(|slot| slot.slot != 1);
+                        self.images.push(ImageSlot { slot: 1, version: info.version, hash: image::hex(&info.hash), bootable: true, ..Default::default() });
+                    }
+                    map(vec![("off", Value::Integer((self.upload.len() as u64).into()))])
+                }
+                _ => map(vec![("rc", Value::Integer(8.into()))]),
+            }
+        }
+
+        fn image_state(&self) -> Value {
+            let images = self
+                .images
+                .iter()
+                .map(|slot| {
+                    map(vec![
+                        ("slot", Value::Integer(slot.slot.into())),
+                        ("version", Value::Text(slot.version.clone())),
+                        ("hash", Value::Bytes(image::from_hex(&slot.hash).unwrap())),
+                        ("bootable", Value::Bool(slot.bootable)),
+                        ("pending", Value::Bool(slot.pending)),
+                        ("confirmed", Value::Bool(slot.confirmed)),
+                        ("active", Value::Bool(slot.active)),
+                        ("permanent", Value::Bool(slot.permanent)),
+                    ])
+                })
+                .collect();
+            map(vec![("images", Value::Array(images))])
+        }
+    }
+
+    impl Link for Responder {
+        fn send_line(&mut self, line: &str) -> Result<(), String> {
+            let Some(packet) = self.decoder.push(line)? else { return Ok(()) };
+            let header = Header::parse(&packet).unwrap();
+            let body: Value = ciborium::from_reader(&packet[HEADER_LEN..]).unwrap();
+            let reply = self.handle(header, body);
+            self.requests += 1;
+            if self.drop_replies > 0 && self.requests.is_multiple_of(5) {
+                self.drop_replies -= 1;
+                return Ok(());
+            }
+            let mut payload = Vec::new();
+            ciborium::into_writer(&reply, &mut payload).unwrap();
+            let reply_header = Header { op: header.op + 1, len: payload.len() as u16, ..header };
+            let mut packet = reply_header.to_bytes().to_vec();
+            packet.extend_from_slice(&payload);
+            // Shell output can land between frames
+            self.outbox.push_back("uart:~$ ".into());
+            self.outbox.extend(frame::encode(&packet));
+            Ok(())
+        }
+
+        fn receive_line(&mut self, _timeout: Duration) -> Option<String> {
+            self.outbox.pop_front()
+        }
+    }
+
+    fn signed_image(body_len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&0x96f3_b83du32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&32u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&(body_len as u32).to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&[1, 4]);
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend((0..body_len).map(|i| (i * 31 % 251) as u8));
+        let hash = Sha256::digest(&data);
+        data.extend_from_slice(&0x6907u16.to_le_bytes());
+        data.extend_from_slice(&(4u16 + 4 + 32).to_le_bytes());
+        data.extend_from_slice(&0x10u16.to_le_bytes());
+        data.extend_from_slice(&32u16.to_le_bytes());
+        data.extend_from_slice(&hash);
+        data
+    }
+
+    #[test]
+    fn image_is_uploaded_verified_and_confirmed() {
+        let running = ImageSlot { slot: 0, version: "1.3.0".into(), hash: "ab".repeat(32), bootable: true, confirmed: true, active: true, ..Default::default() };
+        let responder = Responder { images: vec![running], drop_replies: 2, ..Default::default() };
+        let mut client = Client::new(responder);
+        client.timeout = Duration::from_millis(50);
+
+        assert_eq!(client.echo("hello").unwrap(), "hello");
+
+        let data = signed_image(3000);
+        let info = image::parse(&data).unwrap();
+        assert_eq!(info.version, "1.4.2");
+        let mut corrupt = data.clone();
+        corrupt[100] ^= 0xff;
+        assert!(image::parse(&corrupt).is_err());
+        assert!(image::parse(&data[..20]).is_err());
+
+        let mut sent = Vec::new();
+        client.upload(0, &data, |done, total| sent.push((done, total))).unwrap();
+        assert!(sent.len() > 10);
+        assert_eq!(sent.last(), Some(&(data.len(), data.len())));
+        assert!(sent.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(client.link.drop_replies, 0);
+
+        let hash = image::hex(&info.hash);
+        let images = client.image_list().unwrap();
+        let uploaded = images.iter().find(|slot| slot.slot == 1).unwrap();
+        assert_eq!((uploaded.version.as_str(), uploaded.hash.as_str()), ("1.4.2", hash.as_str()));
+
+        let images = client.image_state(Some(&info.hash), false).unwrap();
+        assert!(images.iter().any(|slot| slot.slot == 1 && slot.pending && !slot.permanent));
+        let err = client.image_state(Some(&[0; 32]), true).unwrap_err();
+        assert!(err.contains("invalid argument"), "{err}");
+
+        client.reset().unwrap();
+        assert!(client.link.reset);
+    }
+}
@@ -1,18 +1,66 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum FixStatus {
     NoFix,
     Fix,
     Diff,
     Est,
+    #[default]
     Unknown,
 }
 
-impl Default for FixStatus {
-    fn default() -> Self {
-        FixStatus::Unknown
+impl FixStatus {
+    // Short form the UI and exports show
+    pub fn label(&self) -> &'static str {
+        match self {
+            FixStatus::NoFix => "NOFIX",
+            FixStatus::Fix => "FIX",
+            FixStatus::Diff => "DIFF",
+            FixStatus::Est => "EST",
+            FixStatus::Unknown => "UNKNOWN",
+        }
     }
+
+    pub fn from_label(label: &str) -> Option<FixStatus> {
+        match label.trim().to_ascii_uppercase().as_str() {
+            "NOFIX" => Some(FixStatus::NoFix),
+            "FIX" => Some(FixStatus::Fix),
+            "DIFF" => Some(FixStatus::Diff),
+            "EST" => Some(FixStatus::Est),
+            "UNKNOWN" => Some(FixStatus::Unknown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RejectReason {
+    NoFix,
+    Estimated,
+    LowSatellites,
+    ImpossibleJump,
+    ImpossibleClimb,
+}
+
+// Output of the position filter. Raw coordinates stay on the packet untouched.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FilteredPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_ft: Option<f64>,
+    pub rejected: Option<RejectReason>,
+}
+
+// One receiver's copy of a transmission.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Reception {
+    pub port: String,
+    pub rssi: Option<i16>,
+    pub snr: Option<i8>,
+    pub arrival_ms: i64,
+    #[serde(default)]
+    pub device_uptime_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -27,5 +75,47 @@ pub struct DataPacket {
     pub receiver_snr: Option<i8>,
     pub callsign: Option<String>,
     pub timestamp_ms: i64,
+    // Receiver uptime from the Zephyr log prefix of the packet's first line
+    #[serde(default)]
+    pub device_uptime_ms: Option<u64>,
     pub raw_lines: Vec<String>,
+    #[serde(default)]
+    pub filtered: Option<FilteredPosition>,
+    // Every receiver that heard this transmission, in arrival order
+    #[serde(default)]
+    pub receptions: Vec<Reception>,
+}
+
+impl DataPacket {
+    // Same identity the frontend uses for trackers: "KD2YIE-4", "Node 4", or the bare callsign.
+    pub fn node_key(&self) -> String {
+        match (&self.callsign, self.node_id) {
+            (Some(callsign), Some(id)) => format!("{callsign}-{id}"),
+            (None, Some(id)) => format!("Node {id}"),
+            (Some(callsign), None) => callsign.clone(),
+            (None, None) => "unknown".into(),
+        }
+    }
+}
+
+// Packets for the tests of every module that handles them
+#[cfg(test)]
+pub mod fixtures {
+    use super::{DataPacket, FixStatus};
+
+    // A good fix; tests override whatever they care about with struct update syntax
+    pub fn packet(node_id: u8, ts: i64, lat: f32, lon: f32, altitude_ft: i32) -> DataPacket {
+        DataPacket {
+            node_id: Some(node_id),
+            latitude: Some(lat),
+            longitude: Some(lon),
+            altitude_ft: Some(altitude_ft),
+            satellites_count: Some(9),
+            fix_status: FixStatus::Fix,
+            receiver_rssi: Some(-90),
+            receiver_snr: Some(6),
+            timestamp_ms: ts,
+            ..Default::default()
+        }
+    }
 }
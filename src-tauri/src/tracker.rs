@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+
+use crate::overlay::{self, Overlay};
+use crate::position_filter::{FilterConfig, PositionFilter};
+use crate::telemetry::DataPacket;
+
+#[derive(Default)]
+struct TrackerState {
+    filter: PositionFilter,
+}
+
+#[derive(Default)]
+struct TrackerRegistry {
+    filter_config: FilterConfig,
+    trackers: HashMap<String, TrackerState>,
+    // Prior tracks and planned landing zones shown for reference
+    overlays: Vec<Overlay>,
+}
+
+static REGISTRY: OnceLock<Mutex<TrackerRegistry>> = OnceLock::new();
+
+fn get_registry() -> &'static Mutex<TrackerRegistry> {
+    REGISTRY.get_or_init(|| Mutex::new(TrackerRegistry::default()))
+}
+
+// Runs the per-node position filter and attaches its result to the packet.
+pub fn ingest(pkt: &mut DataPacket) {
+    let Ok(mut registry) = get_registry().lock() else {
+        return;
+    };
+    let registry = &mut *registry;
+    let tracker = registry.trackers.entry(pkt.node_key()).or_default();
+    pkt.filtered = tracker.filter.update(&registry.filter_config, pkt);
+}
+
+// Rebuilds every node's filter from a recorded session, e.g. one loaded back from the database.
+pub fn load_history(packets: &mut [DataPacket]) {
+    let Ok(mut registry) = get_registry().lock() else {
+        return;
+    };
+    let registry = &mut *registry;
+    registry.trackers.clear();
+    for pkt in packets.iter_mut() {
+        let tracker = registry.trackers.entry(pkt.node_key()).or_default();
+        pkt.filtered = tracker.filter.update(&registry.filter_config, pkt);
+    }
+}
+
+#[tauri::command]
+pub fn get_position_filter_config() -> Result<FilterConfig, String> {
+    let registry = get_registry().lock().map_err(|e| format!("registry lock error: {}", e))?;
+    Ok(registry.filter_config.clone())
+}
+
+#[tauri::command]
+pub fn set_position_filter_config(config: FilterConfig) -> Result<(), String> {
+    let mut registry = get_registry().lock().map_err(|e| format!("registry lock error: {}", e))?;
+    registry.filter_config = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn reset_position_filters() -> Result<(), String> {
+    let mut registry = get_registry().lock().map_err(|e| format!("registry lock error: {}", e))?;
+    for tracker in registry.trackers.values_mut() {
+        tracker.filter.reset();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn import_overlays(path: String) -> Result<Vec<Overlay>, String> {
+    let file = Path::new(&path);
+    let text = fs::read_to_string(file).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    let default_name = file.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "Overlay".into());
+    let mut overlays = overlay::parse_geojson(&text, &default_name)?;
+    if overlays.is_empty() {
+        return Err("No tracks or landing zones found".into());
+    }
+
+    let stamp = Utc::now().timestamp_millis();
+    for (i, overlay) in overlays.iter_mut().enumerate() {
+        overlay.id = format!("overlay-{stamp}-{i}");
+    }
+    let mut registry = get_registry().lock().map_err(|e| format!("registry lock error: {}", e))?;
+    registry.overlays.extend(overlays.iter().cloned());
+    Ok(overlays)
+}
+
+#[tauri::command]
+pub fn list_overlays() -> Result<Vec<Overlay>, String> {
+    let registry = get_registry().lock().map_err(|e| format!("registry lock error: {}", e))?;
+    Ok(registry.overlays.clone())
+}
+
+#[tauri::command]
+pub fn remove_overlay(id: String) -> Result<(), String> {
+    let mut registry = get_registry().lock().map_err(|e| format!("registry lock error: {}", e))?;
+    registry.overlays.retain(|o| o.id != id);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_overlays() -> Result<(), String> {
+    let mut registry = get_registry().lock().map_err(|e| format!("registry lock error: {}", e))?;
+    registry.overlays.clear();
+    Ok(())
+}
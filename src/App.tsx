@@ -3,18 +3,27 @@ import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
 import { DEMO_PORT, useDemoSimulation } from "./demoSimulation";
-import type { SerialPortOption, Tracker, TelemetryPacket } from "./types";
-import { colorForIndex, fixFromString } from "./utils";
+import type { RebootProgress, SerialPortOption, Tracker, TelemetryPacket } from "./types";
+import { colorForIndex, packetFromBackend } from "./utils";
 import { TrackingTab } from "./TrackingTab";
 import { ConfigTab } from "./ConfigTab";
+import { ConsoleTab } from "./ConsoleTab";
 
 function App() {
-  const [activeTab, setActiveTab] = useState<"tracking" | "config">("tracking");
+  const [activeTab, setActiveTab] = useState<"tracking" | "config" | "console">("tracking");
 
   const [portOptions, setPortOptions] = useState<SerialPortOption[]>([]);
   const [selectedPort, setSelectedPort] = useState("");
   const [baud, setBaud] = useState(115200);
-  const [connected, setConnected] = useState(false);
+  // Several receivers can be open at once; their copies of a packet are merged by the backend
+  const [openPorts, setOpenPorts] = useState<string[]>([]);
+  const [demoRunning, setDemoRunning] = useState(false);
+  const connected = demoRunning || openPorts.length > 0;
+  // Read by the reboot listener, which only follows ports opened from the toolbar
+  const openPortsRef = useRef(openPorts);
+  openPortsRef.current = openPorts;
+  // The demo runs on its own; any real port not yet open can join the ones that are
+  const canConnect = selectedPort !== "" && !demoRunning && !openPorts.includes(selectedPort) && !(selectedPort === DEMO_PORT && openPorts.length > 0);
   const [connectError, setConnectError] = useState<string | null>(null);
 
   const [trackers, setTrackers] = useState<Record<string, Tracker>>({});
@@ -36,8 +45,11 @@ function App() {
     setTrackers((prev) => {
       const next = { ...prev } as Record<string, Tracker>;
       const tracker = next[packet.nodeId] ?? { nodeId: packet.nodeId, points: [] };
-      if (packet.lat !== undefined && packet.lon !== undefined) {
-        tracker.points = [...tracker.points, { lat: packet.lat, lon: packet.lon, ts: packet.ts }].slice(-200);
+      // Rejected fixes stay in the packet log but never extend the trail
+      const lat = packet.filteredLat ?? packet.lat;
+      const lon = packet.filteredLon ?? packet.lon;
+      if (lat !== undefined && lon !== undefined && !packet.rejected) {
+        tracker.points = [...tracker.points, { lat, lon, ts: packet.ts }].slice(-200);
       }
       tracker.latest = packet;
       next[packet.nodeId] = tracker;
@@ -74,29 +86,38 @@ function App() {
     setConnectError(null);
     if (selectedPort === DEMO_PORT) {
       startDemo();
-      setConnected(true);
+      setDemoRunning(true);
       return;
     }
     try {
       await invoke("open_port", { portName: selectedPort, baudRate: baud });
-      setConnected(true);
+      setOpenPorts((prev) => [...prev.filter((port) => port !== selectedPort), selectedPort]);
     } catch (e) {
       console.error("open_port failed", e);
-      setConnected(false);
       setConnectError(String(e));
     }
   }
 
+  async function closePort(portName: string) {
+    try {
+      await invoke("close_port", { portName });
+    } catch (e) {
+      console.warn("close_port failed", e);
+    }
+    setOpenPorts((prev) => prev.filter((port) => port !== portName));
+  }
+
   async function disconnect() {
     stopDemo();
-    if (selectedPort !== DEMO_PORT) {
+    setDemoRunning(false);
+    if (openPorts.length > 0) {
       try {
         await invoke("close_port");
       } catch (e) {
         console.warn("close_port failed", e);
       }
     }
-    setConnected(false);
+    setOpenPorts([]);
   }
 
   useEffect(() => {
@@ -110,25 +131,7 @@ function App() {
         unlisten = await listen<any>("serial-packet", (event) => {
           const pktRaw = event.payload;
           if (!pktRaw) return;
-          const callsign: string | undefined = pktRaw.callsign ?? undefined;
-          const rawNodeId: number | null = pktRaw.node_id ?? null;
-          const nodeId = rawNodeId !== null
-            ? callsign ? `${callsign}-${rawNodeId}` : `Node ${rawNodeId}`
-            : callsign ?? "unknown";
-          const pkt: TelemetryPacket = {
-            nodeId,
-            callsign,
-            lat: pktRaw.latitude ?? undefined,
-            lon: pktRaw.longitude ?? undefined,
-            altitudeFt: pktRaw.altitude_ft ?? undefined,
-            rssi: pktRaw.receiver_rssi ?? undefined,
-            snr: pktRaw.receiver_snr ?? undefined,
-            fixStatus: fixFromString(pktRaw.fix_status),
-            sats: pktRaw.satellites_count ?? undefined,
-            ts: pktRaw.timestamp_ms ?? Date.now(),
-            raw: (pktRaw.raw_lines?.join("\n")) || undefined,
-          };
-          processPacket(pkt);
+          processPacket(packetFromBackend(pktRaw));
         });
       } catch (e) {
         console.warn("Could not attach serial listener", e);
@@ -139,6 +142,62 @@ function App() {
     };
   }, [processPacket]);
 
+  // A past session loaded from the history database replaces whatever is on screen
+  useEffect(() => {
+    let unlisten: UnlistenFn | null = null;
+    (async () => {
+      try {
+        unlisten = await listen("session-loaded", async () => {
+          clearedAtRef.current = 0;
+          setPackets([]);
+          setTrackers({});
+          setTrackerColors({});
+          try {
+            const history = await invoke<any[]>("get_session_packets");
+            history.forEach((pktRaw) => processPacket(packetFromBackend(pktRaw)));
+          } catch (e) {
+            console.error("get_session_packets failed", e);
+          }
+        });
+      } catch (e) {
+        console.warn("Could not attach session listener", e);
+      }
+    })();
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [processPacket]);
+
+  // A rebooted device is closed, then reopened by the backend once it re-enumerates, possibly
+  // under a different port name
+  useEffect(() => {
+    let unlisten: UnlistenFn | null = null;
+    let closedPort: string | null = null;
+    (async () => {
+      try {
+        unlisten = await listen<RebootProgress>("device-reboot", (event) => {
+          const { stage, port } = event.payload;
+          if (stage === "waitingForDevice") {
+            closedPort = openPortsRef.current.includes(port) ? port : null;
+          } else if (stage === "reopened" && closedPort) {
+            const previous = closedPort;
+            closedPort = null;
+            setOpenPorts((prev) => [...prev.filter((p) => p !== previous && p !== port), port]);
+            refreshPorts().then(() => setSelectedPort(port));
+          } else if (stage === "failed" && closedPort) {
+            const previous = closedPort;
+            closedPort = null;
+            setOpenPorts((prev) => prev.filter((p) => p !== previous));
+          }
+        });
+      } catch (e) {
+        console.warn("Could not attach reboot listener", e);
+      }
+    })();
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
 
   return (
     <main className="layout">
@@ -158,6 +217,12 @@ function App() {
             >
               Config
             </button>
+            <button
+              className={`tab ${activeTab === "console" ? "active" : ""}`}
+              onClick={() => setActiveTab("console")}
+            >
+              Console
+            </button>
           </nav>
         </div>
         <div className="toolbar-right">
@@ -200,14 +265,28 @@ function App() {
             </div>
 
 
-            {connected ? (
+            {openPorts.length > 0 && (
+              <div className="open-ports">
+                {openPorts.map((port) => (
+                  <span key={port} className="open-port">
+                    {port}
+                    <button className="icon-button" title={`Close ${port}`} onClick={() => closePort(port)}>
+                      ×
+                    </button>
+                  </span>
+                ))}
+              </div>
+            )}
+
+            {(!connected || canConnect) && (
+                <button onClick={connect} disabled={!canConnect} className="primary">
+                  {connected ? "Add receiver" : "Connect"}
+                </button>
+            )}
+            {connected && (
                 <button onClick={disconnect} className="ghost">
                   Disconnect
                 </button>
-            ) : (
-                <button onClick={connect} disabled={!selectedPort} className="primary">
-                  Connect
-                </button>
             )}
           </div>
         </div>
@@ -223,6 +302,7 @@ function App() {
       )}
 
       {activeTab === "config" && <ConfigTab connected={connected} />}
+      {activeTab === "console" && <ConsoleTab connected={connected} />}
     </main>
   );
 }
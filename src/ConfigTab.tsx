@@ -1,6 +1,9 @@
 import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import type { ApplyReport, BackupDiff, ConfigError, ConfigProfile, ConfigSchema, ConfigValue, DeviceDescriptor, FirmwareUploadProgress, FirmwareUploadReport, ImageSlot, ProbeResult, ProvisioningStatus, RebootMethod, RebootProgress, RebootReport, SavedBackup, SchemaField } from "./types";
+import { configErrorText } from "./utils";
 
 interface ConfigTabProps {
   connected: boolean;
@@ -33,101 +36,31 @@ type FieldSpec = {
   transform?: (value: string) => string;
 };
 
-const KNOWN_FIELDS: Record<string, FieldSpec> = {
-  freq: {
-    label: "Frequency (MHz)",
-    hint: "410 - 450 or 902 - 928 MHz",
-    deviceType: "tracker",
-    inputType: "number",
-    step: 0.000001,
-    min: 410,
-    max: 928,
-    placeholder: "e.g. 433.920000 or 903.123456",
-  },
-  node_id: {
-    label: "Node ID",
-    hint: "0 - 9",
-    deviceType: "tracker",
-    inputType: "number",
-    step: 1,
-    min: 0,
-    max: 9,
-    placeholder: "0 - 9",
-  },
-  callsign: {
-    label: "Callsign",
-    hint: "Licensed operators only",
-    deviceType: "tracker",
-    inputType: "text",
-    maxLength: 12,
-    placeholder: "e.g. KD2YIE",
-    transform: (value) => value.toUpperCase(),
-  },
-  mode: {
-    label: "Deploy Mode",
-    hint: "dual_deploy | drogue_only | main_only",
-    deviceType: "marshal",
-    inputType: "select",
-    options: [
-      { label: "Dual Deploy", value: "dual_deploy" },
-      { label: "Drogue Only", value: "drogue_only" },
-      { label: "Main Only", value: "main_only" },
-    ],
-  },
-  main_alt: {
-    label: "Main Deploy Altitude (ft)",
-    hint: "0 - 30000 ft AGL",
-    deviceType: "marshal",
-    inputType: "number",
-    min: 0,
-    max: 30000,
-    step: 1,
-    placeholder: "e.g. 500",
-  },
-  arm_alt: {
-    label: "Arming Altitude (ft)",
-    hint: "0 - 3000 ft AGL",
-    deviceType: "marshal",
-    inputType: "number",
-    min: 0,
-    max: 3000,
-    step: 1,
-    placeholder: "e.g. 100",
-  },
-  apogee_delay: {
-    label: "Apogee Delay (ms)",
-    hint: "0 - 30000 ms",
-    deviceType: "marshal",
-    inputType: "number",
-    min: 0,
-    max: 30000,
-    step: 1,
-    placeholder: "e.g. 0",
-  },
-  bat_min: {
-    label: "Min Battery (mV)",
-    hint: "0 - 10000 mV",
-    deviceType: "marshal",
-    inputType: "number",
-    min: 0,
-    max: 10000,
-    step: 1,
-    placeholder: "e.g. 3300",
-  },
-};
-
-const IGNORED_CONFIG_KEYS = new Set(["config", "subcommands", "uart"]);
-const MARSHAL_KEYS = new Set(["mode", "main_alt", "arm_alt", "apogee_delay", "bat_min"]);
+// Converts a field from the backend's config schema into how it's rendered
+function specFromSchema(field: SchemaField): FieldSpec {
+  const base = { label: field.label, hint: field.hint, deviceType: field.deviceType, placeholder: field.placeholder || undefined };
+  switch (field.kind.type) {
+    case "number":
+      return {
+        ...base,
+        inputType: "number",
+        step: 10 ** -field.kind.decimals,
+        min: field.kind.ranges[0]?.[0],
+        max: field.kind.ranges[field.kind.ranges.length - 1]?.[1],
+      };
+    case "integer":
+      return { ...base, inputType: "number", step: 1, min: field.kind.min, max: field.kind.max };
+    case "callsign":
+      return { ...base, inputType: "text", maxLength: field.kind.maxLength, transform: (value) => value.toUpperCase() };
+    case "choice":
+      return { ...base, inputType: "select", options: field.kind.options };
+  }
+}
 
 type LogKind = "sent" | "recv" | "error" | "info";
 type LogEntry = { id: number; time: string; text: string; kind: LogKind };
 let _logId = 0;
 
-function stripAnsi(str: string): string {
-  // Strip ANSI/VT100 escape sequences (color codes, cursor movement, etc.)
-  return str.replace(/\x1b\[[0-9;]*[A-Za-z]/g, "");
-}
-
 function humanizeKey(key: string): string {
   return key
     .split("_")
@@ -136,9 +69,9 @@ function humanizeKey(key: string): string {
     .join(" ");
 }
 
-function getFieldSpec(key: string): FieldSpec {
+function getFieldSpec(knownFields: Record<string, FieldSpec>, key: string): FieldSpec {
   return (
-    KNOWN_FIELDS[key] ?? {
+    knownFields[key] ?? {
       label: humanizeKey(key),
       hint: `Device-specific setting: ${key}`,
       inputType: "text",
@@ -147,58 +80,6 @@ function getFieldSpec(key: string): FieldSpec {
   );
 }
 
-function extractConfigKeys(text: string): string[] {
-  const keys = new Set<string>();
-  const lowerText = text.toLowerCase();
-
-  for (const rawLine of lowerText.split("\n")) {
-    const line = rawLine.trim();
-    if (!line) continue;
-
-    const patterns = [
-      /^supported\s+([a-z][a-z0-9_]*)\b/,
-      /^([a-z][a-z0-9_]*)$/,
-      /^([a-z][a-z0-9_]*)\s*[:=-]/,
-      /^config\s+([a-z][a-z0-9_]*)\b/,
-      /^[*-]\s*([a-z][a-z0-9_]*)\b/,
-    ];
-
-    for (const pattern of patterns) {
-      const match = line.match(pattern);
-      const key = match?.[1];
-      if (key && !IGNORED_CONFIG_KEYS.has(key)) {
-        keys.add(key);
-      }
-    }
-  }
-
-  for (const key of Object.keys(KNOWN_FIELDS)) {
-    if (lowerText.includes(key)) {
-      keys.add(key);
-    }
-  }
-
-  return Array.from(keys).sort((a, b) => {
-    const aKnown = Number(!(a in KNOWN_FIELDS));
-    const bKnown = Number(!(b in KNOWN_FIELDS));
-    if (aKnown !== bKnown) return aKnown - bKnown;
-    return a.localeCompare(b);
-  });
-}
-
-function detectDeviceType(lines: string[], keys: string[]): DeviceType {
-  const identity = lines.find((line) => line.startsWith("identity "));
-  if (identity === "identity marshal") {
-    return "marshal";
-  }
-
-  if (keys.some((key) => MARSHAL_KEYS.has(key))) {
-    return "marshal";
-  }
-
-  return "tracker";
-}
-
 export function ConfigTab({ connected }: ConfigTabProps) {
   const [probing, setProbing] = useState(false);
   const [probed, setProbed] = useState(false);
@@ -206,7 +87,17 @@ export function ConfigTab({ connected }: ConfigTabProps) {
   const [availableKeys, setAvailableKeys] = useState<string[]>([]);
   const [deviceType, setDeviceType] = useState<DeviceType>("tracker");
   const [sending, setSending] = useState(false);
+  const [profiles, setProfiles] = useState<ConfigProfile[]>([]);
+  const [selectedProfile, setSelectedProfile] = useState("");
   const [configValues, setConfigValues] = useState<Record<string, string>>({});
+  const [verifyAfterReboot, setVerifyAfterReboot] = useState(false);
+  const [knownFields, setKnownFields] = useState<Record<string, FieldSpec>>({});
+  const [provisioning, setProvisioning] = useState<ProvisioningStatus | null>(null);
+  const provisioningRef = useRef<ProvisioningStatus | null>(null);
+  const [rebootMethod, setRebootMethod] = useState<RebootMethod>("shell");
+  const [rebooting, setRebooting] = useState(false);
+  const [images, setImages] = useState<ImageSlot[]>([]);
+  const [upload, setUpload] = useState<FirmwareUploadProgress | null>(null);
 
   const [log, setLog] = useState<LogEntry[]>([]);
   const logRef = useRef<HTMLDivElement>(null);
@@ -223,6 +114,18 @@ export function ConfigTab({ connected }: ConfigTabProps) {
     }
   }, [log]);
 
+  useEffect(() => {
+    // Field limits come from the backend, which enforces the same schema on every write
+    Promise.all(
+      (["tracker", "marshal"] as DeviceType[]).map((deviceType) => invoke<ConfigSchema>("config_schema", { deviceType })),
+    )
+      .then((schemas) => {
+        const fields = schemas.flatMap((schema) => schema.fields);
+        setKnownFields(Object.fromEntries(fields.map((field) => [field.key, specFromSchema(field)])));
+      })
+      .catch(console.error);
+  }, []);
+
   function probe() {
     if (!connected || probing) return;
     abortRef.current = false;
@@ -232,53 +135,50 @@ export function ConfigTab({ connected }: ConfigTabProps) {
     setAvailableKeys([]);
     addLog("info", "Probing device for available configurations…");
 
-    const accumulated: string[] = [];
-    let unlistenFn: (() => void) | null = null;
-
-    listen<string>("serial-line", (event) => {
-      accumulated.push(stripAnsi(event.payload));
-    }).then((fn) => {
-      unlistenFn = fn;
-      invoke("write_serial", { data: "config" }).catch(console.error);
-
-      setTimeout(() => {
-        if (unlistenFn) unlistenFn();
+    // Identify first so the probe and every later write use the right device type and schema
+    invoke<DeviceDescriptor>("identify_device", { refresh: true })
+      .then((descriptor) => {
         if (abortRef.current) return;
-
-        const text = accumulated.join("\n");
-        const lines = text
-          .toLowerCase()
-          .split("\n")
-          .map((line) => line.trim())
-          .filter(Boolean);
-        const keys = extractConfigKeys(text);
-        const found = Object.fromEntries(keys.map((key) => [key, true]));
-
-        setAvailable(found);
+        const details = [
+          descriptor.firmware && `firmware ${descriptor.firmware}`,
+          descriptor.buildHash && `build ${descriptor.buildHash}`,
+          descriptor.hardwareRevision && `hardware ${descriptor.hardwareRevision}`,
+          descriptor.serialNumber && `serial ${descriptor.serialNumber}`,
+        ].filter(Boolean);
+        addLog("recv", `${descriptor.identity ?? descriptor.deviceType}${details.length > 0 ? ` (${details.join(", ")})` : ""}`);
+      })
+      .catch((err: ConfigError) => {
+        if (!abortRef.current) addLog("error", `Could not identify device: ${configErrorText(err)}`);
+      })
+      .then(() => invoke<ProbeResult>("config_probe"))
+      .then((result) => {
+        if (abortRef.current) return;
+        const keys = result.keys;
+        setAvailable(Object.fromEntries(keys.map((key) => [key, true])));
         setAvailableKeys(keys);
-        setDeviceType(detectDeviceType(lines, keys));
-        setProbing(false);
-        setProbed(true);
+        setDeviceType(result.deviceType);
         setConfigValues((prev) => {
           const next: Record<string, string> = {};
           for (const key of keys) {
-            next[key] = prev[key] ?? "";
+            next[key] = prev[key] ?? result.values[key] ?? "";
           }
           return next;
         });
 
-        const identity = lines.find((line) => line.startsWith("identity "));
-        if (identity) {
-          addLog("recv", identity);
-        }
-
         if (keys.length > 0) {
           addLog("recv", `Available: ${keys.join(", ")}`);
         } else {
           addLog("info", "No configurable fields reported by device.");
         }
-      }, 2000);
-    });
+      })
+      .catch((err: ConfigError) => {
+        if (!abortRef.current) addLog("error", configErrorText(err));
+      })
+      .finally(() => {
+        if (abortRef.current) return;
+        setProbing(false);
+        setProbed(true);
+      });
   }
 
   useEffect(() => {
@@ -304,36 +204,292 @@ export function ConfigTab({ connected }: ConfigTabProps) {
     if (fields.length === 0) return;
     setSending(true);
 
+    const written: Record<string, string> = {};
     for (const field of fields) {
-      const cmd = `config ${field.key} ${field.value}`;
-      addLog("sent", cmd);
+      addLog("sent", `config ${field.key} ${field.value}`);
+      try {
+        // Each write is read straight back so a silently dropped one is caught
+        const result = await invoke<ConfigValue>("config_set", { key: field.key, value: field.value, verify: true });
+        addLog("recv", result.reply.length > 0 ? result.reply.join(" / ") : "OK");
+        written[field.key] = field.value;
+      } catch (err) {
+        addLog("error", configErrorText(err as ConfigError));
+      }
+    }
 
-      const lines: string[] = [];
-      const unlisten = await listen<string>("serial-line", (e) => {
-        lines.push(stripAnsi(e.payload));
-      });
+    if (verifyAfterReboot && Object.keys(written).length > 0) {
+      addLog("info", "Rebooting device to check the settings were saved…");
+      try {
+        const report = await invoke<RebootReport>("reboot_device", { method: rebootMethod, expected: written });
+        addLog("recv", `Verified after reboot: ${(report.checks ?? []).map((c) => `${c.key} = ${c.actual}`).join(", ")}`);
+      } catch (err) {
+        addLog("error", configErrorText(err as ConfigError));
+      }
+    }
+
+    setSending(false);
+  }
+
+  function refreshProfiles() {
+    invoke<ConfigProfile[]>("list_profiles").then(setProfiles).catch(console.error);
+  }
+
+  useEffect(refreshProfiles, []);
+
+  useEffect(() => {
+    let unlisten: UnlistenFn | null = null;
+    invoke<ProvisioningStatus | null>("get_provisioning_status")
+      .then((status) => {
+        provisioningRef.current = status;
+        setProvisioning(status);
+      })
+      .catch(console.error);
+    (async () => {
+      try {
+        unlisten = await listen<ProvisioningStatus>("provisioning", (event) => {
+          const status = event.payload;
+          const prev = provisioningRef.current;
+          // Log each device once, as it is recorded
+          const done = status.devices[status.devices.length - 1];
+          if (done && status.devices.length > (prev?.devices.length ?? 0)) {
+            const serial = done.serialNumber ? ` (${done.serialNumber})` : "";
+            addLog(done.ok ? "info" : "error", done.ok ? `Node ${done.nodeId} provisioned on ${done.port}${serial}` : `Node ${done.nodeId} failed on ${done.port}${serial}: ${done.error}`);
+          }
+          if (status.manifestPath && !prev?.manifestPath) addLog("info", `Provisioning manifest saved to ${status.manifestPath}`);
+          provisioningRef.current = status;
+          setProvisioning(status);
+        });
+      } catch (e) {
+        console.warn("Could not attach provisioning listener", e);
+      }
+    })();
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  const provisioningActive = provisioning !== null && provisioning.state !== "finished" && provisioning.state !== "cancelled";
+
+  // Configures a batch of trackers with the selected profile, one node ID each, as they are plugged in
+  function startProvisioning() {
+    if (!selectedProfile) return;
+    const range = window.prompt("Node IDs to hand out, e.g. 0-9", "0-9")?.trim();
+    const match = range?.match(/^(\d+)\s*(?:-\s*(\d+))?$/);
+    if (!match) return;
+    const firstNodeId = Number(match[1]);
+    const lastNodeId = Number(match[2] ?? match[1]);
+    invoke<ProvisioningStatus>("start_provisioning", { profile: selectedProfile, firstNodeId, lastNodeId, reboot: verifyAfterReboot })
+      .then((status) => {
+        addLog("info", `Provisioning with profile ${status.profile}, node IDs ${firstNodeId} - ${lastNodeId}`);
+        provisioningRef.current = status;
+        setProvisioning(status);
+      })
+      .catch((e) => addLog("error", `Provisioning failed to start: ${e}`));
+  }
+
+  function cancelProvisioning() {
+    invoke("cancel_provisioning").catch((e) => addLog("error", `Cancel failed: ${e}`));
+  }
+
+  useEffect(() => {
+    let unlisten: UnlistenFn | null = null;
+    (async () => {
+      try {
+        unlisten = await listen<RebootProgress>("device-reboot", (event) => {
+          if (event.payload.stage !== "failed") addLog("info", event.payload.message);
+        });
+      } catch (e) {
+        console.warn("Could not attach reboot listener", e);
+      }
+    })();
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Reboots the device and re-probes it once it's back, so the fields show what it booted with
+  async function rebootDevice() {
+    setRebooting(true);
+    try {
+      const report = await invoke<RebootReport>("reboot_device", { method: rebootMethod });
+      addLog("recv", `Back after ${(report.elapsedMs / 1000).toFixed(1)} s on ${report.reopenedPort}`);
+      probe();
+    } catch (err) {
+      addLog("error", `Reboot failed: ${configErrorText(err as ConfigError)}`);
+    } finally {
+      setRebooting(false);
+    }
+  }
 
+  useEffect(() => {
+    let unlisten: UnlistenFn | null = null;
+    (async () => {
       try {
-        await invoke("write_serial", { data: cmd });
-        await new Promise((res) => setTimeout(res, 1000));
-        const response = [...lines]
-          .reverse()
-          .find((l) => l.trim() && !l.trim().startsWith("["));
-        addLog("recv", response?.trim() ?? "OK");
+        unlisten = await listen<FirmwareUploadProgress>("firmware-upload", (event) => setUpload(event.payload));
       } catch (e) {
-        addLog("error", String(e));
-      } finally {
-        unlisten();
+        console.warn("Could not attach firmware upload listener", e);
       }
+    })();
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  function listImages() {
+    invoke<ImageSlot[]>("smp_image_list")
+      .then(setImages)
+      .catch((e) => addLog("error", `Image list failed: ${e}`));
+  }
+
+  // Uploads a signed MCUboot image to the spare slot; it only runs once tested and the device reset
+  async function uploadFirmware() {
+    const path = await open({ multiple: false, filters: [{ name: "Signed image", extensions: ["bin"] }] });
+    if (typeof path !== "string") return;
+    addLog("sent", `Uploading ${path}`);
+    setUpload({ port: "", sent: 0, total: 1 });
+    try {
+      const report = await invoke<FirmwareUploadReport>("smp_image_upload", { path });
+      addLog("recv", `Uploaded ${report.version} (${report.size} bytes) in ${(report.elapsedMs / 1000).toFixed(1)} s; slot ${report.slot.slot} hash verified`);
+      listImages();
+    } catch (e) {
+      addLog("error", `Upload failed: ${e}`);
+    } finally {
+      setUpload(null);
     }
+  }
+
+  function testImage(slot: ImageSlot) {
+    invoke<ImageSlot[]>("smp_image_test", { hash: slot.hash })
+      .then((next) => {
+        setImages(next);
+        addLog("info", `Image ${slot.version} will boot on the next reset; confirm it once it's running or it is rolled back`);
+      })
+      .catch((e) => addLog("error", `Test failed: ${e}`));
+  }
+
+  function confirmImage() {
+    invoke<ImageSlot[]>("smp_image_confirm")
+      .then((next) => {
+        setImages(next);
+        addLog("info", "Running image confirmed");
+      })
+      .catch((e) => addLog("error", `Confirm failed: ${e}`));
+  }
+
+  function resetDevice() {
+    invoke("smp_reset")
+      .then(() => addLog("info", "Device is resetting"))
+      .catch((e) => addLog("error", `Reset failed: ${e}`));
+  }
 
+  // Saves the values currently entered as a profile, replacing the selected one if the name matches
+  function saveAsProfile() {
+    const values = Object.fromEntries(availableKeys.filter((key) => configValues[key]).map((key) => [key, configValues[key]]));
+    const name = window.prompt("Profile name", selectedProfile)?.trim();
+    if (!name) return;
+    const profile: ConfigProfile = { name, deviceType, values };
+    invoke<ConfigProfile>("save_profile", { profile, originalName: name === selectedProfile ? selectedProfile : null })
+      .then((saved) => {
+        addLog("info", `Saved profile ${saved.name}`);
+        setSelectedProfile(saved.name);
+        refreshProfiles();
+      })
+      .catch((err) => addLog("error", String(err)));
+  }
+
+  function deleteProfile() {
+    if (!selectedProfile || !window.confirm(`Delete profile ${selectedProfile}?`)) return;
+    invoke("delete_profile", { name: selectedProfile })
+      .then(() => {
+        setSelectedProfile("");
+        refreshProfiles();
+      })
+      .catch((err) => addLog("error", String(err)));
+  }
+
+  function importProfile() {
+    open({ multiple: false, filters: [{ name: "Profile", extensions: ["toml", "json"] }] })
+      .then((path) => {
+        if (!path) return;
+        return invoke<ConfigProfile>("import_profile", { path }).then((profile) => {
+          addLog("info", `Imported profile ${profile.name}`);
+          setSelectedProfile(profile.name);
+          refreshProfiles();
+        });
+      })
+      .catch((err) => addLog("error", String(err)));
+  }
+
+  function exportProfile() {
+    if (!selectedProfile) return;
+    save({ defaultPath: `${selectedProfile}.toml`, filters: [{ name: "TOML", extensions: ["toml"] }, { name: "JSON", extensions: ["json"] }] })
+      .then((path) => path && invoke<string>("export_profile", { name: selectedProfile, path }))
+      .then((path) => path && addLog("info", `Exported profile to ${path}`))
+      .catch((err) => addLog("error", String(err)));
+  }
+
+  async function applyProfile() {
+    if (!selectedProfile) return;
+    setSending(true);
+    addLog("sent", `Applying profile ${selectedProfile}`);
+    try {
+      const report = await invoke<ApplyReport>("apply_profile", { name: selectedProfile, reboot: verifyAfterReboot });
+      for (const result of report.results) {
+        const check = result.checkAfterReboot ?? result.check;
+        if (result.ok) {
+          addLog("recv", `${result.key} = ${check?.actual ?? result.value}`);
+        } else {
+          addLog("error", `${result.key}: ${result.error ?? `expected ${result.value}, device has ${check?.actual ?? "nothing"}`}`);
+        }
+      }
+      addLog(report.ok ? "info" : "error", report.ok ? `Profile ${report.profile} applied and verified` : `Profile ${report.profile} was not fully applied`);
+    } catch (err) {
+      addLog("error", configErrorText(err as ConfigError));
+    }
+    setSending(false);
+  }
+
+  async function backupDevice() {
+    addLog("info", "Reading every setting for a backup…");
+    try {
+      const saved = await invoke<SavedBackup>("backup_device");
+      const unreadable = saved.backup.unreadable.length > 0 ? ` (could not read ${saved.backup.unreadable.join(", ")})` : "";
+      addLog("info", `Backed up ${Object.keys(saved.backup.values).length} settings to ${saved.path}${unreadable}`);
+    } catch (err) {
+      addLog("error", configErrorText(err as ConfigError));
+    }
+  }
+
+  // Shows what a backup would change and writes it back once confirmed
+  async function restoreBackup() {
+    const path = await open({ multiple: false, filters: [{ name: "Config backup", extensions: ["json"] }] });
+    if (!path) return;
+    setSending(true);
+    try {
+      const diff = await invoke<BackupDiff>("diff_backup", { path });
+      const changed = diff.entries.filter((entry) => entry.status === "changed");
+      for (const entry of diff.entries.filter((entry) => entry.status !== "same")) {
+        addLog("info", `${entry.key}: device ${entry.device ?? "—"}, backup ${entry.backup ?? "—"} (${entry.status})`);
+      }
+      if (changed.length === 0) {
+        addLog("info", "The device already matches the backup.");
+      } else if (window.confirm(`Restore ${changed.map((entry) => `${entry.key} = ${entry.backup}`).join(", ")}?`)) {
+        const report = await invoke<ApplyReport>("restore_backup", { path, reboot: verifyAfterReboot });
+        for (const result of report.results) {
+          addLog(result.ok ? "recv" : "error", result.ok ? `${result.key} = ${result.value}` : `${result.key}: ${result.error ?? "not restored"}`);
+        }
+      }
+    } catch (err) {
+      addLog("error", configErrorText(err as ConfigError));
+    }
     setSending(false);
   }
 
   const trackerActive = connected && deviceType === "tracker";
   const marshalActive = connected && deviceType === "marshal";
-  const trackerKeys = availableKeys.filter((key) => !MARSHAL_KEYS.has(key));
-  const marshalKeys = availableKeys.filter((key) => MARSHAL_KEYS.has(key));
+  const isMarshalKey = (key: string) => knownFields[key]?.deviceType === "marshal";
+  const trackerKeys = availableKeys.filter((key) => !isMarshalKey(key));
+  const marshalKeys = availableKeys.filter(isMarshalKey);
   const canSend =
     !sending &&
     availableKeys.some((key) => available[key] && (configValues[key] ?? "") !== "");
@@ -364,7 +520,7 @@ export function ConfigTab({ connected }: ConfigTabProps) {
               <ConfigInput
                 key={key}
                 fieldKey={key}
-                spec={getFieldSpec(key)}
+                spec={getFieldSpec(knownFields, key)}
                 enabled={trackerActive && !!available[key]}
                 probed={probed}
                 value={configValues[key] ?? ""}
@@ -382,6 +538,9 @@ export function ConfigTab({ connected }: ConfigTabProps) {
             <button className="primary" disabled={!trackerActive || !canSend} onClick={sendAll}>
               {sending ? "Sending…" : "Send"}
             </button>
+            <label className="config-actions-note">
+              <input type="checkbox" checked={verifyAfterReboot} onChange={(e) => setVerifyAfterReboot(e.target.checked)} /> Verify after reboot
+            </label>
             <span className="config-actions-note">Settings are saved to device flash and apply after reboot.</span>
           </div>
         </div>
@@ -409,7 +568,7 @@ export function ConfigTab({ connected }: ConfigTabProps) {
               <ConfigInput
                 key={key}
                 fieldKey={key}
-                spec={getFieldSpec(key)}
+                spec={getFieldSpec(knownFields, key)}
                 enabled={marshalActive && !!available[key]}
                 probed={probed}
                 value={configValues[key] ?? ""}
@@ -427,9 +586,128 @@ export function ConfigTab({ connected }: ConfigTabProps) {
             <button className="primary" disabled={!marshalActive || !canSend} onClick={sendAll}>
               {sending ? "Sending…" : "Send"}
             </button>
+            <label className="config-actions-note">
+              <input type="checkbox" checked={verifyAfterReboot} onChange={(e) => setVerifyAfterReboot(e.target.checked)} /> Verify after reboot
+            </label>
             <span className="config-actions-note">Settings are saved to device flash and apply after reboot.</span>
           </div>
         </div>
+
+        <div className="card config-profiles-card">
+          <div className="card-header">
+            <span>Profiles</span>
+            <div className="header-actions">
+              <select value={selectedProfile} onChange={(e) => setSelectedProfile(e.target.value)}>
+                <option value="">-- select --</option>
+                {profiles.map((profile) => (
+                  <option key={profile.name} value={profile.name}>
+                    {profile.name}
+                    {profile.deviceType ? ` (${profile.deviceType})` : ""}
+                  </option>
+                ))}
+              </select>
+              <button className="primary" disabled={!connected || !selectedProfile || sending} onClick={applyProfile}>
+                Apply
+              </button>
+              <button className="icon-button" disabled={!probed} onClick={saveAsProfile} title="Save the values entered above as a profile">
+                Save as…
+              </button>
+              <button className="icon-button" onClick={importProfile}>Import</button>
+              <button className="icon-button" disabled={!selectedProfile} onClick={exportProfile}>Export</button>
+              <button className="icon-button button-danger" disabled={!selectedProfile} onClick={deleteProfile}>Delete</button>
+              <button className="icon-button" disabled={!connected || sending} onClick={backupDevice} title="Save every setting of the device to a file">
+                Backup
+              </button>
+              <button className="icon-button" disabled={!connected || sending} onClick={restoreBackup} title="Write a backup to this device">
+                Restore…
+              </button>
+              <select value={rebootMethod} onChange={(e) => setRebootMethod(e.target.value as RebootMethod)} title="How to reboot the device">
+                <option value="shell">Shell reboot</option>
+                <option value="dtrRts">Reset line (RTS)</option>
+              </select>
+              <button className="icon-button" disabled={!connected || sending || rebooting} onClick={rebootDevice} title="Reboot the device and reconnect when it's back">
+                {rebooting ? "Rebooting…" : "Reboot"}
+              </button>
+              {provisioningActive ? (
+                <button className="icon-button button-danger" onClick={cancelProvisioning}>
+                  Stop provisioning
+                </button>
+              ) : (
+                <button className="icon-button" disabled={!selectedProfile || sending} onClick={startProvisioning} title="Configure trackers one after another as they are plugged in">
+                  Provision…
+                </button>
+              )}
+            </div>
+          </div>
+          {provisioning && (
+            <div className="config-provisioning">
+              <span className={`config-provisioning-state ${provisioning.state}`}>{provisioning.message}</span>
+              <span className="config-provisioning-count">
+                {provisioning.devices.filter((d) => d.ok).length} of {provisioning.lastNodeId - provisioning.firstNodeId + 1} done
+              </span>
+            </div>
+          )}
+        </div>
+
+        <div className="card config-firmware-card">
+          <div className="card-header">
+            <span>Firmware</span>
+            <div className="header-actions">
+              <button className="icon-button" disabled={!connected || upload !== null} onClick={listImages}>
+                List images
+              </button>
+              <button className="primary" disabled={!connected || upload !== null} onClick={uploadFirmware}>
+                {upload ? "Uploading…" : "Upload…"}
+              </button>
+              <button className="icon-button" disabled={!connected || upload !== null || !images.some((slot) => slot.active && !slot.confirmed)} onClick={confirmImage}>
+                Confirm
+              </button>
+              <button className="icon-button button-danger" disabled={!connected || upload !== null} onClick={resetDevice}>
+                Reset
+              </button>
+            </div>
+          </div>
+          {upload && (
+            <div className="firmware-progress">
+              <progress value={upload.sent} max={upload.total} />
+              <span>{Math.floor((upload.sent * 100) / upload.total)}%</span>
+            </div>
+          )}
+          {images.length > 0 && (
+            <table className="firmware-images">
+              <thead>
+                <tr>
+                  <th>Slot</th>
+                  <th>Version</th>
+                  <th>Hash</th>
+                  <th>State</th>
+                  <th></th>
+                </tr>
+              </thead>
+              <tbody>
+                {images.map((slot) => (
+                  <tr key={`${slot.image}-${slot.slot}`}>
+                    <td>{slot.image > 0 ? `${slot.image}/${slot.slot}` : slot.slot}</td>
+                    <td>{slot.version}</td>
+                    <td title={slot.hash}>{slot.hash.slice(0, 12)}…</td>
+                    <td>
+                      {[slot.active && "active", slot.confirmed && "confirmed", slot.pending && "pending", slot.permanent && "permanent", !slot.bootable && "not bootable"]
+                        .filter(Boolean)
+                        .join(", ")}
+                    </td>
+                    <td>
+                      {!slot.active && slot.bootable && !slot.pending && (
+                        <button className="icon-button" disabled={!connected} onClick={() => testImage(slot)}>
+                          Test
+                        </button>
+                      )}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+        </div>
       </div>
 
       <div className="card config-log-card">
@@ -0,0 +1,196 @@
+import { KeyboardEvent, useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import type { ConsoleInfo, ConsoleOutput, ConsoleResponse } from "./types";
+
+interface ConsoleTabProps {
+  connected: boolean;
+}
+
+type ConsoleItem = { kind: "response"; response: ConsoleResponse } | { kind: "output" | "error"; text: string };
+type ConsoleEntry = ConsoleItem & { id: number };
+let _entryId = 0;
+
+function commonPrefix(values: string[]): string {
+  return values.reduce((prefix, value) => {
+    let i = 0;
+    while (i < prefix.length && prefix[i] === value[i]) i++;
+    return prefix.slice(0, i);
+  });
+}
+
+export function ConsoleTab({ connected }: ConsoleTabProps) {
+  const [info, setInfo] = useState<ConsoleInfo | null>(null);
+  const [history, setHistory] = useState<string[]>([]);
+  // Position while stepping back through history; history.length is the line being typed
+  const [historyIndex, setHistoryIndex] = useState(0);
+  const [input, setInput] = useState("");
+  const [candidates, setCandidates] = useState<string[]>([]);
+  const [busy, setBusy] = useState(false);
+  const [entries, setEntries] = useState<ConsoleEntry[]>([]);
+  const outputRef = useRef<HTMLDivElement>(null);
+  const inputRef = useRef<HTMLInputElement>(null);
+
+  function addEntry(item: ConsoleItem) {
+    setEntries((prev) => [...prev, { ...item, id: _entryId++ }]);
+  }
+
+  useEffect(() => {
+    if (outputRef.current) {
+      outputRef.current.scrollTop = outputRef.current.scrollHeight;
+    }
+  }, [entries, candidates]);
+
+  useEffect(() => {
+    if (!connected) {
+      setInfo(null);
+      return;
+    }
+    invoke<ConsoleInfo>("console_open")
+      .then((opened) => {
+        setInfo(opened);
+        setHistory(opened.history);
+        setHistoryIndex(opened.history.length);
+        inputRef.current?.focus();
+      })
+      .catch((e) => addEntry({ kind: "error", text: `Could not open the console: ${e}` }));
+    return () => {
+      invoke("console_close").catch(console.error);
+    };
+  }, [connected]);
+
+  useEffect(() => {
+    let unlisten: UnlistenFn | null = null;
+    (async () => {
+      try {
+        unlisten = await listen<ConsoleOutput>("console-output", (event) => addEntry({ kind: "output", text: event.payload.text }));
+      } catch (e) {
+        console.warn("Could not attach console listener", e);
+      }
+    })();
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  async function send() {
+    if (!info || busy) return;
+    const command = input.trim();
+    setInput("");
+    setCandidates([]);
+    // Mirrors what the backend keeps: no repeats of the previous command
+    const next = command && history[history.length - 1] !== command ? [...history, command] : history;
+    setHistory(next);
+    setHistoryIndex(next.length);
+    setBusy(true);
+    try {
+      const response = await invoke<ConsoleResponse>("console_send", { command });
+      addEntry({ kind: "response", response });
+    } catch (e) {
+      addEntry({ kind: "error", text: `${command}: ${e}` });
+    } finally {
+      setBusy(false);
+      inputRef.current?.focus();
+    }
+  }
+
+  async function complete() {
+    if (!info || busy) return;
+    try {
+      const found = await invoke<string[]>("console_complete", { input });
+      if (found.length === 1) {
+        setInput(`${found[0]} `);
+        setCandidates([]);
+      } else if (found.length > 1) {
+        setInput(commonPrefix(found));
+        setCandidates(found);
+      }
+    } catch (e) {
+      addEntry({ kind: "error", text: `Completion failed: ${e}` });
+    }
+  }
+
+  function stepHistory(step: number) {
+    const index = Math.min(Math.max(historyIndex + step, 0), history.length);
+    setHistoryIndex(index);
+    setInput(history[index] ?? "");
+  }
+
+  function onKeyDown(e: KeyboardEvent<HTMLInputElement>) {
+    switch (e.key) {
+      case "Enter":
+        e.preventDefault();
+        send();
+        break;
+      case "Tab":
+        e.preventDefault();
+        complete();
+        break;
+      case "ArrowUp":
+        e.preventDefault();
+        stepHistory(-1);
+        break;
+      case "ArrowDown":
+        e.preventDefault();
+        stepHistory(1);
+        break;
+    }
+  }
+
+  const prompt = info?.prompt ?? "uart:~$";
+
+  return (
+    <section className="content console-layout">
+      <div className="card console-card">
+        <div className="card-header">
+          <span>{info ? `Console · ${info.port}` : "Console"}</span>
+          {entries.length > 0 && (
+            <button className="icon-button" onClick={() => setEntries([])} title="Clear console">
+              Clear
+            </button>
+          )}
+        </div>
+        <div className="console-screen" ref={outputRef} onClick={() => inputRef.current?.focus()}>
+          {!connected && <span className="config-log-empty">Connect to a device to use its shell.</span>}
+          {entries.map((entry) =>
+            entry.kind === "response" ? (
+              <div key={entry.id} className="console-response">
+                <div className="console-command">
+                  <span className="console-prompt">{prompt}</span> {entry.response.command}
+                </div>
+                {entry.response.lines.map((line, i) => (
+                  <div key={i} className="console-line">
+                    {line}
+                  </div>
+                ))}
+                {!entry.response.complete && <div className="console-note">No prompt after {entry.response.elapsedMs} ms; output may be incomplete</div>}
+              </div>
+            ) : (
+              <div key={entry.id} className={`console-line console-${entry.kind}`}>
+                {entry.text}
+              </div>
+            ),
+          )}
+          {candidates.length > 0 && <div className="console-candidates">{candidates.map((c) => c.split(" ").pop()).join("  ")}</div>}
+        </div>
+        <div className="console-input-row">
+          <span className="console-prompt">{prompt}</span>
+          <input
+            ref={inputRef}
+            className="console-input"
+            value={input}
+            disabled={!info}
+            spellCheck={false}
+            autoComplete="off"
+            onChange={(e) => {
+              setInput(e.target.value);
+              setCandidates([]);
+            }}
+            onKeyDown={onKeyDown}
+            placeholder={busy ? "Waiting for the device…" : "Type a command, Tab to complete"}
+          />
+        </div>
+      </div>
+    </section>
+  );
+}
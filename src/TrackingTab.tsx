@@ -1,21 +1,57 @@
 import { useCallback, useMemo, useState, type DragEvent } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { save } from "@tauri-apps/plugin-dialog";
-import { CircleMarker, MapContainer, Polyline, Popup, TileLayer, useMap } from "react-leaflet";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import { Circle, CircleMarker, MapContainer, Polygon, Polyline, Popup, TileLayer, Tooltip, useMap } from "react-leaflet";
 import { useEffect } from "react";
-import type { Tracker, TelemetryPacket } from "./types";
+import type { ImportReport, Overlay, SessionSummary, Tracker, TelemetryPacket } from "./types";
 import { colorForIndex } from "./utils";
 import "leaflet/dist/leaflet.css";
 
+const OVERLAY_COLOR = "#555555";
+
+// CSV export presets; the backend accepts any combination of these options
+const CSV_STYLES: Record<string, { label: string; options: Record<string, unknown> }> = {
+  standard: { label: "CSV: standard", options: {} },
+  spreadsheet: {
+    label: "CSV: semicolons, decimal commas",
+    options: { dialect: { delimiter: ";", decimalSeparator: ",", timeZone: "local", timeFormat: "spreadsheet" } },
+  },
+  metric: {
+    label: "CSV: metric with derived fields",
+    options: { dialect: { units: "metric" }, includeCallsign: true, includeDerived: true },
+  },
+  full: {
+    label: "CSV: everything, UTM",
+    options: { dialect: { coordinates: "utm" }, includeCallsign: true, includeDerived: true, includeRawLines: true },
+  },
+};
+
+function OverlayLayer({ overlay }: { overlay: Overlay }) {
+  const options = overlay.kind === "track"
+    ? { color: OVERLAY_COLOR, weight: 2, dashArray: "6 6" }
+    : { color: OVERLAY_COLOR, weight: 1, fillOpacity: 0.15 };
+  const label = <Tooltip>{overlay.name}</Tooltip>;
+  switch (overlay.shape.type) {
+    case "line":
+      return <Polyline positions={overlay.shape.points} pathOptions={options}>{label}</Polyline>;
+    case "polygon":
+      return <Polygon positions={overlay.shape.points} pathOptions={options}>{label}</Polygon>;
+    case "point":
+      return overlay.shape.radius_m
+        ? <Circle center={overlay.shape.position} radius={overlay.shape.radius_m} pathOptions={options}>{label}</Circle>
+        : <CircleMarker center={overlay.shape.position} radius={5} pathOptions={options}>{label}</CircleMarker>;
+  }
+}
+
 function ZoomToLatest({ trackers }: { trackers: Record<string, Tracker> }) {
   const map = useMap();
   useEffect(() => {
     const all = Object.values(trackers)
       .map((tracker) => tracker.latest)
-      .filter((packet): packet is TelemetryPacket => Boolean(packet?.lat !== undefined && packet.lon !== undefined));
+      .filter((packet): packet is TelemetryPacket => Boolean(packet?.lat !== undefined && packet.lon !== undefined && !packet.rejected));
     if (all.length === 0) return;
     const latest = all.reduce((a, b) => (a.ts > b.ts ? a : b));
-    map.setView([latest.lat!, latest.lon!], Math.max(map.getZoom(), 15));
+    map.setView([latest.filteredLat ?? latest.lat!, latest.filteredLon ?? latest.lon!], Math.max(map.getZoom(), 15));
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [JSON.stringify(Object.keys(trackers).map((k) => trackers[k].latest?.ts))]);
   return null;
@@ -33,6 +69,13 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
   const [hideAllTrackers, setHideAllTrackers] = useState(false);
   const [trackerOrder, setTrackerOrder] = useState<string[]>([]);
   const [draggingId, setDraggingId] = useState<string | null>(null);
+  const [overlays, setOverlays] = useState<Overlay[]>([]);
+  const [history, setHistory] = useState<SessionSummary[]>([]);
+  const [csvStyle, setCsvStyle] = useState("standard");
+
+  useEffect(() => {
+    invoke<Overlay[]>("list_overlays").then(setOverlays).catch(console.error);
+  }, []);
 
   // Update tracker order when new trackers appear
   useEffect(() => {
@@ -80,8 +123,16 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
     setDraggingId(null);
   }, []);
 
-  function savePacketsCsv() {
-    if (packets.length === 0) return;
+  function formatForPath(path: string) {
+    const lower = path.toLowerCase();
+    if (lower.endsWith(".kml")) return "kml";
+    if (lower.endsWith(".gpx")) return "gpx";
+    if (lower.endsWith(".geojson") || lower.endsWith(".json")) return "geojson";
+    return "csv";
+  }
+
+  // Demo mode never reaches the backend, so its packets can only be exported from the UI's list
+  function exportFromUi(path: string, waypointsOnly: boolean) {
     const payload = packets.map((packet) => ({
       node_id: packet.nodeId,
       lat: packet.lat,
@@ -92,21 +143,151 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
       fix_status: packet.fixStatus,
       sats: packet.sats,
       ts: packet.ts,
+      filtered_lat: packet.filteredLat,
+      filtered_lon: packet.filteredLon,
+      rejected: packet.rejected,
     }));
+    switch (formatForPath(path)) {
+      case "kml":
+        return invoke<string>("export_packets_kml", { packets: payload, path, includeTrack: true });
+      case "gpx":
+        return invoke<string>("export_packets_gpx", { packets: payload, path, waypointsOnly });
+      case "geojson":
+        return Promise.reject(new Error("GeoJSON export needs packets from a connected receiver"));
+      default:
+        return invoke<string>("export_packets_csv", { packets: payload, path });
+    }
+  }
+
+  function exportSession(path: string, options: Record<string, unknown>) {
+    return invoke<string>("export_session", { path, options: { format: formatForPath(path), ...options } }).catch((err) => {
+      if (packets.length > 0 && String(err).includes("No packets")) {
+        return exportFromUi(path, Boolean(options.waypointsOnly));
+      }
+      throw err;
+    });
+  }
+
+  // Exports read the backend's session store, which keeps every packet at full detail
+  function savePackets() {
     const defaultName = `packets-${new Date().toISOString().replace(/[:.]/g, "-")}.csv`;
-    save({ defaultPath: defaultName, filters: [{ name: "CSV", extensions: ["csv"] }] })
+    save({
+      defaultPath: defaultName,
+      filters: [
+        { name: "CSV", extensions: ["csv"] },
+        { name: "KML (Google Earth)", extensions: ["kml"] },
+        { name: "GPX", extensions: ["gpx"] },
+        { name: "GeoJSON", extensions: ["geojson"] },
+        { name: "Session archive (zip)", extensions: ["zip"] },
+      ],
+    })
       .then((path) => {
         if (!path) throw new Error("Save canceled");
-        return invoke<string>("export_packets_csv", { packets: payload, path });
+        if (path.toLowerCase().endsWith(".zip")) return invoke<string>("export_archive", { path });
+        return exportSession(path, { includeTrack: true, ...CSV_STYLES[csvStyle].options });
       })
       .then((path) => {
-        console.log("Saved packets CSV to", path);
+        console.log("Saved packets to", path);
       })
       .catch((err) => {
-        console.error("Failed to save CSV", err);
+        console.error("Failed to save packets", err);
       });
   }
 
+  function saveReport() {
+    const defaultName = `flight-report-${new Date().toISOString().replace(/[:.]/g, "-")}.html`;
+    save({
+      defaultPath: defaultName,
+      filters: [
+        { name: "HTML", extensions: ["html"] },
+        { name: "Markdown", extensions: ["md"] },
+      ],
+    })
+      .then((path) => {
+        if (!path) throw new Error("Save canceled");
+        const format = path.toLowerCase().endsWith(".md") ? "markdown" : "html";
+        return invoke<string>("export_report", { path, options: { format } });
+      })
+      .then((path) => {
+        console.log("Saved flight report to", path);
+      })
+      .catch((err) => {
+        console.error("Failed to save flight report", err);
+      });
+  }
+
+  function saveLastKnownGpx() {
+    const defaultName = `last-known-${new Date().toISOString().replace(/[:.]/g, "-")}.gpx`;
+    save({ defaultPath: defaultName, filters: [{ name: "GPX", extensions: ["gpx"] }] })
+      .then((path) => {
+        if (!path) throw new Error("Save canceled");
+        return exportSession(path, { waypointsOnly: true });
+      })
+      .then((path) => {
+        console.log("Saved last known positions to", path);
+      })
+      .catch((err) => {
+        console.error("Failed to save last known positions", err);
+      });
+  }
+
+  function refreshHistory() {
+    invoke<SessionSummary[]>("list_sessions").then(setHistory).catch(console.error);
+  }
+
+  function loadHistorySession(id: string) {
+    if (id === "import") {
+      importSessionFile();
+      return;
+    }
+    if (!id) return;
+    invoke<SessionSummary>("load_session", { id: Number(id) }).catch((err) => {
+      console.error("Failed to load session", err);
+    });
+  }
+
+  function importSessionFile() {
+    open({
+      multiple: false,
+      filters: [
+        { name: "Dispatch logs", extensions: ["zip", "csv", "jsonl", "log", "txt"] },
+        { name: "All files", extensions: ["*"] },
+      ],
+    })
+      .then((path) => {
+        if (!path) throw new Error("Import canceled");
+        return invoke<ImportReport>("import_session", { path });
+      })
+      .then((report) => {
+        console.log(`Imported ${report.packets} packets`, report);
+        if (report.failures.length > 0) {
+          console.warn(`${report.failures.length} rows failed validation`, report.failures);
+        }
+      })
+      .catch((err) => {
+        console.error("Failed to import session", err);
+      });
+  }
+
+  function importOverlay() {
+    open({ multiple: false, filters: [{ name: "GeoJSON", extensions: ["geojson", "json"] }] })
+      .then((path) => {
+        if (!path) throw new Error("Import canceled");
+        return invoke<Overlay[]>("import_overlays", { path });
+      })
+      .then(() => invoke<Overlay[]>("list_overlays"))
+      .then(setOverlays)
+      .catch((err) => {
+        console.error("Failed to import overlay", err);
+      });
+  }
+
+  function clearOverlays() {
+    invoke("clear_overlays")
+      .then(() => setOverlays([]))
+      .catch(console.error);
+  }
+
   function toggleTrackerHidden(nodeId: string) {
     setHiddenTrackers((prev) => {
       const next = new Set(prev);
@@ -129,6 +310,7 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
           <MapContainer center={[0, 0]} zoom={2} style={{ height: "100%", width: "100%" }}>
             <TileLayer url="https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png" attribution="© OpenStreetMap contributors" />
             <ZoomToLatest trackers={trackersMemo} />
+            {overlays.map((overlay) => <OverlayLayer key={overlay.id} overlay={overlay} />)}
             {Object.values(trackers).map((t) => {
               const color = trackerColors[t.nodeId] ?? colorForIndex(0);
               const latlons = t.points.map((point) => [point.lat, point.lon] as [number, number]);
@@ -136,8 +318,8 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
               const isHidden = hiddenTrackers.has(t.nodeId) || hideAllTrackers;
               const lastKnownPoint = t.points.length > 0 ? t.points[t.points.length - 1] : null;
               const markerPos =
-                latest?.lat !== undefined && latest?.lon !== undefined
-                  ? { lat: latest.lat, lon: latest.lon }
+                latest?.lat !== undefined && latest?.lon !== undefined && !latest.rejected
+                  ? { lat: latest.filteredLat ?? latest.lat, lon: latest.filteredLon ?? latest.lon }
                   : lastKnownPoint;
               return (
                 <div key={t.nodeId}>
@@ -173,7 +355,26 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
           <div className="card-header">
             <span>Latest packets</span>
             <div className="header-actions">
-              <button className="icon-button button-success" title="Save packets to CSV" onClick={savePacketsCsv}>
+              <select
+                title="Load a past session (disconnect first)"
+                value=""
+                onFocus={refreshHistory}
+                onChange={(e) => loadHistorySession(e.target.value)}
+              >
+                <option value="">History…</option>
+                <option value="import">Import file…</option>
+                {history.map((s) => (
+                  <option key={s.id} value={s.id}>
+                    {s.name ?? new Date(s.startedMs).toLocaleString()} ({s.packetCount} packets)
+                  </option>
+                ))}
+              </select>
+              <select title="How CSV exports are written" value={csvStyle} onChange={(e) => setCsvStyle(e.target.value)}>
+                {Object.entries(CSV_STYLES).map(([key, style]) => (
+                  <option key={key} value={key}>{style.label}</option>
+                ))}
+              </select>
+              <button className="icon-button button-success" title="Save packets to CSV, KML, GPX or GeoJSON" onClick={savePackets}>
                 <svg width="14" height="14" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg" style={{ marginRight: 6 }}>
                   <path d="M5 5h11l3 3v11H5V5Z" stroke="currentColor" strokeWidth="1.6" strokeLinecap="round" strokeLinejoin="round" />
                   <path d="M9 5v4h6V5" stroke="currentColor" strokeWidth="1.6" strokeLinecap="round" strokeLinejoin="round" />
@@ -181,6 +382,20 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
                 </svg>
                 Save
               </button>
+              <button className="icon-button button-success" title="Save last known positions as GPX waypoints" onClick={saveLastKnownGpx}>
+                <svg width="14" height="14" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg" style={{ marginRight: 6 }}>
+                  <path d="M12 21s-6-5.5-6-10a6 6 0 0 1 12 0c0 4.5-6 10-6 10Z" stroke="currentColor" strokeWidth="1.6" strokeLinecap="round" strokeLinejoin="round" />
+                  <circle cx="12" cy="11" r="2" stroke="currentColor" strokeWidth="1.6" />
+                </svg>
+                Last known
+              </button>
+              <button className="icon-button button-success" title="Save a flight summary report (HTML or Markdown)" onClick={saveReport}>
+                <svg width="14" height="14" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg" style={{ marginRight: 6 }}>
+                  <path d="M6 3h9l4 4v14H6V3Z" stroke="currentColor" strokeWidth="1.6" strokeLinecap="round" strokeLinejoin="round" />
+                  <path d="M9 12h7M9 16h7M9 8h3" stroke="currentColor" strokeWidth="1.6" strokeLinecap="round" strokeLinejoin="round" />
+                </svg>
+                Report
+              </button>
               <button className="icon-button button-danger" title="Clear latest packets" onClick={onClearPackets}>
                 <svg width="14" height="14" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg" style={{ marginRight: 6 }}>
                   <path d="M3 6h18" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round" />
@@ -232,6 +447,19 @@ export function TrackingTab({ trackers, packets, trackerColors, onClearPackets }
           <div className="card-header">
             <span>Trackers</span>
             <div className="header-actions">
+              {overlays.length > 0 && (
+                <button className="icon-button" title="Remove overlays" onClick={clearOverlays}>
+                  <svg width="16" height="16" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg">
+                    <path d="M6 6l12 12M18 6L6 18" stroke="currentColor" strokeWidth="1.2" strokeLinecap="round" strokeLinejoin="round" />
+                  </svg>
+                </button>
+              )}
+              <button className="icon-button" title="Import prior tracks or landing zones (GeoJSON)" onClick={importOverlay}>
+                <svg width="16" height="16" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg">
+                  <path d="M12 3 2 8l10 5 10-5-10-5Z" stroke="currentColor" strokeWidth="1.2" strokeLinecap="round" strokeLinejoin="round" />
+                  <path d="m2 13 10 5 10-5" stroke="currentColor" strokeWidth="1.2" strokeLinecap="round" strokeLinejoin="round" />
+                </svg>
+              </button>
               <button className="icon-button" title={hideAllTrackers ? "Show trackers" : "Hide trackers"} onClick={toggleHideAll}>
                 {hideAllTrackers ? (
                   <svg width="16" height="16" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg">
@@ -12,6 +12,9 @@ export type TelemetryPacket = {
   sats?: number;
   ts: number;
   raw?: string;
+  filteredLat?: number;
+  filteredLon?: number;
+  rejected?: string;
 };
 
 export type Tracker = {
@@ -24,3 +27,247 @@ export type SerialPortOption = {
   portName: string;
   label: string;
 };
+
+export type OverlayShape =
+  | { type: "line"; points: [number, number][] }
+  | { type: "polygon"; points: [number, number][] }
+  | { type: "point"; position: [number, number]; radius_m?: number | null };
+
+export type Overlay = {
+  id: string;
+  name: string;
+  kind: "track" | "landingZone";
+  shape: OverlayShape;
+};
+
+export type SessionSummary = {
+  id: number;
+  name?: string | null;
+  notes?: string | null;
+  startedMs: number;
+  endedMs?: number | null;
+  archived: boolean;
+  packetCount: number;
+  nodes: string[];
+  ports: string[];
+};
+
+export type ImportReport = {
+  format: "csv" | "jsonl" | "raw" | "archive";
+  packets: number;
+  failures: { line: number; reason: string }[];
+  skippedLines: number;
+  sessionId?: number | null;
+};
+
+export type ProbeResult = {
+  port: string;
+  identity?: string | null;
+  deviceType: "tracker" | "marshal";
+  keys: string[];
+  values: Record<string, string>;
+  reply: string[];
+};
+
+export type DeviceDescriptor = {
+  port: string;
+  identity?: string | null;
+  deviceType: "tracker" | "marshal";
+  firmware?: string | null;
+  buildHash?: string | null;
+  hardwareRevision?: string | null;
+  serialNumber?: string | null;
+  dialect: "deputy" | "console";
+  schema: ConfigSchema;
+  identifiedAt: string;
+};
+
+export type ConfigValue = {
+  key: string;
+  value?: string | null;
+  reply: string[];
+};
+
+export type ConfigError =
+  | { kind: "port"; message: string }
+  | { kind: "busy"; port: string }
+  | { kind: "timeout"; command: string; timeoutMs: number }
+  | { kind: "unknownKey"; key: string }
+  | { kind: "invalid"; key: string; message: string }
+  | { kind: "rejected"; command: string; message: string }
+  | { kind: "malformed"; command: string; reply: string[] }
+  | { kind: "wrongDevice"; expected: "tracker" | "marshal"; found: "tracker" | "marshal" }
+  | { kind: "mismatch"; checks: KeyCheck[] }
+  | { kind: "write"; message: string };
+
+export type SchemaField = {
+  key: string;
+  label: string;
+  hint: string;
+  deviceType: "tracker" | "marshal";
+  placeholder: string;
+  kind:
+    | { type: "number"; ranges: [number, number][]; decimals: number; unit: string }
+    | { type: "integer"; min: number; max: number; unit: string }
+    | { type: "callsign"; maxLength: number }
+    | { type: "choice"; options: { label: string; value: string }[] };
+};
+
+export type ConfigSchema = {
+  deviceType: "tracker" | "marshal";
+  firmware?: string | null;
+  fields: SchemaField[];
+};
+
+export type KeyCheck = {
+  key: string;
+  expected: string;
+  actual?: string | null;
+  matches: boolean;
+  error?: string | null;
+};
+
+export type VerifyReport = {
+  port: string;
+  reopenedPort?: string | null;
+  rebooted: boolean;
+  checks: KeyCheck[];
+};
+
+export type ConfigProfile = {
+  name: string;
+  description?: string | null;
+  deviceType?: "tracker" | "marshal" | null;
+  values: Record<string, string>;
+};
+
+export type ApplyReport = {
+  profile: string;
+  port: string;
+  reopenedPort?: string | null;
+  rebooted: boolean;
+  ok: boolean;
+  results: {
+    key: string;
+    value: string;
+    ok: boolean;
+    error?: string | null;
+    check?: KeyCheck | null;
+    checkAfterReboot?: KeyCheck | null;
+  }[];
+};
+
+export type ConfigBackup = {
+  format: string;
+  version: number;
+  createdAt: string;
+  port: string;
+  identity?: string | null;
+  deviceType: "tracker" | "marshal";
+  firmware?: string | null;
+  values: Record<string, string>;
+  unreadable: string[];
+};
+
+export type SavedBackup = {
+  path: string;
+  backup: ConfigBackup;
+};
+
+export type BackupDiff = {
+  backup: ConfigBackup;
+  device: ConfigBackup;
+  entries: {
+    key: string;
+    backup?: string | null;
+    device?: string | null;
+    status: "same" | "changed" | "missingOnDevice" | "deviceOnly";
+  }[];
+};
+
+export type ProvisionedDevice = {
+  serialNumber?: string | null;
+  port: string;
+  nodeId: number;
+  ok: boolean;
+  error?: string | null;
+  timestamp: string;
+  report?: ApplyReport | null;
+};
+
+export type ProvisioningStatus = {
+  profile: string;
+  firstNodeId: number;
+  lastNodeId: number;
+  nextNodeId?: number | null;
+  state: "waitingForDevice" | "configuring" | "unplug" | "finished" | "cancelled";
+  port?: string | null;
+  message: string;
+  devices: ProvisionedDevice[];
+  manifestPath?: string | null;
+};
+
+export type RebootMethod = "shell" | "dtrRts";
+
+export type RebootProgress = {
+  port: string;
+  stage: "rebooting" | "waitingForDevice" | "reopened" | "ready" | "verified" | "failed";
+  message: string;
+};
+
+export type RebootReport = {
+  port: string;
+  reopenedPort: string;
+  method: RebootMethod;
+  elapsedMs: number;
+  checks?: KeyCheck[] | null;
+};
+
+export type ImageSlot = {
+  image: number;
+  slot: number;
+  version: string;
+  hash: string;
+  bootable: boolean;
+  pending: boolean;
+  confirmed: boolean;
+  active: boolean;
+  permanent: boolean;
+};
+
+export type FirmwareUploadReport = {
+  port: string;
+  version: string;
+  hash: string;
+  size: number;
+  elapsedMs: number;
+  slot: ImageSlot;
+};
+
+export type FirmwareUploadProgress = {
+  port: string;
+  sent: number;
+  total: number;
+};
+
+export type ConsoleInfo = {
+  port: string;
+  device: string;
+  prompt?: string | null;
+  history: string[];
+};
+
+export type ConsoleResponse = {
+  id: number;
+  port: string;
+  command: string;
+  lines: string[];
+  prompt?: string | null;
+  complete: boolean;
+  elapsedMs: number;
+};
+
+export type ConsoleOutput = {
+  port: string;
+  text: string;
+};
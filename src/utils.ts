@@ -1,4 +1,4 @@
-import type { FixStatus } from "./types";
+import type { ConfigError, FixStatus, TelemetryPacket } from "./types";
 
 export function colorForIndex(idx: number) {
   const palette = [
@@ -26,3 +26,56 @@ export function fixFromString(s?: string): FixStatus | undefined {
   if (upper.includes("FIX")) return "FIX";
   return "UNKNOWN";
 }
+
+// Converts a backend DataPacket (snake_case) into the shape the UI works with.
+export function packetFromBackend(pktRaw: any): TelemetryPacket {
+  const callsign: string | undefined = pktRaw.callsign ?? undefined;
+  const rawNodeId: number | null = pktRaw.node_id ?? null;
+  const nodeId = rawNodeId !== null
+    ? callsign ? `${callsign}-${rawNodeId}` : `Node ${rawNodeId}`
+    : callsign ?? "unknown";
+  return {
+    nodeId,
+    callsign,
+    lat: pktRaw.latitude ?? undefined,
+    lon: pktRaw.longitude ?? undefined,
+    altitudeFt: pktRaw.altitude_ft ?? undefined,
+    rssi: pktRaw.receiver_rssi ?? undefined,
+    snr: pktRaw.receiver_snr ?? undefined,
+    fixStatus: fixFromString(pktRaw.fix_status),
+    sats: pktRaw.satellites_count ?? undefined,
+    ts: pktRaw.timestamp_ms ?? Date.now(),
+    raw: (pktRaw.raw_lines?.join("\n")) || undefined,
+    filteredLat: pktRaw.filtered?.latitude ?? undefined,
+    filteredLon: pktRaw.filtered?.longitude ?? undefined,
+    rejected: pktRaw.filtered?.rejected ?? undefined,
+  };
+}
+
+// Readable text for a config command's typed error, mirroring its Display impl in config.rs.
+export function configErrorText(err: ConfigError | string): string {
+  if (typeof err === "string") return err;
+  switch (err.kind) {
+    case "port":
+    case "write":
+    case "invalid":
+      return err.message;
+    case "busy":
+      return `${err.port} is busy with another config request`;
+    case "timeout":
+      return `No reply to "${err.command}" within ${err.timeoutMs} ms`;
+    case "unknownKey":
+      return `The device has no setting named ${err.key}`;
+    case "rejected":
+      return `The device rejected "${err.command}": ${err.message}`;
+    case "malformed":
+      return `Unexpected reply to "${err.command}": ${err.reply.join(" / ")}`;
+    case "wrongDevice":
+      return `This is for a ${err.expected} but the device is a ${err.found}`;
+    case "mismatch":
+      return `Settings differ after writing: ${err.checks
+        .filter((c) => !c.matches)
+        .map((c) => `${c.key} expected ${c.expected}, device has ${c.actual ?? "nothing"}`)
+        .join("; ")}`;
+  }
+}